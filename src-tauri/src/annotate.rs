@@ -0,0 +1,89 @@
+use serde::Deserialize;
+
+/// Annotations are drawn in this colour, `THICKNESS` pixels wide at the
+/// video's resolution.
+const COLOR: [u8; 4] = [255, 48, 48, 255];
+const THICKNESS: f64 = 4.0;
+/// Length of the arrow head's sides, relative to `THICKNESS`.
+const HEAD: f64 = 5.0;
+
+/// Something drawn on a paused frame, in video pixel coordinates.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Shape {
+    Rect { from: (f64, f64), to: (f64, f64) },
+    Arrow { from: (f64, f64), to: (f64, f64) },
+    Freehand { points: Vec<(f64, f64)> },
+}
+
+/// Draws `shapes` into `rgba`, tightly packed with `w * 4` bytes per row.
+pub fn draw(rgba: &mut [u8], w: u32, h: u32, shapes: &[Shape]) {
+    let mut canvas = Canvas { rgba, w, h };
+
+    for shape in shapes {
+        match shape {
+            &Shape::Rect { from, to } => {
+                let corners = [from, (to.0, from.1), to, (from.0, to.1), from];
+                canvas.polyline(&corners);
+            }
+            &Shape::Arrow { from, to } => {
+                canvas.line(from, to);
+                let angle = (from.1 - to.1).atan2(from.0 - to.0);
+                for side in [-0.5, 0.5] {
+                    let a = angle + side;
+                    let tip = (
+                        to.0 + a.cos() * THICKNESS * HEAD,
+                        to.1 + a.sin() * THICKNESS * HEAD,
+                    );
+                    canvas.line(to, tip);
+                }
+            }
+            Shape::Freehand { points } => canvas.polyline(points),
+        }
+    }
+}
+
+struct Canvas<'a> {
+    rgba: &'a mut [u8],
+    w: u32,
+    h: u32,
+}
+
+impl Canvas<'_> {
+    fn polyline(&mut self, points: &[(f64, f64)]) {
+        if let [only] = points {
+            self.dot(*only);
+        }
+        for pair in points.windows(2) {
+            self.line(pair[0], pair[1]);
+        }
+    }
+
+    /// Stamps dots along the line, close enough together to look solid.
+    fn line(&mut self, from: (f64, f64), to: (f64, f64)) {
+        let length = (to.0 - from.0).hypot(to.1 - from.1);
+        let steps = (length / (THICKNESS / 4.0)).ceil().max(1.0) as usize;
+        for i in 0..=steps {
+            let f = i as f64 / steps as f64;
+            self.dot((from.0 + (to.0 - from.0) * f, from.1 + (to.1 - from.1) * f));
+        }
+    }
+
+    fn dot(&mut self, (cx, cy): (f64, f64)) {
+        let r = THICKNESS / 2.0;
+        let x0 = (cx - r).floor().max(0.0) as u32;
+        let y0 = (cy - r).floor().max(0.0) as u32;
+        let x1 = ((cx + r).ceil().max(0.0) as u32).min(self.w);
+        let y1 = ((cy + r).ceil().max(0.0) as u32).min(self.h);
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let (dx, dy) = (x as f64 + 0.5 - cx, y as f64 + 0.5 - cy);
+                if dx * dx + dy * dy <= r * r {
+                    let i = (y as usize * self.w as usize + x as usize) * 4;
+                    self.rgba[i..i + 4].copy_from_slice(&COLOR);
+                }
+            }
+        }
+    }
+}
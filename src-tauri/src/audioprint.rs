@@ -0,0 +1,185 @@
+use ffmpeg_next::{
+    self as ffmpeg,
+    channel_layout::ChannelLayout,
+    format::{sample::Type, Sample},
+    frame::Audio,
+    media,
+};
+use std::{error::Error, f64::consts::PI, path::Path};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Audio is mixed down to mono at this rate before anything else, the same
+/// as chromaprint does.
+const RATE: u32 = 11025;
+/// FFT size and the step between frames, two thirds overlap.
+const FRAME: usize = 4096;
+const HOP: usize = FRAME / 3;
+/// Only these frequencies are folded into the chroma bands.
+const MIN_FREQ: f64 = 28.0;
+const MAX_FREQ: f64 = 3520.0;
+/// How much audio is fingerprinted by default, like `fpcalc -length`.
+const DEFAULT_LENGTH: f64 = 120.0;
+
+/// A chromaprint-style fingerprint: one 32 bit value per analysis frame,
+/// built from how the energy of the 12 pitch classes compares within and
+/// between frames.
+pub struct AudioPrint {
+    pub duration: f64,
+    pub values: Vec<u32>,
+}
+
+impl AudioPrint {
+    /// Fingerprints the first `length` seconds of the best audio stream.
+    pub fn of<P: AsRef<Path> + ?Sized>(path: &P, length: f64) -> BasicResult<Self> {
+        let mut ictx = ffmpeg::format::input(path)?;
+        let stream = ictx
+            .streams()
+            .best(media::Type::Audio)
+            .ok_or("no audio stream")?;
+        let index = stream.index();
+        let duration = ictx.duration().max(0) as f64 / ffmpeg::ffi::AV_TIME_BASE as f64;
+
+        let context_decoder =
+            ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+        let mut decoder = context_decoder.decoder().audio()?;
+        let mut resampler =
+            decoder.resampler(Sample::F32(Type::Packed), ChannelLayout::MONO, RATE)?;
+
+        let limit = (length * RATE as f64) as usize;
+        let mut samples = Vec::<f32>::new();
+        let mut decoded = Audio::empty();
+        let mut mono = Audio::empty();
+        for (stream, packet) in ictx.packets() {
+            if samples.len() >= limit {
+                break;
+            }
+            if stream.index() != index || decoder.send_packet(&packet).is_err() {
+                continue;
+            }
+            while decoder.receive_frame(&mut decoded).is_ok() {
+                resampler.run(&decoded, &mut mono)?;
+                samples.extend_from_slice(&mono.plane::<f32>(0)[..mono.samples()]);
+            }
+        }
+        samples.truncate(limit);
+
+        let chroma = samples
+            .windows(FRAME)
+            .step_by(HOP)
+            .map(chroma)
+            .collect::<Vec<_>>();
+        let values = chroma.windows(2).map(|w| encode(&w[0], &w[1])).collect();
+
+        Ok(Self { duration, values })
+    }
+}
+
+/// Energy per pitch class of one frame, normalised to unit length.
+fn chroma(frame: &[f32]) -> [f64; 12] {
+    // Hann window against leakage between bins
+    let mut re = frame
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| s as f64 * (0.5 - 0.5 * (2.0 * PI * i as f64 / FRAME as f64).cos()))
+        .collect::<Vec<_>>();
+    let mut im = vec![0.0; FRAME];
+    fft(&mut re, &mut im);
+
+    let mut bands = [0.0; 12];
+    for k in 1..FRAME / 2 {
+        let freq = k as f64 * RATE as f64 / FRAME as f64;
+        if !(MIN_FREQ..=MAX_FREQ).contains(&freq) {
+            continue;
+        }
+        // semitones from A440, folded into one octave
+        let note = (12.0 * (freq / 440.0).log2()).round() as i64;
+        bands[note.rem_euclid(12) as usize] += re[k] * re[k] + im[k] * im[k];
+    }
+
+    let norm = bands.iter().map(|b| b * b).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        bands.iter_mut().for_each(|b| *b /= norm);
+    }
+    bands
+}
+
+/// 12 bits comparing neighbouring pitch classes, 12 comparing each class with
+/// the frame before and 8 comparing pairs of classes a third apart.
+fn encode(previous: &[f64; 12], current: &[f64; 12]) -> u32 {
+    let mut bits = 0u32;
+    let mut push = |bit: bool| bits = (bits << 1) | bit as u32;
+    for i in 0..12 {
+        push(current[i] > current[(i + 1) % 12]);
+    }
+    for i in 0..12 {
+        push(current[i] > previous[i]);
+    }
+    for i in 0..8 {
+        push(current[i] + current[(i + 4) % 12] > current[(i + 2) % 12] + current[(i + 6) % 12]);
+    }
+    bits
+}
+
+/// In-place radix-2 FFT; the length has to be a power of two.
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (wr, wi) = ((angle * k as f64).cos(), (angle * k as f64).sin());
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = re[b] * wr - im[b] * wi;
+                let ti = re[b] * wi + im[b] * wr;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Prints an audio fingerprint for each file, in the same FILE, DURATION and
+/// FINGERPRINT lines as Chromaprint's fpcalc.
+#[derive(clap::Args)]
+pub struct CliArgs {
+    /// Videos, or folders of them.
+    #[arg(required = true)]
+    inputs: Vec<std::path::PathBuf>,
+    /// Seconds of audio to fingerprint from the start.
+    #[arg(long, default_value_t = DEFAULT_LENGTH)]
+    length: f64,
+}
+
+pub fn run_cli(args: CliArgs) -> BasicResult<()> {
+    let length = args.length;
+    for (i, file) in crate::cli::video_files(args.inputs)?.iter().enumerate() {
+        let print = AudioPrint::of(file, length)?;
+        if i > 0 {
+            println!();
+        }
+        println!("FILE={}", file.display());
+        println!("DURATION={}", print.duration.round());
+        let values = print.values.iter().map(u32::to_string).collect::<Vec<_>>();
+        println!("FINGERPRINT={}", values.join(","));
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use ffmpeg_next::ffi;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    ffi::CStr,
+    os::raw::{c_char, c_int, c_void},
+    sync::{Arc, Mutex},
+};
+
+/// Messages kept per capture; older ones are dropped.
+const MAX_LINES: usize = 50;
+
+pub type Log = Arc<Mutex<VecDeque<String>>>;
+
+thread_local! {
+    static SINK: RefCell<Option<Log>> = const { RefCell::new(None) };
+}
+
+#[cfg(all(unix, target_arch = "x86_64"))]
+type VaList = *mut ffi::__va_list_tag;
+#[cfg(not(all(unix, target_arch = "x86_64")))]
+type VaList = ffi::va_list;
+
+/// Routes libav logging into the `log` crate under the `ffmpeg` target, so
+/// it ends up in the log file even when there is no console to print to.
+/// Warnings and errors are additionally kept for whoever is capturing on the
+/// logging thread.
+pub fn install() {
+    unsafe { ffi::av_log_set_callback(Some(callback)) }
+}
+
+/// Sets the least severe message libav passes on, by its `-loglevel` name.
+pub fn set_level(name: &str) {
+    let level = match name.to_ascii_lowercase().as_str() {
+        "quiet" => ffi::AV_LOG_QUIET,
+        "panic" => ffi::AV_LOG_PANIC,
+        "fatal" => ffi::AV_LOG_FATAL,
+        "error" => ffi::AV_LOG_ERROR,
+        "warning" => ffi::AV_LOG_WARNING,
+        "info" => ffi::AV_LOG_INFO,
+        "verbose" => ffi::AV_LOG_VERBOSE,
+        "debug" => ffi::AV_LOG_DEBUG,
+        "trace" => ffi::AV_LOG_TRACE,
+        _ => {
+            log::warn!("unknown ffmpeg log level {name:?}");
+            return;
+        }
+    };
+    unsafe { ffi::av_log_set_level(level) }
+}
+
+fn log_level(level: c_int) -> log::Level {
+    match level {
+        ..=ffi::AV_LOG_ERROR => log::Level::Error,
+        ..=ffi::AV_LOG_WARNING => log::Level::Warn,
+        ..=ffi::AV_LOG_INFO => log::Level::Info,
+        ..=ffi::AV_LOG_VERBOSE => log::Level::Debug,
+        _ => log::Level::Trace,
+    }
+}
+
+/// Collects warnings and errors logged on this thread into `log`, or stops
+/// collecting when `None` is given. Messages from libav's own worker threads
+/// (e.g. frame threaded decoders) are not seen here.
+pub fn capture(log: Option<Log>) {
+    SINK.with(|s| *s.borrow_mut() = log);
+}
+
+unsafe extern "C" fn callback(avcl: *mut c_void, level: c_int, fmt: *const c_char, vl: VaList) {
+    let shown = level <= ffi::av_log_get_level();
+    let sink = if level <= ffi::AV_LOG_WARNING {
+        SINK.with(|s| s.borrow().clone())
+    } else {
+        None
+    };
+    if !shown && sink.is_none() {
+        return;
+    }
+
+    let mut buf = [0 as c_char; 1024];
+    let mut print_prefix: c_int = 1;
+    // the va_list can only be walked once, so format it ourselves and reuse the line
+    ffi::av_log_format_line2(
+        avcl,
+        level,
+        fmt,
+        vl,
+        buf.as_mut_ptr(),
+        buf.len() as c_int,
+        &mut print_prefix,
+    );
+    let line = CStr::from_ptr(buf.as_ptr()).to_string_lossy();
+    let line = line.trim_end();
+    if line.is_empty() {
+        return;
+    }
+
+    if shown {
+        log::log!(target: "ffmpeg", log_level(level), "{line}");
+    }
+    if let Some(Ok(mut log)) = sink.as_deref().map(Mutex::lock) {
+        if log.len() == MAX_LINES {
+            log.pop_front();
+        }
+        log.push_back(line.to_string());
+    }
+}
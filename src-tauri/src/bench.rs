@@ -0,0 +1,193 @@
+use ffmpeg_next::{self as ffmpeg, ffi, frame::Video, media, software};
+use std::{
+    error::Error,
+    ffi::CString,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Tried when `--hwaccel` isn't given, the usual API of each platform.
+#[cfg(target_os = "windows")]
+const DEFAULT_HWACCEL: &str = "d3d11va";
+#[cfg(target_os = "macos")]
+const DEFAULT_HWACCEL: &str = "videotoolbox";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const DEFAULT_HWACCEL: &str = "vaapi";
+
+/// One run through the whole video stream.
+struct Pass {
+    frames: u64,
+    /// Frames that came out of the decoder in GPU memory. Zero after asking
+    /// for hardware decoding means libav quietly fell back to software.
+    hw_frames: u64,
+    wall: Duration,
+    cpu: Duration,
+}
+
+impl Pass {
+    fn print(&self, name: &str, duration: f64) {
+        let wall = self.wall.as_secs_f64().max(1e-9);
+        println!("{name}:");
+        println!(
+            "  frames:   {} ({} in hardware)",
+            self.frames, self.hw_frames
+        );
+        println!("  time:     {wall:.2}s");
+        println!("  fps:      {:.1}", self.frames as f64 / wall);
+        println!("  realtime: {:.1}x", duration / wall);
+        // above 100% when the decoder uses several threads
+        println!("  cpu:      {:.0}%", self.cpu.as_secs_f64() / wall * 100.0);
+    }
+}
+
+/// Owns a libav hardware device context.
+struct HwDevice(*mut ffi::AVBufferRef);
+
+impl HwDevice {
+    fn open(name: &str) -> BasicResult<Self> {
+        let kind = unsafe { ffi::av_hwdevice_find_type_by_name(CString::new(name)?.as_ptr()) };
+        if kind == ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_NONE {
+            return Err(format!("unknown hardware decoder {name:?}").into());
+        }
+
+        let mut device = std::ptr::null_mut();
+        let ret = unsafe {
+            ffi::av_hwdevice_ctx_create(
+                &mut device,
+                kind,
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        if ret < 0 {
+            return Err(Box::new(ffmpeg::Error::from(ret)));
+        }
+        Ok(Self(device))
+    }
+}
+
+impl Drop for HwDevice {
+    fn drop(&mut self) {
+        unsafe { ffi::av_buffer_unref(&mut self.0) }
+    }
+}
+
+/// Decodes the video stream of `path` as fast as it goes. With `render` every
+/// frame is also converted to RGBA, as for screenshots, so the pass includes
+/// the copy back from the GPU.
+fn decode(path: &Path, hw: Option<&HwDevice>, render: bool) -> BasicResult<Pass> {
+    let mut ictx = ffmpeg::format::input(path)?;
+    let stream = ictx
+        .streams()
+        .best(media::Type::Video)
+        .ok_or("no video stream")?;
+    let index = stream.index();
+
+    let mut context = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+    if let Some(device) = hw {
+        unsafe { (*context.as_mut_ptr()).hw_device_ctx = ffi::av_buffer_ref(device.0) };
+    }
+    let mut decoder = context.decoder().video()?;
+
+    let mut pass = Pass {
+        frames: 0,
+        hw_frames: 0,
+        wall: Duration::ZERO,
+        cpu: Duration::ZERO,
+    };
+    let mut scaler: Option<software::scaling::Context> = None;
+    let mut decoded = Video::empty();
+    let mut downloaded = Video::empty();
+    let mut rgba = Video::empty();
+
+    let mut receive = |decoder: &mut ffmpeg::decoder::Video| -> BasicResult<()> {
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            pass.frames += 1;
+            let on_gpu = unsafe { !(*decoded.as_ptr()).hw_frames_ctx.is_null() };
+            if on_gpu {
+                pass.hw_frames += 1;
+            }
+            if !render {
+                continue;
+            }
+
+            let frame = if on_gpu {
+                let ret = unsafe {
+                    ffi::av_hwframe_transfer_data(downloaded.as_mut_ptr(), decoded.as_ptr(), 0)
+                };
+                if ret < 0 {
+                    return Err(Box::new(ffmpeg::Error::from(ret)));
+                }
+                &downloaded
+            } else {
+                &decoded
+            };
+            // the format of downloaded frames is only known once there is one
+            if scaler.is_none() {
+                scaler = Some(software::scaling::Context::get(
+                    frame.format(),
+                    frame.width(),
+                    frame.height(),
+                    ffmpeg::format::Pixel::RGBA,
+                    frame.width(),
+                    frame.height(),
+                    software::scaling::flag::Flags::BILINEAR,
+                )?);
+            }
+            scaler.as_mut().unwrap().run(frame, &mut rgba)?;
+        }
+        Ok(())
+    };
+
+    let cpu = cpu_time::ProcessTime::now();
+    let wall = Instant::now();
+
+    for (stream, packet) in ictx.packets() {
+        if stream.index() == index {
+            decoder.send_packet(&packet)?;
+            receive(&mut decoder)?;
+        }
+    }
+    decoder.send_eof()?;
+    receive(&mut decoder)?;
+
+    pass.wall = wall.elapsed();
+    pass.cpu = cpu.elapsed();
+    Ok(pass)
+}
+
+/// Decodes a file as fast as possible, once in software and once in
+/// hardware, and prints how each went.
+#[derive(clap::Args)]
+pub struct CliArgs {
+    file: PathBuf,
+    /// libav hardware device type to try, e.g. `cuda` or `qsv`.
+    #[arg(long, default_value = DEFAULT_HWACCEL)]
+    hwaccel: String,
+    /// Also convert every frame to RGBA, like screenshots do.
+    #[arg(long)]
+    render: bool,
+}
+
+pub fn run_cli(args: CliArgs) -> BasicResult<()> {
+    let CliArgs {
+        file,
+        hwaccel,
+        render,
+    } = args;
+    let duration = crate::media_duration(&file).unwrap_or(0.0);
+
+    println!("FILE={}", file.display());
+    decode(&file, None, render)?.print("software", duration);
+
+    // a missing GPU driver is a result worth printing, not a failure
+    let name = format!("hardware ({hwaccel})");
+    match HwDevice::open(&hwaccel).and_then(|device| decode(&file, Some(&device), render)) {
+        Ok(pass) => pass.print(&name, duration),
+        Err(e) => println!("{name}:\n  unavailable: {e}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use sha2::{Digest, Sha256};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tauri::{AppHandle, Manager};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Where the converted copy of `source` lives in the cache dir. The name is
+/// a SHA-256 of the path, size and modification time, so a file that changes
+/// gets converted again and names stay the same across builds. Whether
+/// deinterlacing is on goes in as well, as it is the one filter cached
+/// copies are made with.
+pub fn converted_path(app: &AppHandle, source: &Path) -> Option<PathBuf> {
+    let meta = std::fs::metadata(source).ok()?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos());
+    let deinterlace = app
+        .state::<crate::AppState>()
+        .deinterlace
+        .load(Ordering::Relaxed);
+
+    let digest = Sha256::new()
+        .chain_update(source.as_os_str().as_encoded_bytes())
+        .chain_update(meta.len().to_le_bytes())
+        .chain_update(modified.to_le_bytes())
+        .chain_update([deinterlace as u8])
+        .finalize();
+
+    let dir = app.path().app_cache_dir().ok()?.join("converted");
+    Some(dir.join(format!("{digest:x}.mp4")))
+}
+
+/// Marks a cached copy as just used, so [`evict`] removes it last.
+pub fn touch(path: &Path) {
+    let result = std::fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|f| f.set_modified(SystemTime::now()));
+    if let Err(e) = result {
+        log::warn!("could not touch {}: {e}", path.display());
+    }
+}
+
+/// Removes the least recently used copies until the cache fits in
+/// [`crate::config::Config::cache_size_mb`]. `keep` was just made and stays
+/// even when it doesn't fit on its own.
+pub fn evict(app: &AppHandle, keep: &Path) {
+    let Some(entries) = keep.parent().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return;
+    };
+    let limit = app.state::<crate::config::Config>().cache_size_mb * 1024 * 1024;
+
+    let mut copies: Vec<(SystemTime, u64, PathBuf)> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = entry.path();
+            // half-finished copies belong to conversions still running
+            if path.extension()? != "mp4" || path.to_string_lossy().ends_with(".tmp.mp4") {
+                return None;
+            }
+            let meta = entry.metadata().ok()?;
+            Some((meta.modified().ok()?, meta.len(), path))
+        })
+        .collect();
+    let mut total: u64 = copies.iter().map(|(_, len, _)| len).sum();
+    copies.sort();
+
+    for (_, len, path) in copies {
+        if total <= limit {
+            break;
+        }
+        if path == keep {
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => total -= len,
+            Err(e) => log::warn!("could not evict {}: {e}", path.display()),
+        }
+    }
+}
+
+/// Converts `source` into the cache unless it is already there.
+pub fn convert(app: &AppHandle, source: &Path) -> BasicResult<PathBuf> {
+    let out = converted_path(app, source).ok_or("no cache directory")?;
+    if out.exists() {
+        touch(&out);
+        return Ok(out);
+    }
+
+    std::fs::create_dir_all(out.parent().unwrap())?;
+    crate::convert::preflight(source, out.parent().unwrap(), &Default::default())?;
+
+    // write under a temporary name so a half-finished file is never picked up
+    let tmp = out.with_extension("tmp.mp4");
+    // filtered copies would be served once the filters are off again
+    let options = crate::unfiltered_options(app, Default::default());
+    let scheduler = app.state::<crate::jobs::Scheduler>();
+    let job = scheduler.start(crate::jobs::Priority::Batch);
+    job.writes(&tmp);
+
+    match crate::convert::convert_to_mp4(source, &tmp, &options, |_| job.checkpoint()) {
+        Ok(0) => {}
+        Ok(dropped) => log::warn!("{}: dropped {dropped} damaged packets", source.display()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(job.report(e).into());
+        }
+    }
+    std::fs::rename(&tmp, &out)?;
+    evict(app, &out);
+
+    Ok(out)
+}
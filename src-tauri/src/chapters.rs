@@ -0,0 +1,359 @@
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt::Write, path::Path};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// A chapter read from the container or a bookmark set by the user.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Marker {
+    pub start: f64,
+    pub end: Option<f64>,
+    pub title: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// `;FFMETADATA1` files, as written by `ffmpeg -f ffmetadata`.
+    FfMetadata,
+    /// `0:00 Title` lines, the format YouTube descriptions use.
+    YouTube,
+    Cue,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::FfMetadata => "ffmetadata",
+            Format::YouTube => "chapters.txt",
+            Format::Cue => "cue",
+        }
+    }
+
+    /// Plain `.txt` files are only taken for chapters when named like our
+    /// exports, `*.chapters.txt`; any other text file is most likely not.
+    pub fn from_path<P: AsRef<Path>>(path: &P) -> Option<Self> {
+        let name = path.as_ref().file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".chapters.txt") {
+            return Some(Format::YouTube);
+        }
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "ffmetadata" | "ffmeta" => Some(Format::FfMetadata),
+            "cue" => Some(Format::Cue),
+            _ => None,
+        }
+    }
+}
+
+pub fn read_chapters<P: AsRef<Path>>(path: &P) -> BasicResult<Vec<Marker>> {
+    let ictx = crate::probe::open(path)?;
+
+    Ok(ictx
+        .chapters()
+        .map(|ch| {
+            let tb = f64::from(ch.time_base());
+            Marker {
+                start: ch.start() as f64 * tb,
+                end: Some(ch.end() as f64 * tb),
+                title: ch
+                    .metadata()
+                    .get("title")
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("Chapter {}", ch.id())),
+            }
+        })
+        .collect())
+}
+
+/// `markers` must be sorted by start. A marker without an end runs until the
+/// next one starts, or until `duration` for the last one.
+pub fn export(markers: &[Marker], format: Format, media_name: &str, duration: f64) -> String {
+    let end_of = |i: usize| {
+        markers[i]
+            .end
+            .or_else(|| markers.get(i + 1).map(|m| m.start))
+            .unwrap_or(duration)
+    };
+
+    let mut out = String::new();
+    match format {
+        Format::FfMetadata => {
+            out.push_str(";FFMETADATA1\n");
+            for (i, m) in markers.iter().enumerate() {
+                let _ = write!(
+                    out,
+                    "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+                    (m.start * 1000.0).round() as i64,
+                    (end_of(i) * 1000.0).round() as i64,
+                    escape_ffmetadata(&m.title)
+                );
+            }
+        }
+        Format::YouTube => {
+            for m in markers {
+                let _ = writeln!(out, "{} {}", youtube_timestamp(m.start), m.title);
+            }
+        }
+        Format::Cue => {
+            let _ = writeln!(out, "FILE \"{}\" MP4", media_name.replace('"', "'"));
+            for (i, m) in markers.iter().enumerate() {
+                // cue sheets count in 1/75 s frames
+                let frames = (m.start * 75.0).round() as u64;
+                let _ = write!(
+                    out,
+                    "  TRACK {:02} AUDIO\n    TITLE \"{}\"\n    INDEX 01 {:02}:{:02}:{:02}\n",
+                    i + 1,
+                    m.title.replace('"', "'"),
+                    frames / 75 / 60,
+                    frames / 75 % 60,
+                    frames % 75
+                );
+            }
+        }
+    }
+
+    out
+}
+
+pub fn import(text: &str, format: Format) -> Vec<Marker> {
+    let text = text.trim_start_matches('\u{feff}');
+    let mut markers = match format {
+        Format::FfMetadata => import_ffmetadata(text),
+        Format::YouTube => text
+            .lines()
+            .filter_map(|line| {
+                let (ts, title) = line.trim().split_once(char::is_whitespace)?;
+                Some(Marker {
+                    start: parse_clock(ts)?,
+                    end: None,
+                    title: title.trim().trim_start_matches(['-', '–']).trim().into(),
+                })
+            })
+            .collect(),
+        Format::Cue => import_cue(text),
+    };
+    markers.sort_by(|a, b| a.start.total_cmp(&b.start));
+    markers
+}
+
+fn import_ffmetadata(text: &str) -> Vec<Marker> {
+    let mut markers = Vec::new();
+    // (timebase, start, end, title) of the chapter being read
+    let mut current: Option<(f64, Option<i64>, Option<i64>, String)> = None;
+
+    let mut flush = |current: &mut Option<(f64, Option<i64>, Option<i64>, String)>| {
+        if let Some((tb, Some(start), end, title)) = current.take() {
+            markers.push(Marker {
+                start: start as f64 * tb,
+                end: end.map(|e| e as f64 * tb),
+                title,
+            });
+        }
+    };
+
+    for line in text.lines().map(|l| l.trim()) {
+        if line.starts_with('[') {
+            flush(&mut current);
+            if line.eq_ignore_ascii_case("[chapter]") {
+                current = Some((0.001, None, None, String::new()));
+            }
+            continue;
+        }
+        let Some(ch) = current.as_mut() else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        match key.to_ascii_uppercase().as_str() {
+            "TIMEBASE" => {
+                if let Some((num, den)) = value.split_once('/') {
+                    if let (Ok(num), Ok(den)) = (num.parse::<f64>(), den.parse::<f64>()) {
+                        ch.0 = num / den;
+                    }
+                }
+            }
+            "START" => ch.1 = value.parse().ok(),
+            "END" => ch.2 = value.parse().ok(),
+            "TITLE" => ch.3 = unescape_ffmetadata(value),
+            _ => {}
+        }
+    }
+    flush(&mut current);
+
+    markers
+}
+
+fn import_cue(text: &str) -> Vec<Marker> {
+    let mut markers = Vec::new();
+    let mut title = String::new();
+
+    for line in text.lines().map(|l| l.trim()) {
+        if line.starts_with("TRACK ") {
+            title.clear();
+        } else if let Some(t) = line.strip_prefix("TITLE ") {
+            title = t.trim_matches('"').into();
+        } else if let Some(index) = line.strip_prefix("INDEX 01 ") {
+            let parts = index
+                .trim()
+                .split(':')
+                .filter_map(|p| p.parse::<u64>().ok())
+                .collect::<Vec<_>>();
+            if let [m, s, f] = parts[..] {
+                markers.push(Marker {
+                    start: (m * 60 + s) as f64 + f as f64 / 75.0,
+                    end: None,
+                    title: title.clone(),
+                });
+            }
+        }
+    }
+
+    markers
+}
+
+/// `h:mm:ss` or `m:ss`, leaving the hour out when it is zero.
+pub fn youtube_timestamp(time: f64) -> String {
+    let secs = time.max(0.0) as u64;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Parses `ss`, `m:ss` or `h:mm:ss` (fractions allowed) into seconds.
+pub fn parse_clock(ts: &str) -> Option<f64> {
+    ts.split(':').try_fold(0.0, |acc, part| {
+        let v = part.parse::<f64>().ok()?;
+        (v >= 0.0).then_some(acc * 60.0 + v)
+    })
+}
+
+fn escape_ffmetadata(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn unescape_ffmetadata(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export, import, parse_clock, youtube_timestamp, Format, Marker};
+
+    fn marker(start: f64, end: Option<f64>, title: &str) -> Marker {
+        Marker {
+            start,
+            end,
+            title: title.into(),
+        }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn parses_clocks() {
+        assert_eq!(parse_clock("90"), Some(90.0));
+        assert_eq!(parse_clock("1:30"), Some(90.0));
+        assert_eq!(parse_clock("1:02:03.5"), Some(3723.5));
+        assert_eq!(parse_clock("-1"), None);
+        assert_eq!(parse_clock("a:10"), None);
+        assert_eq!(parse_clock(""), None);
+    }
+
+    #[test]
+    fn formats_youtube_timestamps() {
+        assert_eq!(youtube_timestamp(59.9), "0:59");
+        assert_eq!(youtube_timestamp(90.0), "1:30");
+        assert_eq!(youtube_timestamp(3723.0), "1:02:03");
+        assert_eq!(youtube_timestamp(-5.0), "0:00");
+    }
+
+    #[test]
+    fn exports_youtube_lines() {
+        let markers = [marker(0.0, None, "Intro"), marker(90.0, None, "Main")];
+        assert_eq!(
+            export(&markers, Format::YouTube, "movie.mp4", 120.0),
+            "0:00 Intro\n1:30 Main\n"
+        );
+    }
+
+    #[test]
+    fn imports_youtube_lines() {
+        let text = "\u{feff}Chapters:\n1:30 - Main\n0:00 Intro\nTracklist here\n";
+        let markers = import(text, Format::YouTube);
+        assert_eq!(markers.len(), 2);
+        assert_eq!(
+            (markers[0].start, markers[0].title.as_str()),
+            (0.0, "Intro")
+        );
+        assert_eq!(
+            (markers[1].start, markers[1].title.as_str()),
+            (90.0, "Main")
+        );
+    }
+
+    #[test]
+    fn round_trips_ffmetadata() {
+        let markers = [
+            marker(0.0, Some(90.0), "A=B; #1"),
+            marker(90.0, None, "Two"),
+        ];
+        let text = export(&markers, Format::FfMetadata, "movie.mp4", 120.0);
+        assert!(text.starts_with(";FFMETADATA1\n"));
+
+        let back = import(&text, Format::FfMetadata);
+        assert_eq!(back.len(), 2);
+        assert!(close(back[0].start, 0.0) && close(back[0].end.unwrap(), 90.0));
+        assert_eq!(back[0].title, "A=B; #1");
+        // the last one runs to the end of the file
+        assert!(close(back[1].start, 90.0) && close(back[1].end.unwrap(), 120.0));
+        assert_eq!(back[1].title, "Two");
+    }
+
+    #[test]
+    fn round_trips_cue_sheets() {
+        let markers = [marker(0.0, None, "Intro"), marker(61.0, None, "Say \"hi\"")];
+        let text = export(&markers, Format::Cue, "movie.mp4", 120.0);
+        assert!(text.contains("    INDEX 01 01:01:00\n"));
+
+        let back = import(&text, Format::Cue);
+        assert_eq!(back.len(), 2);
+        assert_eq!((back[0].start, back[0].title.as_str()), (0.0, "Intro"));
+        assert_eq!((back[1].start, back[1].title.as_str()), (61.0, "Say 'hi'"));
+    }
+
+    #[test]
+    fn picks_formats_by_file_name() {
+        assert!(matches!(
+            Format::from_path(&"a.Chapters.txt"),
+            Some(Format::YouTube)
+        ));
+        assert!(matches!(Format::from_path(&"x.CUE"), Some(Format::Cue)));
+        assert!(matches!(
+            Format::from_path(&"x.ffmeta"),
+            Some(Format::FfMetadata)
+        ));
+        assert!(Format::from_path(&"notes.txt").is_none());
+    }
+}
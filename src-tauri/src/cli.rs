@@ -0,0 +1,241 @@
+use clap::{Parser, Subcommand};
+use std::{
+    error::Error,
+    ffi::OsString,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Release builds are GUI programs on Windows and have no console, so
+/// anything printed would go nowhere. This borrows the console of the shell
+/// that started us; started from Explorer there is none and nothing changes.
+#[cfg(windows)]
+pub fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // SAFETY: no pointers involved; failing just leaves us without a console
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+pub fn attach_console() {}
+
+/// A simple video player. Without a subcommand it plays INPUTS as a
+/// playlist.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub player: PlayerArgs,
+}
+
+#[derive(clap::Args, Clone, Default)]
+pub struct PlayerArgs {
+    /// Files or folders to play. `file#t=90` starts a file at 90 seconds,
+    /// and simplevidview:// links open like shared ones.
+    pub inputs: Vec<OsString>,
+    /// Where the first file starts, as `ss`, `m:ss` or `h:mm:ss`.
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub start: Option<f64>,
+    /// Open the first file paused at TIME, for pointing at an exact moment.
+    #[arg(long, value_name = "TIME", value_parser = parse_time, conflicts_with_all = ["start", "frame"])]
+    pub time: Option<f64>,
+    /// Open the first file paused at frame N, counting from 0.
+    #[arg(long, value_name = "N", conflicts_with = "start")]
+    pub frame: Option<u64>,
+    #[arg(long)]
+    pub fullscreen: bool,
+    /// Repeat the file, or the whole playlist when there are several.
+    #[arg(long = "loop")]
+    pub repeat: bool,
+    /// Playback rate, e.g. 1.5.
+    #[arg(long, value_parser = parse_speed)]
+    pub speed: Option<f64>,
+    /// Ask the webview to decode video without the GPU. Only WebView2, on
+    /// Windows, takes the hint.
+    #[arg(long)]
+    pub no_hwaccel: bool,
+    /// libavfilter chain run on the video when converting, like ffmpeg's
+    /// `-vf`, e.g. `crop=iw:ih-80,hqdn3d`. Replaces `video_filter` in the
+    /// config.
+    #[arg(long, value_name = "FILTERS", value_parser = parse_filter)]
+    pub vf: Option<String>,
+    /// Bytes libav reads to find the streams of a file. Less opens large
+    /// files faster but may miss streams that start late.
+    #[arg(long, value_name = "BYTES")]
+    pub probesize: Option<u64>,
+    /// Microseconds of media libav analyses when opening a file.
+    #[arg(long, value_name = "MICROSECONDS")]
+    pub analyzeduration: Option<u64>,
+    /// Demuxer flags like ffmpeg's `-fflags`, e.g. `+genpts`.
+    #[arg(long, value_name = "FLAGS", allow_hyphen_values = true)]
+    pub fflags: Option<String>,
+    /// Open another window even when the player is already running, instead
+    /// of playing INPUTS there.
+    #[arg(long)]
+    pub new_instance: bool,
+    /// Convert the input to OUT the way the player would, then exit.
+    #[arg(long, value_name = "OUT")]
+    pub convert: Option<PathBuf>,
+    /// With --convert: scale OUT down to at most N lines.
+    #[arg(long, value_name = "N", requires = "convert")]
+    pub max_height: Option<u32>,
+    /// With --convert: also write a full size copy to FULL, from the same
+    /// decode.
+    #[arg(long, value_name = "FULL", requires = "convert")]
+    pub tee: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Render a grid of frames for each video.
+    ContactSheet(crate::sheet::CliArgs),
+    /// Find videos that look the same.
+    Duplicates(crate::phash::CliArgs),
+    /// Print audio fingerprints like Chromaprint's fpcalc.
+    Fingerprint(crate::audioprint::CliArgs),
+    /// Time software against hardware decoding.
+    #[command(long_flag = "benchmark")]
+    Benchmark(crate::bench::CliArgs),
+}
+
+impl Command {
+    pub fn run(self) -> BasicResult<()> {
+        match self {
+            Command::ContactSheet(args) => crate::sheet::run_cli(args),
+            Command::Duplicates(args) => crate::phash::run_cli(args),
+            Command::Fingerprint(args) => crate::audioprint::run_cli(args),
+            Command::Benchmark(args) => crate::bench::run_cli(args),
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<f64, String> {
+    crate::chapters::parse_clock(value).ok_or_else(|| format!("not a time: {value}"))
+}
+
+fn parse_filter(value: &str) -> Result<String, String> {
+    crate::filter::check(value)
+        .map(|_| value.to_string())
+        .map_err(|e| format!("invalid filter chain: {e}"))
+}
+
+/// Limited to the range browsers accept for `playbackRate`.
+fn parse_speed(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(speed) if (0.0625..=16.0).contains(&speed) => Ok(speed),
+        _ => Err("speed must be between 0.0625 and 16".into()),
+    }
+}
+
+impl PlayerArgs {
+    /// `--loop` as one of the frontend's loop modes, for a playlist of
+    /// `items` files.
+    pub fn loop_mode(&self, items: usize) -> Option<&'static str> {
+        match (self.repeat, items) {
+            (false, _) => None,
+            (true, 0..=1) => Some("file"),
+            (true, _) => Some("playlist"),
+        }
+    }
+
+    /// `--probesize`, `--analyzeduration` and `--fflags`, which win over the
+    /// config's `[probe]` table.
+    pub fn probe_options(&self) -> crate::probe::ProbeOptions {
+        crate::probe::ProbeOptions {
+            probesize: self.probesize,
+            analyzeduration: self.analyzeduration,
+            fflags: self.fflags.clone(),
+        }
+    }
+
+    /// Where `--time` or `--frame` want `path`, the first file, paused.
+    pub fn pause_at(&self, path: &Path) -> Option<f64> {
+        if let Some(time) = self.time {
+            return Some(time);
+        }
+        let frame = self.frame?;
+        let rate = ffmpeg_next::format::input(path)
+            .ok()
+            .and_then(|ictx| {
+                let stream = ictx.streams().best(ffmpeg_next::media::Type::Video)?;
+                Some(f64::from(stream.avg_frame_rate()))
+            })
+            .filter(|fps| *fps > 0.0);
+        let Some(fps) = rate else {
+            log::warn!("--frame: no frame rate for {}", path.display());
+            return None;
+        };
+        // the middle of the frame, so rounding can't land on a neighbour
+        Some((frame as f64 + 0.5) / fps)
+    }
+
+    /// `--convert`: runs the input through the same conversion as opening it
+    /// would, printing progress, without starting the player.
+    pub fn convert(&self, out: &Path) -> BasicResult<()> {
+        let [input] = self.inputs.as_slice() else {
+            return Err("--convert takes exactly one input".into());
+        };
+
+        let options = crate::convert::ConvertOptions {
+            video_filters: self.vf.iter().cloned().collect(),
+            max_height: self.max_height,
+            tee: self.tee.clone().map(|path| crate::convert::Tee {
+                path,
+                max_height: None,
+            }),
+            ..Default::default()
+        };
+        let out_dir = out
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        crate::convert::preflight(input, out_dir, &options.mapping)?;
+        let dropped = crate::convert::convert_to_mp4(input, out, &options, |p| {
+            eprint!("\r{:3.0}%", p * 100.0);
+            let _ = std::io::stderr().flush();
+        })?;
+        eprintln!();
+        if dropped > 0 {
+            eprintln!("skipped {dropped} damaged packets");
+        }
+        Ok(())
+    }
+}
+
+/// Files as given, with folders replaced by the videos directly inside them.
+pub fn video_files(inputs: Vec<PathBuf>) -> BasicResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            let mut entries = std::fs::read_dir(&input)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.is_file() && is_video(p))
+                .collect::<Vec<_>>();
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(input);
+        }
+    }
+    if files.is_empty() {
+        return Err("no video files given".into());
+    }
+    Ok(files)
+}
+
+fn is_video(path: &Path) -> bool {
+    crate::watch::is_video_file(path)
+        || path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("mp4"))
+}
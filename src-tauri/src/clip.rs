@@ -0,0 +1,212 @@
+use crate::filter::VideoFilter;
+use ffmpeg_next::{self as ffmpeg, codec, frame::Video, media, Packet, Rational};
+use serde::Deserialize;
+use std::{error::Error, path::Path};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Kind of animated picture a clip is saved as.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Gif,
+    Webp,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Gif => "gif",
+            Format::Webp => "webp",
+        }
+    }
+
+    fn encoder(self) -> Option<ffmpeg::Codec> {
+        match self {
+            Format::Gif => ffmpeg::encoder::find(codec::Id::GIF),
+            // the plain libwebp encoder only writes stills
+            Format::Webp => ffmpeg::encoder::find_by_name("libwebp_anim"),
+        }
+    }
+}
+
+/// `[clip]` table: how segments are exported as animated pictures.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct ClipOptions {
+    /// `gif` or `webp`.
+    pub format: Format,
+    pub fps: u32,
+    /// Clips are scaled down to this width; smaller videos keep theirs.
+    pub width: u32,
+}
+
+impl Default for ClipOptions {
+    fn default() -> Self {
+        Self {
+            format: Format::Gif,
+            fps: 15,
+            width: 480,
+        }
+    }
+}
+
+impl ClipOptions {
+    fn spec(&self, width: u32) -> String {
+        let scaled = format!("fps={},scale={width}:-1:flags=lanczos", self.fps);
+        match self.format {
+            // one palette made for the whole clip looks far better than the
+            // generic 256 colours
+            Format::Gif => format!(
+                "{scaled},split[a][b];[a]palettegen=stats_mode=diff[p];[b][p]paletteuse=dither=bayer"
+            ),
+            Format::Webp => format!("{scaled},format=yuva420p"),
+        }
+    }
+}
+
+/// Renders `start` to `end` seconds of `path` into an animated picture at
+/// `out` and returns how many frames it has. `on_progress` gets the fraction
+/// done after every decoded frame.
+pub fn export<P: AsRef<Path> + ?Sized>(
+    path: &P,
+    start: f64,
+    end: f64,
+    options: &ClipOptions,
+    out: &Path,
+    mut on_progress: impl FnMut(f64),
+) -> BasicResult<usize> {
+    let mut ictx = ffmpeg::format::input(path)?;
+    let stream = ictx
+        .streams()
+        .best(media::Type::Video)
+        .ok_or("no video stream")?;
+    let index = stream.index();
+    let time_base = stream.time_base();
+
+    let context_decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+    let mut decoder = context_decoder.decoder().video()?;
+    let width = options.width.min(decoder.width());
+    let mut filter = VideoFilter::new(&options.spec(width), &decoder, time_base)?;
+
+    let codec = options.format.encoder().ok_or("clip encoder not found")?;
+    let mut writer = Writer {
+        octx: ffmpeg::format::output(out)?,
+        codec,
+        time_base: filter.time_base(),
+        encoder: None,
+        frames: 0,
+    };
+
+    let target = (start * ffmpeg::ffi::AV_TIME_BASE as f64) as i64;
+    ictx.seek(target, ..target)?;
+
+    // returns whether `end` has been passed
+    let mut feed = |frame: &mut Video, filter: &mut VideoFilter| -> BasicResult<bool> {
+        let time = frame.timestamp().unwrap_or(0) as f64 * f64::from(time_base);
+        if time < start {
+            return Ok(false);
+        }
+        if time > end {
+            return Ok(true);
+        }
+        frame.set_pts(frame.timestamp());
+        filter.push(frame)?;
+        if end > start {
+            on_progress(((time - start) / (end - start)).min(1.0));
+        }
+        Ok(false)
+    };
+
+    let mut frame = Video::empty();
+    let mut filtered = Video::empty();
+    'decode: {
+        for (stream, packet) in ictx.packets() {
+            if stream.index() != index {
+                continue;
+            }
+            if decoder.send_packet(&packet).is_err() {
+                continue;
+            }
+            while decoder.receive_frame(&mut frame).is_ok() {
+                if feed(&mut frame, &mut filter)? {
+                    break 'decode;
+                }
+                // GIFs come out only after the palette, at the end
+                while filter.pull(&mut filtered).is_ok() {
+                    writer.write(&filtered)?;
+                }
+            }
+        }
+        decoder.send_eof()?;
+        while decoder.receive_frame(&mut frame).is_ok() {
+            if feed(&mut frame, &mut filter)? {
+                break 'decode;
+            }
+        }
+    }
+
+    filter.flush()?;
+    while filter.pull(&mut filtered).is_ok() {
+        writer.write(&filtered)?;
+    }
+    writer.finish()
+}
+
+/// Output that is only set up once the first frame shows its size.
+struct Writer {
+    octx: ffmpeg::format::context::Output,
+    codec: ffmpeg::Codec,
+    time_base: Rational,
+    encoder: Option<ffmpeg::encoder::Video>,
+    frames: usize,
+}
+
+impl Writer {
+    fn write(&mut self, frame: &Video) -> BasicResult<()> {
+        if self.encoder.is_none() {
+            let mut encoder = codec::context::Context::new_with_codec(self.codec)
+                .encoder()
+                .video()?;
+            encoder.set_width(frame.width());
+            encoder.set_height(frame.height());
+            encoder.set_format(frame.format());
+            encoder.set_time_base(self.time_base);
+            let encoder = encoder.open_as(self.codec)?;
+
+            let mut ostream = self.octx.add_stream(self.codec)?;
+            ostream.set_parameters(&encoder);
+            ostream.set_time_base(self.time_base);
+            self.octx.write_header()?;
+            self.encoder = Some(encoder);
+        }
+
+        self.encoder.as_mut().unwrap().send_frame(frame)?;
+        self.frames += 1;
+        self.drain()
+    }
+
+    fn drain(&mut self) -> BasicResult<()> {
+        let Some(encoder) = self.encoder.as_mut() else {
+            return Ok(());
+        };
+        let mut packet = Packet::empty();
+        while encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(0);
+            packet.rescale_ts(self.time_base, self.octx.stream(0).unwrap().time_base());
+            packet.write_interleaved(&mut self.octx)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> BasicResult<usize> {
+        let Some(encoder) = self.encoder.as_mut() else {
+            return Ok(0);
+        };
+        encoder.send_eof()?;
+        self.drain()?;
+        self.octx.write_trailer()?;
+        Ok(self.frames)
+    }
+}
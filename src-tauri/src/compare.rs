@@ -0,0 +1,247 @@
+use crate::{convert, jobs, readahead::ReadAhead};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+use tauri::{
+    AppHandle, DragDropEvent, Emitter, LogicalPosition, Manager, WebviewUrl, WebviewWindowBuilder,
+    Window, WindowEvent,
+};
+
+/// Labels of the extra windows are this followed by their number; the main
+/// window is `main`.
+const LABEL_PREFIX: &str = "player-";
+
+/// Logical size new windows open with, the same as the main window's default.
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 600.0;
+
+/// One extra window. Playback, and with it the clock, runs in its webview;
+/// this is what the backend keeps for it.
+struct Side {
+    source: Option<PathBuf>,
+    /// `v-<n>.mp4` is complete and can be played.
+    ready: bool,
+    /// A conversion is writing `v-<n>.mp4`.
+    busy: bool,
+    read_ahead: Arc<ReadAhead>,
+}
+
+/// Extra player windows for comparing files side by side. Each one plays a
+/// file of its own from `v-<n>.mp4`, apart from the main window's playlist,
+/// tracks and filters, and takes new files by drag and drop.
+#[derive(Default)]
+pub struct Windows {
+    next: AtomicUsize,
+    open: Mutex<HashMap<usize, Side>>,
+}
+
+impl Windows {
+    /// The read-ahead of window `n`, which reads independently of the others.
+    pub fn read_ahead(&self, n: usize) -> Option<Arc<ReadAhead>> {
+        let open = self.open.lock().unwrap();
+        open.get(&n).map(|side| side.read_ahead.clone())
+    }
+
+    /// The name of the file window `n` plays, once it can be played.
+    pub fn ready(&self, n: usize) -> Option<String> {
+        let open = self.open.lock().unwrap();
+        open.get(&n)
+            .filter(|side| side.ready)
+            .and_then(|side| side.source.as_deref())
+            .map(crate::file_label)
+    }
+}
+
+/// Where window `n` plays from.
+pub fn output(n: usize) -> PathBuf {
+    PathBuf::from(format!("./v-{n}.mp4"))
+}
+
+/// The number of an extra window from its label; `None` for the main window.
+pub fn number(label: &str) -> Option<usize> {
+    label.strip_prefix(LABEL_PREFIX)?.parse().ok()
+}
+
+/// Opens a new window to the right of the main one, playing `path` if there
+/// is one and waiting for a file to be dropped otherwise.
+pub fn open(h: &AppHandle, path: Option<PathBuf>) -> Result<(), String> {
+    let windows = h.state::<Windows>();
+    let n = windows.next.fetch_add(1, Ordering::Relaxed) + 1;
+    windows.open.lock().unwrap().insert(
+        n,
+        Side {
+            source: None,
+            ready: false,
+            busy: false,
+            read_ahead: Default::default(),
+        },
+    );
+
+    let mut builder = WebviewWindowBuilder::new(
+        h,
+        format!("{LABEL_PREFIX}{n}"),
+        WebviewUrl::App(format!("index.html?window={n}").into()),
+    )
+    .title("simplevidview")
+    .inner_size(WIDTH, HEIGHT);
+    if let Some(position) = beside_main(h) {
+        builder = builder.position(position.x, position.y);
+    }
+    if let Err(e) = builder.build() {
+        windows.open.lock().unwrap().remove(&n);
+        return Err(e.to_string());
+    }
+
+    if let Some(path) = path {
+        play(h, n, path);
+    }
+    Ok(())
+}
+
+/// Just right of the main window, offset a little for each window already
+/// open so they don't stack exactly.
+fn beside_main(h: &AppHandle) -> Option<LogicalPosition<f64>> {
+    let main = h.get_webview_window("main")?;
+    let scale = main.scale_factor().ok()?;
+    let position = main.outer_position().ok()?.to_logical::<f64>(scale);
+    let size = main.outer_size().ok()?.to_logical::<f64>(scale);
+    let count = h.state::<Windows>().open.lock().unwrap().len() as f64;
+    Some(LogicalPosition::new(
+        position.x + size.width + 32.0 * (count - 1.0),
+        position.y + 32.0 * (count - 1.0),
+    ))
+}
+
+/// Makes `v-<n>.mp4` from `path` for window `n`, as the main window would
+/// for its own file, and tells the window through `window-progress`,
+/// `window-ready` and `window-error`.
+pub fn play(h: &AppHandle, n: usize, path: PathBuf) {
+    let label = format!("{LABEL_PREFIX}{n}");
+    {
+        let windows = h.state::<Windows>();
+        let mut open = windows.open.lock().unwrap();
+        let Some(side) = open.get_mut(&n) else {
+            return;
+        };
+        // the file is still being written for the previous one
+        if side.busy {
+            h.emit_to(label.as_str(), "window-error", "window.busy")
+                .unwrap();
+            return;
+        }
+        side.source = Some(path.clone());
+        side.ready = false;
+        side.busy = true;
+        side.read_ahead.reset();
+    }
+    if let Some(window) = h.get_webview_window(&label) {
+        let _ = window.set_title(&format!("{} — simplevidview", crate::file_label(&path)));
+    }
+
+    let h = h.clone();
+    std::thread::spawn(move || {
+        let result = make_playable(&h, &path, &output(n), |p| {
+            h.emit_to(label.as_str(), "window-progress", p).unwrap();
+        });
+
+        let windows = h.state::<Windows>();
+        let mut open = windows.open.lock().unwrap();
+        // closed meanwhile
+        let Some(side) = open.get_mut(&n) else {
+            let _ = std::fs::remove_file(output(n));
+            return;
+        };
+        side.busy = false;
+        match result {
+            Ok(()) => {
+                side.ready = true;
+                h.emit_to(label.as_str(), "window-ready", crate::file_label(&path))
+                    .unwrap();
+            }
+            Err(e) => h.emit_to(label.as_str(), "window-error", e).unwrap(),
+        }
+    });
+}
+
+/// Copies `path` to `out` when the webview plays it as is, and converts it
+/// otherwise, using the cached copy if there is one.
+fn make_playable(
+    h: &AppHandle,
+    path: &Path,
+    out: &Path,
+    mut on_progress: impl FnMut(f64),
+) -> Result<(), String> {
+    if crate::network::is_url(path) {
+        return Err("window.no-urls".into());
+    }
+    // there is no PIN prompt here, so locked files only play in the main window
+    let locks = h.state::<crate::lock::Locks>();
+    if locks.get(path).whole && !locks.unlocked(path) {
+        return Err("window.locked".into());
+    }
+    // cached copies are made without filters
+    let cached = crate::cache::converted_path(h, path)
+        .filter(|p| p.exists())
+        .filter(|_| !crate::filtering(h));
+    let copy = if crate::plays_directly(h, path) {
+        Some(path)
+    } else {
+        cached.as_deref()
+    };
+    if let Some(from) = copy {
+        std::fs::copy(from, out).map_err(|e| format!("could not read {}: {e}", path.display()))?;
+        if cached.as_deref() == Some(from) {
+            crate::cache::touch(from);
+        }
+        return Ok(());
+    }
+
+    convert::preflight(path, Path::new("."), &Default::default()).map_err(|e| e.to_string())?;
+    let scheduler = h.state::<jobs::Scheduler>();
+    let job = scheduler.start(jobs::Priority::Current);
+    job.writes(out);
+    let options = crate::convert_options(h, Default::default());
+    convert::convert_to_mp4(path, out, &options, |p| {
+        job.checkpoint();
+        on_progress(p);
+    })
+    .map(|_| ())
+    .map_err(|e| job.report(e))
+}
+
+/// Window events of the extra windows: dropped files play there, and
+/// closing one forgets it along with its copy.
+pub fn window_event(window: &Window, event: &WindowEvent) {
+    let Some(n) = number(window.label()) else {
+        return;
+    };
+    let h = window.app_handle();
+    match event {
+        WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) => {
+            if let Some(path) = paths.first() {
+                play(h, n, path.clone());
+            }
+        }
+        WindowEvent::Destroyed => {
+            let side = h.state::<Windows>().open.lock().unwrap().remove(&n);
+            // a running conversion cleans up after itself when it ends
+            if side.is_some_and(|side| !side.busy) {
+                let _ = std::fs::remove_file(output(n));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Removes what the extra windows were playing, when the player exits.
+pub fn clean_up(h: &AppHandle) {
+    let windows = h.state::<Windows>();
+    for n in windows.open.lock().unwrap().keys() {
+        let _ = std::fs::remove_file(output(*n));
+    }
+}
@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// User-editable options, read once at startup from `config.toml` in the
+/// platform config dir. Missing keys fall back to their defaults.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// See [`crate::naming::render`] for the supported placeholders.
+    pub screenshot_template: String,
+    /// Names of exported clips and frame folders, with the same placeholders
+    /// as `screenshot_template` and `{timecode}` where the export starts.
+    /// Clips get their extension added.
+    pub export_template: String,
+    /// Where screenshots are saved; the pictures folder when not set.
+    pub screenshot_folder: Option<PathBuf>,
+    /// `png` or `jpeg`, for frame ranges exported with Shift+S. They go into
+    /// a folder of their own in the screenshot folder.
+    pub sequence_format: crate::sequence::Format,
+    /// Files opened for playback are converted at most this many lines tall,
+    /// which plays smoothly on slow machines. A full quality copy goes into
+    /// the cache from the same decode and is played the next time.
+    pub preview_height: Option<u32>,
+    /// Megabytes of converted copies kept in the cache; the least recently
+    /// played go first when there are more.
+    pub cache_size_mb: u64,
+    /// `[probe]` table, see [`crate::probe::ProbeOptions`].
+    pub probe: crate::probe::ProbeOptions,
+    /// `[clip]` table, see [`crate::clip::ClipOptions`].
+    pub clip: crate::clip::ClipOptions,
+    /// New videos appearing here are converted in the background and added
+    /// to the playlist.
+    pub watch_folder: Option<PathBuf>,
+    /// Re-open every conversion and check it for truncation or corruption.
+    pub verify_conversions: bool,
+    /// Least severe ffmpeg message that still gets logged: `quiet`, `panic`,
+    /// `fatal`, `error`, `warning`, `info`, `verbose`, `debug` or `trace`.
+    pub ffmpeg_log_level: String,
+    /// `[decode_errors]` table, see [`crate::convert::ErrorPolicy`].
+    pub decode_errors: crate::convert::ErrorPolicy,
+    /// Deinterlace frames flagged as interlaced; toggled at runtime with D.
+    pub deinterlace: bool,
+    /// libavfilter chain run on every video when converting, like ffmpeg's
+    /// `-vf`, e.g. `"crop=iw:ih-80,hqdn3d"`. `--vf` replaces it.
+    pub video_filter: Option<String>,
+    /// `srgb`, `p3` or `rec2020`: what the display shows, deciding whether
+    /// BT.2020 video is mapped to BT.709. Asked from the webview when unset,
+    /// assuming `srgb` until it answers.
+    pub display_gamut: Option<crate::filter::Gamut>,
+    /// `[interpolation]` table, see [`crate::filter::Interpolation`].
+    pub interpolation: crate::filter::Interpolation,
+    /// Effects from [`crate::effects`] applied, in order, when converting,
+    /// e.g. `["grayscale"]`.
+    pub frame_filters: Vec<String>,
+    /// UI language as a BCP 47 tag such as `de`; the system's when not set.
+    pub language: Option<String>,
+    pub appearance: Appearance,
+    pub osd: OsdTheme,
+    pub seek_steps: SeekSteps,
+    /// `[keys]` table of action names to keys, see `src/keymap.ts` for both.
+    /// Only the actions listed here change.
+    pub keys: HashMap<String, KeyBinding>,
+    /// `[motion]` table, see [`crate::motion::MotionOptions`].
+    pub motion: crate::motion::MotionOptions,
+    /// Localhost TCP port other tools can send commands to, see
+    /// [`crate::control`]. Off when not set.
+    pub control_port: Option<u16>,
+    /// Rhai scripts to load at startup, see [`crate::scripting::Scripts`].
+    pub scripts: Vec<PathBuf>,
+    /// `[hooks]` table of shell commands to run on player events.
+    pub hooks: crate::hooks::Hooks,
+    /// `auto`, `on` or `off`, see [`crate::power::PowerSaver`]; toggled at
+    /// runtime with P.
+    pub power_saver: crate::power::Mode,
+    /// Closing the window hides it to the tray and keeps playing; Exit in the
+    /// tray menu quits.
+    pub close_to_tray: bool,
+}
+
+/// One key or several for the same action.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// `[appearance]` table, for people who have trouble seeing or hitting the
+/// default controls.
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Appearance {
+    /// Black and white controls with a yellow progress bar.
+    pub high_contrast: bool,
+    /// Twice the height for the seek bar and play button.
+    pub large_controls: bool,
+    /// Fill the bars around the picture with colours from its edges instead
+    /// of black; toggled at runtime with B.
+    pub ambient: bool,
+    /// How the picture is scaled to the window.
+    pub scaling: Scaling,
+    /// Only scale by whole multiples of the video size, leaving a border
+    /// around it; toggled at runtime with Shift+X.
+    pub integer_scale: bool,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scaling {
+    /// Smooth, right for camera footage.
+    #[default]
+    Linear,
+    /// Hard pixel edges, for pixel art and screen recordings.
+    Nearest,
+}
+
+/// `[osd]` table: how on-screen messages, the stats overlay and the
+/// buffering notice look. Colours and fonts are CSS values; unset ones keep
+/// the stylesheet's.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OsdTheme {
+    /// Text colour, e.g. `"#ffd700"`.
+    pub color: Option<String>,
+    /// Behind the text, e.g. `"rgba(0, 0, 0, 0.6)"`.
+    pub background: Option<String>,
+    pub font_family: Option<String>,
+    /// Text height in percent of the window height.
+    pub font_size: f64,
+    /// Milliseconds a message stays up.
+    pub duration: u32,
+}
+
+impl Default for OsdTheme {
+    fn default() -> Self {
+        Self {
+            color: None,
+            background: None,
+            font_family: None,
+            font_size: 3.0,
+            duration: 2000,
+        }
+    }
+}
+
+/// `[seek_steps]` table: seconds jumped by Shift and Ctrl with the arrow
+/// keys.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SeekSteps {
+    pub short: f64,
+    pub long: f64,
+}
+
+impl Default for SeekSteps {
+    fn default() -> Self {
+        Self {
+            short: 5.0,
+            long: 30.0,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            screenshot_template: "{filename}_{timecode}_{n}.png".into(),
+            export_template: "{filename}_{timecode}_{n}".into(),
+            screenshot_folder: None,
+            sequence_format: Default::default(),
+            clip: Default::default(),
+            preview_height: None,
+            cache_size_mb: 10 * 1024,
+            probe: Default::default(),
+            watch_folder: None,
+            verify_conversions: false,
+            ffmpeg_log_level: "warning".into(),
+            decode_errors: Default::default(),
+            deinterlace: true,
+            video_filter: None,
+            display_gamut: None,
+            interpolation: Default::default(),
+            frame_filters: Vec::new(),
+            language: None,
+            appearance: Appearance::default(),
+            osd: OsdTheme::default(),
+            seek_steps: SeekSteps::default(),
+            keys: HashMap::new(),
+            motion: Default::default(),
+            control_port: None,
+            scripts: Vec::new(),
+            hooks: Default::default(),
+            power_saver: Default::default(),
+            close_to_tray: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn path(app: &AppHandle) -> Option<PathBuf> {
+        app.path()
+            .app_config_dir()
+            .ok()
+            .map(|dir| dir.join("config.toml"))
+    }
+
+    pub fn load(app: &AppHandle) -> Self {
+        let Some(path) = Self::path(app) else {
+            return Self::default();
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text).unwrap_or_else(|e| {
+                log::warn!("ignoring invalid {}: {e}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use serde::Deserialize;
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+};
+use tauri::{AppHandle, Emitter};
+
+/// One line of JSON sent to the control port, e.g.
+/// `{"cmd": "osd", "text": "check the cut at 1:02", "seconds": 5}`.
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+enum Command {
+    /// Shows `text` the same way the player's own messages are shown.
+    Osd {
+        text: String,
+        #[serde(default = "default_osd_seconds")]
+        seconds: f64,
+    },
+}
+
+fn default_osd_seconds() -> f64 {
+    2.0
+}
+
+/// Listens on `port` on localhost for other tools to control the player.
+/// Each request gets `ok` or `error: ...` back on its own line.
+pub fn spawn(app: AppHandle, port: u16) -> std::io::Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let app = app.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = serve(&app, stream) {
+                    log::warn!("control connection failed: {e}");
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn serve(app: &AppHandle, stream: TcpStream) -> std::io::Result<()> {
+    let mut out = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Command>(&line) {
+            Ok(command) => {
+                run(app, command);
+                writeln!(out, "ok")?;
+            }
+            Err(e) => writeln!(out, "error: {e}")?,
+        }
+    }
+    Ok(())
+}
+
+fn run(app: &AppHandle, command: Command) {
+    match command {
+        Command::Osd { text, seconds } => app.emit("osd-text", (text, seconds)).unwrap(),
+    }
+}
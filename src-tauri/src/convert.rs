@@ -0,0 +1,792 @@
+use crate::{effects::FrameFilter, filter::VideoFilter};
+use ffmpeg_next::{self as ffmpeg, Rescale};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// Whether a mapped stream is re-encoded or its packets are passed through.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamMode {
+    Copy,
+    Transcode,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StreamMap {
+    /// Input stream index, as listed by [`probe_streams`].
+    pub index: usize,
+    pub mode: StreamMode,
+}
+
+/// Which input streams end up in the output, like ffmpeg's `-map`. An empty
+/// mapping means [`StreamMapping::auto`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct StreamMapping {
+    pub streams: Vec<StreamMap>,
+}
+
+impl StreamMapping {
+    /// The best video stream and the first audio stream, both transcoded.
+    /// Other languages are only converted once picked in the player, see
+    /// [`StreamMapping::audio_track`]. Cover art is left out; the player
+    /// shows it from [`cover_art`].
+    pub fn auto(ictx: &ffmpeg::format::context::Input) -> Self {
+        let video = ictx
+            .streams()
+            .best(ffmpeg::media::Type::Video)
+            .filter(|s| !is_attached_pic(s));
+
+        let audio = ictx
+            .streams()
+            .find(|s| s.parameters().medium() == ffmpeg::media::Type::Audio);
+
+        let streams = video
+            .into_iter()
+            .chain(audio)
+            .map(|s| StreamMap {
+                index: s.index(),
+                mode: StreamMode::Transcode,
+            })
+            .collect();
+
+        Self { streams }
+    }
+
+    /// Like [`StreamMapping::auto`], but with the `track`th audio stream
+    /// instead of the first, for switching languages.
+    pub fn audio_track<P: AsRef<std::path::Path> + ?Sized>(
+        path: &P,
+        track: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        let ictx = crate::probe::open(path)?;
+        let audio = ictx
+            .streams()
+            .filter(|s| s.parameters().medium() == ffmpeg::media::Type::Audio)
+            .nth(track)
+            .ok_or("no such audio track")?
+            .index();
+
+        let mut mapping = Self::auto(&ictx);
+        mapping.streams.retain(|s| {
+            ictx.stream(s.index)
+                .is_some_and(|s| s.parameters().medium() != ffmpeg::media::Type::Audio)
+        });
+        mapping.streams.push(StreamMap {
+            index: audio,
+            mode: StreamMode::Transcode,
+        });
+        Ok(mapping)
+    }
+
+    /// Replaces an empty mapping with [`StreamMapping::auto`].
+    fn resolve(&self, ictx: &ffmpeg::format::context::Input) -> Self {
+        if self.streams.is_empty() {
+            Self::auto(ictx)
+        } else {
+            self.clone()
+        }
+    }
+}
+
+/// Everything about a conversion besides where it reads and writes.
+#[derive(Clone, Debug, Default)]
+pub struct ConvertOptions {
+    pub mapping: StreamMapping,
+    pub errors: ErrorPolicy,
+    /// libavfilter chains run, in order, on decoded video before it is
+    /// scaled for the encoder.
+    pub video_filters: Vec<String>,
+    /// Names of [`crate::effects`] run on each frame after `video_filters`.
+    pub frame_filters: Vec<String>,
+    /// Fewer threads and a cheaper encoder preset, for running on battery.
+    pub low_power: bool,
+    /// Run BT.2020 SDR video through [`crate::filter::GAMUT_MAP`].
+    pub map_gamut: bool,
+    /// Video taller than this is scaled down to it.
+    pub max_height: Option<u32>,
+    /// A second file written from the same decode.
+    pub tee: Option<Tee>,
+}
+
+/// Another output next to the main one. It costs an extra encode, but the
+/// input is only demuxed and decoded once, and audio is only encoded once.
+#[derive(Clone, Debug)]
+pub struct Tee {
+    pub path: std::path::PathBuf,
+    /// Like [`ConvertOptions::max_height`], for this output.
+    pub max_height: Option<u32>,
+}
+
+/// Decoder and encoder threads each with [`ConvertOptions::low_power`].
+const LOW_POWER_THREADS: usize = 2;
+
+/// How much damage a conversion puts up with before giving up.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct ErrorPolicy {
+    /// Drop packets the demuxer already flagged as corrupt instead of
+    /// feeding them to a decoder.
+    pub skip_corrupt: bool,
+    /// Packets a decoder may reject before the conversion fails. Rejected
+    /// packets are dropped, which shows up as a glitch in the output.
+    pub max_decode_errors: usize,
+}
+
+impl Default for ErrorPolicy {
+    fn default() -> Self {
+        Self {
+            skip_corrupt: true,
+            max_decode_errors: 100,
+        }
+    }
+}
+
+/// Counts a rejected packet, failing once there were more than `policy` allows.
+fn tolerate(
+    err: ffmpeg::Error,
+    dropped: &mut usize,
+    policy: &ErrorPolicy,
+) -> Result<(), ffmpeg::Error> {
+    *dropped += 1;
+    if *dropped > policy.max_decode_errors {
+        return Err(err);
+    }
+    log::warn!("dropping packet the decoder rejected: {err}");
+    Ok(())
+}
+
+/// Bits per pixel x264 tends to stay under at the default CRF with the
+/// `medium` preset; deliberately on the generous side.
+const H264_BITS_PER_PIXEL: f64 = 0.1;
+/// The native AAC encoder's bitrate when none is set is 128k; leave headroom.
+const AAC_BITRATE: f64 = 192_000.0;
+
+/// Rough upper bound, in bytes, of what [`convert_to_mp4`] will write.
+pub fn estimate_output_size(
+    ictx: &ffmpeg::format::context::Input,
+    mapping: &StreamMapping,
+) -> u64 {
+    let duration = ictx.duration().max(0) as f64 / ffmpeg::ffi::AV_TIME_BASE as f64;
+    let container_rate = ictx.bit_rate().max(0) as f64;
+
+    let mut bit_rate = 0.0;
+    for map in &mapping.resolve(ictx).streams {
+        let Some(stream) = ictx.stream(map.index) else {
+            continue;
+        };
+        let params = stream.parameters();
+        let (source_rate, width, height) = unsafe {
+            let p = params.as_ptr();
+            ((*p).bit_rate as f64, (*p).width as f64, (*p).height as f64)
+        };
+
+        bit_rate += match (map.mode, params.medium()) {
+            // streams often don't know their own rate, the container does
+            (StreamMode::Copy, _) if source_rate > 0.0 => source_rate,
+            (StreamMode::Copy, _) => container_rate,
+            (StreamMode::Transcode, ffmpeg::media::Type::Video) => {
+                width * height * f64::from(stream.avg_frame_rate()) * H264_BITS_PER_PIXEL
+            }
+            (StreamMode::Transcode, ffmpeg::media::Type::Audio) => AAC_BITRATE,
+            _ => 0.0,
+        };
+    }
+
+    // plus some room for the container itself
+    (bit_rate * duration / 8.0 * 1.1) as u64
+}
+
+/// Refuses conversions that would run out of disk space halfway through.
+pub fn preflight<P: AsRef<std::path::Path> + ?Sized>(
+    input_path: &P,
+    output_dir: &std::path::Path,
+    mapping: &StreamMapping,
+) -> Result<(), Box<dyn Error>> {
+    // ffmpeg-next hands paths to libav as UTF-8 and panics on anything else
+    let input = input_path.as_ref();
+    if input.to_str().is_none() {
+        return Err(format!("{} is not valid Unicode", input.display()).into());
+    }
+    let ictx = crate::probe::open(input_path)?;
+    let needed = estimate_output_size(&ictx, mapping);
+    let available = fs4::available_space(output_dir)?;
+
+    if needed > available {
+        return Err(format!(
+            "not enough disk space in {}: the conversion needs about {} MB, but only {} MB are free",
+            output_dir.display(),
+            needed / 1_000_000,
+            available / 1_000_000
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Whether `stream` is a still picture like an album cover rather than video.
+fn is_attached_pic(stream: &ffmpeg::format::stream::Stream) -> bool {
+    stream
+        .disposition()
+        .contains(ffmpeg::format::stream::Disposition::ATTACHED_PIC)
+}
+
+/// The embedded cover of a music file, as the JPEG or PNG it was stored as.
+pub fn cover_art<P: AsRef<std::path::Path> + ?Sized>(
+    path: &P,
+) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+    let ictx = crate::probe::open(path)?;
+    let Some(stream) = ictx.streams().find(is_attached_pic) else {
+        return Ok(None);
+    };
+
+    let picture = unsafe { &(*stream.as_ptr()).attached_pic };
+    if picture.data.is_null() || picture.size <= 0 {
+        return Ok(None);
+    }
+    let data = unsafe { std::slice::from_raw_parts(picture.data, picture.size as usize) };
+    Ok(Some(data.to_vec()))
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StreamInfo {
+    pub index: usize,
+    pub medium: String,
+    pub codec: String,
+    pub language: Option<String>,
+    pub title: Option<String>,
+}
+
+pub fn probe_streams<P: AsRef<std::path::Path> + ?Sized>(
+    path: &P,
+) -> Result<Vec<StreamInfo>, Box<dyn Error>> {
+    let ictx = crate::probe::open(path)?;
+
+    Ok(ictx
+        .streams()
+        .map(|s| StreamInfo {
+            index: s.index(),
+            medium: format!("{:?}", s.parameters().medium()).to_lowercase(),
+            codec: s.parameters().id().name().to_string(),
+            language: s.metadata().get("language").map(String::from),
+            title: s.metadata().get("title").map(String::from),
+        })
+        .collect())
+}
+
+/// One H.264 encoder and the output stream it feeds.
+struct VideoSink {
+    encoder: ffmpeg::codec::encoder::Video,
+    output: usize,
+    stream: usize,
+}
+
+/// Where a stream goes: (output, stream index in that output) pairs.
+type Targets = Vec<(usize, usize)>;
+
+// Helper enum to hold state
+enum Transcoder {
+    /// Decoded and filtered once, then encoded once per output.
+    Video(
+        ffmpeg::codec::decoder::Video,
+        Vec<VideoSink>,
+        ffmpeg::Rational, // Input time base
+        Option<VideoFilter>,
+    ),
+    /// Encoded once; the packets are written to every output.
+    Audio(
+        ffmpeg::codec::decoder::Audio,
+        ffmpeg::codec::encoder::Audio,
+        Targets,
+        ffmpeg::Rational,
+    ),
+    /// Packets are remuxed untouched.
+    Copy(Targets, ffmpeg::Rational),
+}
+
+/// `width` x `height` scaled down to at most `max_height` lines, keeping the
+/// aspect ratio and the even sizes 4:2:0 needs.
+fn fit(width: u32, height: u32, max_height: Option<u32>) -> (u32, u32) {
+    match max_height {
+        Some(max) if height > max => {
+            let scaled = (width as u64 * max as u64 / height as u64) as u32;
+            (scaled.max(2) & !1, max.max(2) & !1)
+        }
+        _ => (width, height),
+    }
+}
+
+/// `on_progress` is called every few frames with the fraction done. It may
+/// block, which pauses the conversion.
+///
+/// Returns how many damaged packets were dropped along the way.
+pub fn convert_to_mp4<
+    PI: AsRef<std::path::Path> + ?Sized,
+    PO: AsRef<std::path::Path> + ?Sized,
+    F: FnMut(f64),
+>(
+    input_path: &PI,
+    output_path: &PO,
+    options: &ConvertOptions,
+    mut on_progress: F,
+) -> Result<usize, Box<dyn Error>> {
+    let policy = &options.errors;
+    let mut effects = crate::effects::create(&options.frame_filters)?;
+
+    // 1. Input Context
+    let mut ictx = crate::probe::open(input_path)?;
+
+    // 2. Output Contexts, the tee's after the main one
+    let mut outputs = vec![ffmpeg::format::output(output_path)?];
+    let mut max_heights = vec![options.max_height];
+    if let Some(tee) = &options.tee {
+        outputs.push(ffmpeg::format::output(&tee.path)?);
+        max_heights.push(tee.max_height);
+    }
+    let global_header = outputs.iter().any(|octx| {
+        octx.format()
+            .flags()
+            .contains(ffmpeg::format::flag::Flags::GLOBAL_HEADER)
+    });
+
+    let mapping = options.mapping.resolve(&ictx);
+
+    // Map input stream index to its Transcoder Context
+    let mut streamer = std::collections::HashMap::new();
+
+    // 3. Setup Streams & Transcoders
+    for map in &mapping.streams {
+        let istream = ictx
+            .stream(map.index)
+            .ok_or_else(|| format!("input has no stream #{}", map.index))?;
+        let stream_index = istream.index();
+        let istream_params = istream.parameters();
+        let medium = istream_params.medium();
+
+        let mut targets = Targets::new();
+        for (output, octx) in outputs.iter_mut().enumerate() {
+            let ostream_index = octx
+                .add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::None))?
+                .index();
+            targets.push((output, ostream_index));
+        }
+
+        if map.mode == StreamMode::Copy {
+            for &(output, ostream_index) in &targets {
+                let mut ostream = outputs[output].stream_mut(ostream_index).unwrap();
+                ostream.set_parameters(istream_params.clone());
+                // the input container's codec tag is usually meaningless in mp4
+                unsafe {
+                    (*ostream.parameters().as_mut_ptr()).codec_tag = 0;
+                }
+            }
+
+            streamer.insert(stream_index, Transcoder::Copy(targets, istream.time_base()));
+        } else if medium == ffmpeg::media::Type::Video {
+            // -- VIDEO TRANSCODER (H.264) --
+
+            // Decoder
+            let mut context_decoder =
+                ffmpeg::codec::context::Context::from_parameters(istream_params)?;
+            if options.low_power {
+                unsafe { (*context_decoder.as_mut_ptr()).thread_count = LOW_POWER_THREADS as i32 };
+            }
+            let decoder = context_decoder.decoder().video()?;
+
+            let mut filters = options.video_filters.clone();
+            if let Some(tonemap) = Some(&istream)
+                .filter(|s| crate::filter::is_hdr(s))
+                .and_then(|_| crate::filter::tonemap())
+            {
+                // first, so everything after works on SDR
+                filters.insert(0, tonemap.to_string());
+            } else if options.map_gamut && crate::filter::is_wide_gamut(&istream) {
+                filters.insert(0, crate::filter::GAMUT_MAP.to_string());
+            }
+            let filter = match filters.join(",") {
+                spec if spec.is_empty() => None,
+                spec => Some(VideoFilter::new(&spec, &decoder, istream.time_base())?),
+            };
+            // interpolation raises the frame rate
+            let frame_rate = match &filter {
+                Some(filter) => filter.frame_rate().or(decoder.frame_rate()),
+                None => decoder.frame_rate(),
+            };
+
+            // Encoders (H.264), one per output
+            let codec =
+                ffmpeg::encoder::find(ffmpeg::codec::Id::H264).ok_or("H.264 encoder not found")?;
+            let mut sinks = Vec::new();
+            for &(output, ostream_index) in &targets {
+                let context_encoder = ffmpeg::codec::context::Context::new_with_codec(codec);
+                let mut encoder = context_encoder.encoder().video()?;
+
+                // Set Encoder Parameters
+                let (width, height) = fit(decoder.width(), decoder.height(), max_heights[output]);
+                encoder.set_height(height);
+                encoder.set_width(width);
+                encoder.set_aspect_ratio(decoder.aspect_ratio());
+                encoder.set_format(ffmpeg::format::Pixel::YUV420P); // Standard for MP4 compatibility
+                encoder.set_frame_rate(frame_rate);
+                encoder.set_time_base(istream.time_base()); // Use input timebase
+
+                if global_header {
+                    encoder.set_flags(ffmpeg::codec::flag::Flags::GLOBAL_HEADER);
+                }
+
+                // Optional: Set H.264 specific options (presets)
+                let mut opts = ffmpeg::Dictionary::new();
+                if options.low_power {
+                    opts.set("preset", "veryfast");
+                    opts.set("threads", &LOW_POWER_THREADS.to_string());
+                } else {
+                    opts.set("preset", "medium");
+                }
+                let encoder = encoder.open_with(opts)?;
+
+                // Update output stream parameters to match encoder
+                let mut ostream = outputs[output].stream_mut(ostream_index).unwrap();
+                ostream.set_parameters(&encoder);
+
+                sinks.push(VideoSink {
+                    encoder,
+                    output,
+                    stream: ostream_index,
+                });
+            }
+
+            streamer.insert(
+                stream_index,
+                Transcoder::Video(decoder, sinks, istream.time_base(), filter),
+            );
+        } else if medium == ffmpeg::media::Type::Audio {
+            // -- AUDIO TRANSCODER (AAC) --
+
+            let context_decoder = ffmpeg::codec::context::Context::from_parameters(istream_params)?;
+            let decoder = context_decoder.decoder().audio()?;
+
+            let codec =
+                ffmpeg::encoder::find(ffmpeg::codec::Id::AAC).ok_or("AAC encoder not found")?;
+            let context_encoder = ffmpeg::codec::context::Context::new_with_codec(codec);
+            let mut encoder = context_encoder.encoder().audio()?;
+
+            // Set Encoder Parameters
+            encoder.set_rate(decoder.rate() as i32);
+            // ffmpeg-next handling of channel layouts can be tricky; using default/stereo is safest for a mimic
+            encoder.set_channel_layout(ffmpeg::channel_layout::ChannelLayout::STEREO);
+            encoder.set_format(ffmpeg::format::Sample::F32(
+                ffmpeg::format::sample::Type::Planar,
+            )); // AAC usually likes planar floats
+            encoder.set_time_base(ffmpeg::Rational::new(1, decoder.rate() as i32));
+
+            if global_header {
+                encoder.set_flags(ffmpeg::codec::flag::Flags::GLOBAL_HEADER);
+            }
+
+            let encoder = encoder.open()?;
+
+            // Update output stream parameters
+            for &(output, ostream_index) in &targets {
+                let mut ostream = outputs[output].stream_mut(ostream_index).unwrap();
+                ostream.set_parameters(&encoder);
+            }
+
+            streamer.insert(
+                stream_index,
+                Transcoder::Audio(decoder, encoder, targets, istream.time_base()),
+            );
+        } else {
+            // mp4 only takes mov_text subtitles, and those can be copied
+            return Err(format!(
+                "stream #{} ({medium:?}) can only be copied, not transcoded",
+                map.index
+            )
+            .into());
+        }
+    }
+
+    // 4. Write Headers
+    for octx in &mut outputs {
+        octx.write_header()?;
+    }
+
+    let mut fps = 0.0;
+    let mut fcount = 0;
+    let mut vlen = 0.0;
+    let mut i = 0;
+    let mut dropped = 0;
+
+    // 5. Transcoding Loop
+    for (stream, packet) in ictx.packets() {
+        if fps == 0.0 && fcount == 0 && vlen == 0.0 && i == 0 {
+            fps = stream.avg_frame_rate().numerator() as f64
+                / stream.avg_frame_rate().denominator() as f64;
+            fcount = stream.frames();
+            vlen = fcount as f64 / fps;
+        }
+
+        if let Some(transcoder) = streamer.get_mut(&stream.index()) {
+            if policy.skip_corrupt && packet.is_corrupt() {
+                dropped += 1;
+                continue;
+            }
+
+            match transcoder {
+                Transcoder::Video(decoder, sinks, in_time_base, filter) => {
+                    // Decode
+                    if let Err(e) = decoder.send_packet(&packet) {
+                        tolerate(e, &mut dropped, policy)?;
+                        continue;
+                    }
+                    let mut decoded_frame = ffmpeg::frame::Video::empty();
+                    while decoder.receive_frame(&mut decoded_frame).is_ok() {
+                        i += 1;
+                        // network streams often don't say how many frames they have
+                        if i % 5 == 0 && fcount > 0 {
+                            on_progress(i as f64 / fcount as f64);
+                        }
+
+                        let tb = *in_time_base;
+                        let Some(filter) = filter.as_mut() else {
+                            encode_video(&decoded_frame, sinks, tb, &mut effects, &mut outputs)?;
+                            continue;
+                        };
+                        filter.push(&decoded_frame)?;
+                        let filter_tb = filter.time_base();
+                        let mut filtered = ffmpeg::frame::Video::empty();
+                        while filter.pull(&mut filtered).is_ok() {
+                            filtered.set_pts(filtered.pts().map(|t| t.rescale(filter_tb, tb)));
+                            encode_video(&filtered, sinks, tb, &mut effects, &mut outputs)?;
+                        }
+                    }
+                }
+                Transcoder::Audio(decoder, encoder, targets, in_time_base) => {
+                    if let Err(e) = decoder.send_packet(&packet) {
+                        tolerate(e, &mut dropped, policy)?;
+                        continue;
+                    }
+                    let mut decoded_frame = ffmpeg::frame::Audio::empty();
+                    while decoder.receive_frame(&mut decoded_frame).is_ok() {
+                        encoder.send_frame(&decoded_frame)?;
+                        let mut encoded_packet = ffmpeg::Packet::empty();
+                        while encoder.receive_packet(&mut encoded_packet).is_ok() {
+                            write_packet(&encoded_packet, *in_time_base, targets, &mut outputs)?;
+                        }
+                    }
+                }
+                Transcoder::Copy(targets, in_time_base) => {
+                    write_packet(&packet, *in_time_base, targets, &mut outputs)?;
+                }
+            }
+        }
+    }
+
+    // 6. Flush Encoders
+    for (_, transcoder) in streamer.iter_mut() {
+        match transcoder {
+            Transcoder::Video(_, sinks, in_time_base, filter) => {
+                let tb = *in_time_base;
+                if let Some(filter) = filter {
+                    filter.flush()?;
+                    let filter_tb = filter.time_base();
+                    let mut filtered = ffmpeg::frame::Video::empty();
+                    while filter.pull(&mut filtered).is_ok() {
+                        filtered.set_pts(filtered.pts().map(|t| t.rescale(filter_tb, tb)));
+                        encode_video(&filtered, sinks, tb, &mut effects, &mut outputs)?;
+                    }
+                }
+                for sink in sinks.iter_mut() {
+                    sink.encoder.send_eof()?;
+                    let mut encoded_packet = ffmpeg::Packet::empty();
+                    while sink.encoder.receive_packet(&mut encoded_packet).is_ok() {
+                        let target = [(sink.output, sink.stream)];
+                        write_packet(&encoded_packet, tb, &target, &mut outputs)?;
+                    }
+                }
+            }
+            Transcoder::Audio(_, encoder, targets, in_time_base) => {
+                encoder.send_eof()?;
+                let mut encoded_packet = ffmpeg::Packet::empty();
+                while encoder.receive_packet(&mut encoded_packet).is_ok() {
+                    write_packet(&encoded_packet, *in_time_base, targets, &mut outputs)?;
+                }
+            }
+            Transcoder::Copy(..) => {}
+        }
+    }
+
+    println!();
+
+    // 7. Write Trailers
+    for octx in &mut outputs {
+        octx.write_trailer()?;
+    }
+
+    Ok(dropped)
+}
+
+/// Writes a copy of `packet`, timed in `time_base`, to each of `targets`.
+fn write_packet(
+    packet: &ffmpeg::Packet,
+    time_base: ffmpeg::Rational,
+    targets: &[(usize, usize)],
+    outputs: &mut [ffmpeg::format::context::Output],
+) -> Result<(), ffmpeg::Error> {
+    for &(output, stream) in targets {
+        let octx = &mut outputs[output];
+        let mut packet = packet.clone();
+        packet.rescale_ts(time_base, octx.stream(stream).unwrap().time_base());
+        packet.set_position(-1);
+        packet.set_stream(stream);
+        packet.write_interleaved(octx)?;
+    }
+    Ok(())
+}
+
+/// Runs the effects on `frame` once, then scales it for each H.264 encoder,
+/// encodes it and writes out whatever packets the encoders have ready.
+fn encode_video(
+    frame: &ffmpeg::frame::Video,
+    sinks: &mut [VideoSink],
+    in_time_base: ffmpeg::Rational,
+    effects: &mut [Box<dyn FrameFilter>],
+    outputs: &mut [ffmpeg::format::context::Output],
+) -> Result<(), Box<dyn Error>> {
+    let processed = match effects {
+        [] => None,
+        effects => Some(crate::effects::apply(frame, effects)?),
+    };
+    let frame = processed.as_ref().unwrap_or(frame);
+
+    for sink in sinks {
+        let (width, height) = (sink.encoder.width(), sink.encoder.height());
+        let mut sws_ctx = ffmpeg::software::scaling::context::Context::get(
+            frame.format(),
+            frame.width(),
+            frame.height(),
+            ffmpeg::format::Pixel::YUV420P,
+            width,
+            height,
+            ffmpeg::software::scaling::flag::Flags::BILINEAR,
+        )?;
+        let mut converted = ffmpeg::frame::Video::empty();
+        converted.set_width(width);
+        converted.set_height(height);
+        converted.set_format(ffmpeg::format::Pixel::YUV420P);
+
+        sws_ctx.run(frame, &mut converted)?;
+        // Rescale timestamps for the frame (Input -> Encoder)
+        converted.set_pts(frame.pts()); // Often needs rescaling here if bases differ significantly
+
+        // Encode
+        sink.encoder.send_frame(&converted)?;
+        let mut encoded_packet = ffmpeg::Packet::empty();
+        while sink.encoder.receive_packet(&mut encoded_packet).is_ok() {
+            // Rescale Packet Timestamp (Encoder -> Output)
+            let target = [(sink.output, sink.stream)];
+            write_packet(&encoded_packet, in_time_base, &target, outputs)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Verification {
+    pub ok: bool,
+    pub problems: Vec<String>,
+    pub source_duration: f64,
+    pub output_duration: f64,
+}
+
+/// Re-opens a finished conversion and checks that it is complete: the
+/// duration has to match the source, and the first and last GOP of the video
+/// have to decode cleanly.
+pub fn verify_output<
+    PI: AsRef<std::path::Path> + ?Sized,
+    PO: AsRef<std::path::Path> + ?Sized,
+>(
+    input_path: &PI,
+    output_path: &PO,
+) -> Result<Verification, Box<dyn Error>> {
+    let duration = |ictx: &ffmpeg::format::context::Input| {
+        ictx.duration().max(0) as f64 / ffmpeg::ffi::AV_TIME_BASE as f64
+    };
+
+    let source_duration = duration(&crate::probe::open(input_path)?);
+    let mut octx = ffmpeg::format::input(output_path)?;
+    let output_duration = duration(&octx);
+
+    let mut problems = Vec::new();
+
+    // encoder delay and rounding move the end a little, a second or 1% is fine
+    let tolerance = (source_duration * 0.01).max(1.0);
+    if source_duration > 0.0 && (source_duration - output_duration).abs() > tolerance {
+        problems.push(format!(
+            "duration is {output_duration:.2}s, source is {source_duration:.2}s"
+        ));
+    }
+
+    if let Some(stream) = octx.streams().best(ffmpeg::media::Type::Video) {
+        let index = stream.index();
+        let context_decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+        let mut decoder = context_decoder.decoder().video()?;
+
+        match decode_gop(&mut octx, index, &mut decoder, true) {
+            Ok(0) => problems.push("first GOP has no frames".into()),
+            Ok(_) => {}
+            Err(e) => problems.push(format!("first GOP does not decode: {e}")),
+        }
+
+        // the last few seconds certainly contain the last keyframe
+        let ts = ((output_duration - 10.0).max(0.0) * ffmpeg::ffi::AV_TIME_BASE as f64) as i64;
+        octx.seek(ts, ..=ts)?;
+        decoder.flush();
+
+        match decode_gop(&mut octx, index, &mut decoder, false) {
+            Ok(0) => problems.push("last GOP has no frames".into()),
+            Ok(_) => {}
+            Err(e) => problems.push(format!("last GOP does not decode: {e}")),
+        }
+    }
+
+    Ok(Verification {
+        ok: problems.is_empty(),
+        problems,
+        source_duration,
+        output_duration,
+    })
+}
+
+/// Decodes from the current position, either up to the next keyframe or to
+/// the end of the file, returning the number of frames decoded.
+fn decode_gop(
+    ictx: &mut ffmpeg::format::context::Input,
+    index: usize,
+    decoder: &mut ffmpeg::codec::decoder::Video,
+    stop_at_keyframe: bool,
+) -> Result<usize, ffmpeg::Error> {
+    let mut frames = 0;
+    let mut frame = ffmpeg::frame::Video::empty();
+
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != index {
+            continue;
+        }
+        if stop_at_keyframe && frames > 0 && packet.is_key() {
+            return Ok(frames);
+        }
+
+        decoder.send_packet(&packet)?;
+        while decoder.receive_frame(&mut frame).is_ok() {
+            frames += 1;
+        }
+    }
+
+    decoder.send_eof()?;
+    while decoder.receive_frame(&mut frame).is_ok() {
+        frames += 1;
+    }
+
+    Ok(frames)
+}
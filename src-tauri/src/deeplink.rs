@@ -0,0 +1,45 @@
+use std::path::{Path, PathBuf};
+use tauri::Url;
+
+pub const SCHEME: &str = "simplevidview";
+
+/// `simplevidview://open?path=<file>&t=<seconds>`, with `t` optional.
+pub struct Link {
+    pub path: PathBuf,
+    pub time: Option<f64>,
+}
+
+pub fn build(path: &Path, time: f64) -> String {
+    let mut url = Url::parse(&format!("{SCHEME}://open")).unwrap();
+    url.query_pairs_mut()
+        .append_pair("path", &path.to_string_lossy())
+        .append_pair("t", &format!("{}", (time * 1000.0).round() / 1000.0));
+    url.into()
+}
+
+pub fn parse(url: &Url) -> Option<Link> {
+    if url.scheme() != SCHEME || url.host_str() != Some("open") {
+        return None;
+    }
+
+    let mut path = None;
+    let mut time = None;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "path" => path = Some(PathBuf::from(value.as_ref())),
+            "t" => time = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    Some(Link { path: path?, time })
+}
+
+/// Splits a trailing `#t=90`, `?t=90` or `&t=90` off a path or URL given on
+/// the command line. The time may also be written `1:30` or `90s`.
+pub fn split_time(arg: &str) -> Option<(&str, f64)> {
+    let at = arg.rfind(['#', '?', '&'])?;
+    let value = arg[at + 1..].strip_prefix("t=")?;
+    let time = crate::chapters::parse_clock(value.trim_end_matches('s'))?;
+    Some((&arg[..at], time))
+}
@@ -0,0 +1,148 @@
+use ffmpeg_next::{self as ffmpeg, format::Pixel, frame::Video, software::scaling};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+/// A decoded frame as packed 8-bit RGB, `width * 3` bytes per row.
+pub struct RgbFrame {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// An effect applied to every frame of a conversion, after the libavfilter
+/// chain. Unlike `video_filters` these are plain Rust, so an effect only needs
+/// to know about RGB pixels.
+pub trait FrameFilter: Send {
+    fn process(&mut self, frame: &mut RgbFrame);
+}
+
+pub type Constructor = fn() -> Box<dyn FrameFilter>;
+
+static REGISTRY: LazyLock<Mutex<HashMap<String, Constructor>>> = LazyLock::new(Default::default);
+
+/// Makes an effect available under `name` for the `frame_filters` option.
+/// Registering a name again replaces the earlier effect. Builds embedding the
+/// player add their own before calling [`crate::run`], which registers the
+/// built-in ones first only where the name is still free.
+pub fn register(name: &str, constructor: Constructor) {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .insert(name.to_ascii_lowercase(), constructor);
+}
+
+pub(crate) fn register_builtins() {
+    let builtins: [(&str, Constructor); 3] = [
+        ("grayscale", || Box::new(Grayscale)),
+        ("invert", || Box::new(Invert)),
+        ("sepia", || Box::new(Sepia)),
+    ];
+    let mut registry = REGISTRY.lock().unwrap();
+    for (name, constructor) in builtins {
+        registry.entry(name.into()).or_insert(constructor);
+    }
+}
+
+/// Builds the effects named in `names`, failing on the first unknown one.
+pub(crate) fn create(names: &[String]) -> Result<Vec<Box<dyn FrameFilter>>, String> {
+    let registry = REGISTRY.lock().unwrap();
+    names
+        .iter()
+        .map(|name| match registry.get(&name.to_ascii_lowercase()) {
+            Some(constructor) => Ok(constructor()),
+            None => Err(format!("unknown frame filter: {name}")),
+        })
+        .collect()
+}
+
+/// Runs `filters` over `frame` and returns the result as an RGB24 frame with
+/// the same timestamp.
+pub(crate) fn apply(
+    frame: &Video,
+    filters: &mut [Box<dyn FrameFilter>],
+) -> Result<Video, ffmpeg::Error> {
+    let (width, height) = (frame.width(), frame.height());
+    let mut rgb = Video::new(Pixel::RGB24, width, height);
+    scaling::Context::get(
+        frame.format(),
+        width,
+        height,
+        Pixel::RGB24,
+        width,
+        height,
+        scaling::Flags::BILINEAR,
+    )?
+    .run(frame, &mut rgb)?;
+
+    // frame rows may be padded, RgbFrame rows are not
+    let row = width as usize * 3;
+    let stride = rgb.stride(0);
+    let mut image = RgbFrame {
+        width,
+        height,
+        data: rgb
+            .data(0)
+            .chunks(stride)
+            .take(height as usize)
+            .flat_map(|line| &line[..row])
+            .copied()
+            .collect(),
+    };
+
+    for filter in filters.iter_mut() {
+        filter.process(&mut image);
+    }
+
+    for (line, pixels) in rgb
+        .data_mut(0)
+        .chunks_mut(stride)
+        .zip(image.data.chunks(row))
+    {
+        line[..row].copy_from_slice(pixels);
+    }
+    rgb.set_pts(frame.pts());
+
+    Ok(rgb)
+}
+
+struct Grayscale;
+
+impl FrameFilter for Grayscale {
+    fn process(&mut self, frame: &mut RgbFrame) {
+        for px in frame.data.chunks_exact_mut(3) {
+            let y = luma(px);
+            px.fill(y);
+        }
+    }
+}
+
+struct Invert;
+
+impl FrameFilter for Invert {
+    fn process(&mut self, frame: &mut RgbFrame) {
+        for v in frame.data.iter_mut() {
+            *v = 255 - *v;
+        }
+    }
+}
+
+struct Sepia;
+
+impl FrameFilter for Sepia {
+    fn process(&mut self, frame: &mut RgbFrame) {
+        for px in frame.data.chunks_exact_mut(3) {
+            let y = luma(px) as f32;
+            px[0] = (y * 1.07).min(255.0) as u8;
+            px[1] = (y * 0.74) as u8;
+            px[2] = (y * 0.43) as u8;
+        }
+    }
+}
+
+/// BT.601 luma of an RGB pixel.
+fn luma(px: &[u8]) -> u8 {
+    let [r, g, b] = [px[0], px[1], px[2]].map(f32::from);
+    (0.299 * r + 0.587 * g + 0.114 * b).round() as u8
+}
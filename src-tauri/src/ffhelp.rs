@@ -1,3 +1,4 @@
+use crate::filter::VideoFilter;
 use ffmpeg_next::{self as ffmpeg, decoder, frame::Video, media, software, Rational};
 use std::{error::Error, path::Path};
 
@@ -11,6 +12,8 @@ pub struct FFHelp {
     ictx: ffmpeg::format::context::Input,
     video_stream_index: usize,
     decoder: decoder::Video,
+    /// Tone mapping for HDR sources, run before `scalar`.
+    tonemap: Option<VideoFilter>,
     scalar: software::scaling::Context,
     w: u32,
     h: u32,
@@ -23,6 +26,14 @@ unsafe impl Send for FFHelp {}
 
 impl FFHelp {
     pub fn open<P>(path: &P) -> BasicResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::open_scaled(path, u32::MAX)
+    }
+
+    /// Like [`FFHelp::open`], but frames come out no wider than `max_width`.
+    pub fn open_scaled<P>(path: &P, max_width: u32) -> BasicResult<Self>
     where
         P: AsRef<Path>,
     {
@@ -33,7 +44,7 @@ impl FFHelp {
         let stream = ictx
             .streams()
             .best(media::Type::Video)
-            .expect("no video stream");
+            .ok_or("no video stream")?;
 
         let video_stream_index = stream.index();
 
@@ -41,13 +52,23 @@ impl FFHelp {
             ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
         let decoder = context_decoder.decoder().video()?;
 
-        let w = decoder.width();
-        let h = decoder.height();
+        let w = decoder.width().min(max_width);
+        // keep the aspect ratio, rounded to an even height
+        let h = (decoder.height() as u64 * w as u64 / decoder.width().max(1) as u64) as u32 & !1;
+
+        let tonemap = match crate::filter::tonemap().filter(|_| crate::filter::is_hdr(&stream)) {
+            Some(spec) => Some(VideoFilter::new(spec, &decoder, stream.time_base())?),
+            None => None,
+        };
+        let format = match tonemap {
+            Some(_) => ffmpeg::format::Pixel::YUV420P,
+            None => decoder.format(),
+        };
 
         let scalar = software::scaling::context::Context::get(
-            decoder.format(),
-            w,
-            h,
+            format,
+            decoder.width(),
+            decoder.height(),
             ffmpeg::format::Pixel::RGBA,
             w,
             h,
@@ -62,30 +83,56 @@ impl FFHelp {
             ictx,
             video_stream_index,
             decoder,
+            tonemap,
             scalar,
-            w: w,
-            h: h,
+            w,
+            h,
             time_base: tb,
             fps,
         })
     }
 
+    /// Length of the file in seconds.
+    pub fn duration(&self) -> f64 {
+        self.ictx.duration().max(0) as f64 / ffmpeg::ffi::AV_TIME_BASE as f64
+    }
+
     pub fn total_frames(&self) -> usize {
-        let duration = self.ictx.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64;
-        (duration * self.fps).ceil() as usize
+        (self.duration() * self.fps).ceil() as usize
+    }
+
+    /// The frame shown at `time` seconds.
+    pub fn frame_index(&self, time: f64) -> usize {
+        // a little slack so a timestamp a hair below the frame start still hits it
+        (time.max(0.0) * self.fps + 1e-3) as usize
     }
 
+    /// Seeks to the last keyframe at or before `target`; callers decode
+    /// forward from there. Seeking without an upper bound may land on a later
+    /// keyframe, which makes stepping backwards skip frames.
     fn seek_to_frame(&mut self, target: usize) -> BasicResult<()> {
-        let ts = (target as f64 / self.fps) / rational_to_f64(self.time_base);
-        self.ictx.seek(ts as i64, ..)?;
+        // no stream is given to seek, so the timestamp is in AV_TIME_BASE units
+        let ts = (target as f64 / self.fps * ffmpeg::ffi::AV_TIME_BASE as f64) as i64;
+        self.ictx.seek(ts, ..=ts)?;
         self.decoder.flush();
         Ok(())
     }
 
     pub fn get_frame(&mut self, frame_index: usize) -> BasicResult<Vec<u8>> {
+        self.decode_from_seek(frame_index, true)
+    }
+
+    /// The keyframe at or before `frame_index`. Much cheaper than
+    /// [`FFHelp::get_frame`] since nothing past the keyframe is decoded.
+    pub fn get_keyframe(&mut self, frame_index: usize) -> BasicResult<Vec<u8>> {
+        self.decode_from_seek(frame_index, false)
+    }
+
+    fn decode_from_seek(&mut self, frame_index: usize, exact: bool) -> BasicResult<Vec<u8>> {
         self.seek_to_frame(frame_index)?;
 
         let mut decoded = Video::empty();
+        let mut mapped = Video::empty();
         let mut rgba = Video::empty();
 
         for (stream, packet) in self.ictx.packets() {
@@ -97,8 +144,16 @@ impl FFHelp {
                     let current_frame =
                         (pts as f64 * rational_to_f64(self.time_base) * self.fps) as usize;
 
-                    if current_frame >= frame_index {
-                        self.scalar.run(&decoded, &mut rgba)?;
+                    if current_frame >= frame_index || !exact {
+                        let frame = match &mut self.tonemap {
+                            Some(tonemap) => {
+                                tonemap.push(&decoded)?;
+                                tonemap.pull(&mut mapped)?;
+                                &mapped
+                            }
+                            None => &decoded,
+                        };
+                        self.scalar.run(frame, &mut rgba)?;
 
                         let stride = rgba.stride(0);
                         let data = rgba.data(0);
@@ -0,0 +1,233 @@
+use ffmpeg_next::{self as ffmpeg, filter, frame::Video, media, Rational};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Deinterlaces frames flagged as interlaced and passes progressive ones
+/// through untouched, one frame out per frame in.
+pub const DEINTERLACE: &str = "bwdif=mode=send_frame:deint=interlaced";
+
+/// Maps PQ or HLG HDR down to BT.709 SDR, in the 8 bit 4:2:0 the H.264
+/// encoder takes. Without it HDR comes out washed out.
+const TONEMAP: &str = "zscale=t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,\
+    tonemap=tonemap=hable:desat=0,zscale=t=bt709:m=bt709:r=tv,format=yuv420p";
+
+/// Maps BT.2020 SDR onto BT.709, what a standard monitor shows. Shown as is,
+/// wide-gamut colours come out oversaturated.
+pub const GAMUT_MAP: &str = "colorspace=all=bt709:iall=bt2020:format=yuv420p";
+
+/// The [`TONEMAP`] chain, if this libavfilter has what it needs: `zscale`
+/// only exists in builds with zimg.
+pub fn tonemap() -> Option<&'static str> {
+    let available = filter::find("zscale").is_some() && filter::find("tonemap").is_some();
+    if !available {
+        log::warn!("HDR is shown as is, this ffmpeg has no zscale or tonemap filter");
+    }
+    available.then_some(TONEMAP)
+}
+
+/// The colours a display can show, as CSS's `color-gamut` media query tells
+/// them apart.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Gamut {
+    #[default]
+    Srgb,
+    /// DCI-P3; the webview spreads BT.709 over it by itself.
+    P3,
+    Rec2020,
+}
+
+impl Gamut {
+    /// Whether BT.2020 sources need [`GAMUT_MAP`] to look right here.
+    pub fn needs_mapping(self) -> bool {
+        self != Gamut::Rec2020
+    }
+}
+
+/// How [`Interpolation`] makes up the frames in between.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InterpolationMode {
+    /// Motion compensated, smooth but far slower than realtime.
+    #[default]
+    Motion,
+    /// Cross-fades neighbouring frames, cheap but ghosts on fast motion.
+    Blend,
+}
+
+/// `[interpolation]` in the config: raising the frame rate, e.g. showing
+/// 24 fps films at 60 fps for smoother motion.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct Interpolation {
+    /// Whether it starts on; toggled at runtime with Shift+D.
+    pub enabled: bool,
+    pub fps: u32,
+    pub mode: InterpolationMode,
+}
+
+impl Default for Interpolation {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fps: 60,
+            mode: InterpolationMode::default(),
+        }
+    }
+}
+
+impl Interpolation {
+    /// The filter for [`crate::convert::ConvertOptions::video_filters`].
+    pub fn spec(&self) -> String {
+        match self.mode {
+            InterpolationMode::Motion => format!(
+                "minterpolate=fps={}:mi_mode=mci:mc_mode=aobmc:me_mode=bidir:vsbmc=1",
+                self.fps
+            ),
+            InterpolationMode::Blend => format!("framerate=fps={}", self.fps),
+        }
+    }
+}
+
+/// A libavfilter chain for decoded video, like ffmpeg's `-vf`.
+pub struct VideoFilter {
+    graph: filter::Graph,
+}
+
+impl VideoFilter {
+    /// `spec` is in ffmpeg's filtergraph syntax, `time_base` is the one of
+    /// the frames that will be pushed.
+    pub fn new(
+        spec: &str,
+        decoder: &ffmpeg::decoder::Video,
+        time_base: Rational,
+    ) -> Result<Self, ffmpeg::Error> {
+        let aspect = match decoder.aspect_ratio() {
+            a if a.numerator() > 0 => a,
+            _ => Rational::new(1, 1),
+        };
+        let args = format!(
+            "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{}",
+            decoder.width(),
+            decoder.height(),
+            ffmpeg::ffi::AVPixelFormat::from(decoder.format()) as i32,
+            time_base.numerator(),
+            time_base.denominator(),
+            aspect.numerator(),
+            aspect.denominator(),
+        );
+
+        let mut graph = filter::Graph::new();
+        let buffer = filter::find("buffer").ok_or(ffmpeg::Error::FilterNotFound)?;
+        let buffersink = filter::find("buffersink").ok_or(ffmpeg::Error::FilterNotFound)?;
+        graph.add(&buffer, "in", &args)?;
+        graph.add(&buffersink, "out", "")?;
+        graph.output("in", 0)?.input("out", 0)?.parse(spec)?;
+        graph.validate()?;
+
+        Ok(Self { graph })
+    }
+
+    /// Time base of the frames that come out. Filters that change the frame
+    /// rate change it too.
+    pub fn time_base(&self) -> Rational {
+        let sink = self.graph.get("out").unwrap();
+        unsafe { ffmpeg::ffi::av_buffersink_get_time_base(sink.as_ptr()).into() }
+    }
+
+    /// Frame rate of what comes out, if the filters know it.
+    pub fn frame_rate(&self) -> Option<Rational> {
+        let sink = self.graph.get("out").unwrap();
+        let rate: Rational =
+            unsafe { ffmpeg::ffi::av_buffersink_get_frame_rate(sink.as_ptr()).into() };
+        Some(rate).filter(|r| r.numerator() > 0)
+    }
+
+    pub fn push(&mut self, frame: &Video) -> Result<(), ffmpeg::Error> {
+        self.graph.get("in").unwrap().source().add(frame)
+    }
+
+    /// Fails with `EAGAIN` once no more frames are ready.
+    pub fn pull(&mut self, frame: &mut Video) -> Result<(), ffmpeg::Error> {
+        self.graph.get("out").unwrap().sink().frame(frame)
+    }
+
+    /// Signals the end of input so frames the filters held back come out.
+    pub fn flush(&mut self) -> Result<(), ffmpeg::Error> {
+        self.graph.get("in").unwrap().source().flush()
+    }
+}
+
+/// Checks that `spec` is a filter chain libavfilter can build, so typos
+/// show up before anything is converted.
+pub fn check(spec: &str) -> Result<(), ffmpeg::Error> {
+    ffmpeg::init()?;
+    let mut graph = filter::Graph::new();
+    let buffer = filter::find("buffer").ok_or(ffmpeg::Error::FilterNotFound)?;
+    let buffersink = filter::find("buffersink").ok_or(ffmpeg::Error::FilterNotFound)?;
+    let args = format!(
+        "video_size=1920x1080:pix_fmt={}:time_base=1/25:pixel_aspect=1/1",
+        ffmpeg::ffi::AVPixelFormat::AV_PIX_FMT_YUV420P as i32
+    );
+    graph.add(&buffer, "in", &args)?;
+    graph.add(&buffersink, "out", "")?;
+    graph.output("in", 0)?.input("out", 0)?.parse(spec)?;
+    graph.validate()
+}
+
+/// Whether `stream` is HDR, going by its transfer function: PQ as in HDR10
+/// and Dolby Vision, or HLG. A high bit depth alone is no reason to tone
+/// map, 10 bit SDR scales fine.
+pub fn is_hdr(stream: &ffmpeg::format::stream::Stream) -> bool {
+    let transfer = unsafe { (*stream.parameters().as_ptr()).color_trc };
+    matches!(
+        transfer,
+        ffmpeg::ffi::AVColorTransferCharacteristic::AVCOL_TRC_SMPTE2084
+            | ffmpeg::ffi::AVColorTransferCharacteristic::AVCOL_TRC_ARIB_STD_B67
+    )
+}
+
+/// Whether `stream` has BT.2020 primaries. HDR has them too, but
+/// [`TONEMAP`] maps those already.
+pub fn is_wide_gamut(stream: &ffmpeg::format::stream::Stream) -> bool {
+    let primaries = unsafe { (*stream.parameters().as_ptr()).color_primaries };
+    primaries == ffmpeg::ffi::AVColorPrimaries::AVCOL_PRI_BT2020 && !is_hdr(stream)
+}
+
+/// Whether the main video stream of `path` is wide-gamut SDR, see
+/// [`is_wide_gamut`].
+pub fn is_wide_gamut_file<P: AsRef<Path> + ?Sized>(path: &P) -> bool {
+    let Ok(ictx) = crate::probe::open(path) else {
+        return false;
+    };
+    ictx.streams()
+        .best(media::Type::Video)
+        .is_some_and(|stream| is_wide_gamut(&stream))
+}
+
+/// Whether the main video stream of `path` is HDR, see [`is_hdr`].
+pub fn is_hdr_file<P: AsRef<Path> + ?Sized>(path: &P) -> bool {
+    let Ok(ictx) = crate::probe::open(path) else {
+        return false;
+    };
+    ictx.streams()
+        .best(media::Type::Video)
+        .is_some_and(|stream| is_hdr(&stream))
+}
+
+/// Whether the container flags the main video stream as interlaced.
+pub fn is_interlaced<P: AsRef<Path> + ?Sized>(path: &P) -> bool {
+    let Ok(ictx) = crate::probe::open(path) else {
+        return false;
+    };
+    let Some(stream) = ictx.streams().best(media::Type::Video) else {
+        return false;
+    };
+
+    let field_order = unsafe { (*stream.parameters().as_ptr()).field_order };
+    !matches!(
+        field_order,
+        ffmpeg::ffi::AVFieldOrder::AV_FIELD_PROGRESSIVE
+            | ffmpeg::ffi::AVFieldOrder::AV_FIELD_UNKNOWN
+    )
+}
@@ -0,0 +1,73 @@
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the source is checked for new data.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Checks without growth after which the file counts as finished.
+const IDLE_POLLS: u32 = 5;
+
+/// Keeps `./v.mp4` in step with a source that is still being written, such
+/// as a download or a recording. New bytes are appended as they show up and
+/// `source-grew` is emitted with the new duration; once the file has been
+/// quiet for a while it is copied once more, since writers like to patch
+/// the header when they finish, and `source-done` is emitted.
+///
+/// Files that aren't growing cost a few `stat` calls before this gives up.
+pub fn spawn(app: AppHandle, source: PathBuf) {
+    std::thread::spawn(move || {
+        let mut copied = std::fs::metadata("./v.mp4").map(|m| m.len()).unwrap_or(0);
+        let mut grew = false;
+        let mut idle = 0;
+
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+
+            // stop following once something else was opened
+            let state = app.state::<crate::AppState>();
+            if state.source.lock().unwrap().as_ref() != Some(&source) {
+                return;
+            }
+
+            let len = std::fs::metadata(&source).map(|m| m.len()).unwrap_or(0);
+            if len > copied {
+                if let Err(e) = append(&source, copied) {
+                    log::warn!("could not follow {}: {e}", source.display());
+                    return;
+                }
+                copied = len;
+                grew = true;
+                idle = 0;
+                if let Some(duration) = crate::media_duration(&source) {
+                    app.emit("source-grew", duration).unwrap();
+                }
+            } else {
+                idle += 1;
+                if idle < IDLE_POLLS {
+                    continue;
+                }
+                if grew {
+                    if let Err(e) = std::fs::copy(&source, "./v.mp4") {
+                        log::warn!("could not copy {}: {e}", source.display());
+                    }
+                    app.emit("source-done", ()).unwrap();
+                }
+                return;
+            }
+        }
+    });
+}
+
+/// Appends everything in `source` past `from` to `./v.mp4`.
+fn append(source: &Path, from: u64) -> std::io::Result<()> {
+    let mut src = std::fs::File::open(source)?;
+    src.seek(SeekFrom::Start(from))?;
+    let mut dst = std::fs::OpenOptions::new().append(true).open("./v.mp4")?;
+
+    let mut buf = Vec::new();
+    src.read_to_end(&mut buf)?;
+    dst.write_all(&buf)
+}
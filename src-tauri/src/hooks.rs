@@ -0,0 +1,77 @@
+use serde::Deserialize;
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+use tauri::{AppHandle, Manager};
+
+/// `[hooks]` table: shell commands run when something happens in the
+/// player. Each gets a JSON object describing the event on stdin, with the
+/// event name under `"event"`.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+    pub on_file_open: Option<String>,
+    pub on_playback_finished: Option<String>,
+    pub on_screenshot: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+pub enum Event {
+    FileOpen,
+    PlaybackFinished,
+    Screenshot,
+}
+
+impl Event {
+    fn name(self) -> &'static str {
+        match self {
+            Event::FileOpen => "file-open",
+            Event::PlaybackFinished => "playback-finished",
+            Event::Screenshot => "screenshot",
+        }
+    }
+}
+
+/// Runs the hook for `event`, if one is configured, without waiting for it.
+pub fn run(app: &AppHandle, event: Event, mut context: serde_json::Value) {
+    let hooks = &app.state::<crate::config::Config>().hooks;
+    let command = match event {
+        Event::FileOpen => &hooks.on_file_open,
+        Event::PlaybackFinished => &hooks.on_playback_finished,
+        Event::Screenshot => &hooks.on_screenshot,
+    };
+    let Some(command) = command.clone() else {
+        return;
+    };
+    context["event"] = event.name().into();
+
+    std::thread::spawn(move || {
+        if let Err(e) = run_command(&command, &context.to_string()) {
+            log::warn!("{} hook `{command}` failed: {e}", event.name());
+        }
+    });
+}
+
+fn run_command(command: &str, input: &str) -> std::io::Result<()> {
+    let mut child = if cfg!(windows) {
+        Command::new("cmd")
+            .args(["/C", command])
+            .stdin(Stdio::piped())
+            .spawn()?
+    } else {
+        Command::new("sh")
+            .args(["-c", command])
+            .stdin(Stdio::piped())
+            .spawn()?
+    };
+
+    // a hook that doesn't read its input closes the pipe early, that's fine
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("exited with {status}")));
+    }
+    Ok(())
+}
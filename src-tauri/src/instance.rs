@@ -0,0 +1,46 @@
+use clap::Parser;
+use std::path::PathBuf;
+use tauri::{plugin::TauriPlugin, AppHandle, Manager, Wry};
+
+/// Makes later launches hand their command line to the player that is
+/// already running and exit. tauri-plugin-single-instance talks over a
+/// channel only the same user's processes can reach (a named pipe on
+/// Windows, the session bus on Linux), so nobody else can make the player
+/// open files. Subcommands and `--convert` return before the plugin runs
+/// and never forward.
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_single_instance::init(|app, argv, cwd| {
+        if let Err(e) = take_over(app, argv, PathBuf::from(cwd)) {
+            log::warn!("could not take over a launch: {e}");
+        }
+    })
+}
+
+/// Opens what a later launch was given here, in the existing window.
+fn take_over(
+    app: &AppHandle,
+    argv: Vec<String>,
+    cwd: PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut player = match crate::cli::Cli::try_parse_from(argv) {
+        Ok(cli) if cli.command.is_none() => cli.player,
+        _ => return Err("unusable command line".into()),
+    };
+    for input in &mut player.inputs {
+        let path = PathBuf::from(&*input);
+        if path.is_relative() && cwd.join(&path).exists() {
+            *input = cwd.join(path).into_os_string();
+        }
+    }
+
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+        if player.fullscreen {
+            let _ = window.set_fullscreen(true);
+        }
+    }
+    crate::open_inputs(app, &player);
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use crate::phash;
+use std::{
+    collections::HashMap,
+    error::Error,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Seconds between hashed frames.
+const INTERVAL: f64 = 1.0;
+/// Intros are looked for this far into each episode.
+const SEARCH: f64 = 600.0;
+/// Frames whose hashes differ in at most this many bits are the same shot.
+const MATCH_BITS: u32 = 8;
+/// Shorter shared stretches are recaps or logos rather than an intro.
+const MIN_LENGTH: f64 = 15.0;
+/// A run survives this many mismatched samples in a row, for fades that
+/// don't line up exactly.
+const MAX_MISSES: usize = 2;
+
+/// Intros found so far, so going back and forth in a playlist doesn't
+/// search again. `None` means the file was searched and has none.
+#[derive(Default)]
+pub struct Intros {
+    found: Mutex<HashMap<PathBuf, Option<(f64, f64)>>>,
+}
+
+impl Intros {
+    pub fn get(&self, episode: &Path) -> Option<Option<(f64, f64)>> {
+        self.found.lock().unwrap().get(episode).copied()
+    }
+
+    pub fn insert(&self, episode: &Path, intro: Option<(f64, f64)>) {
+        self.found
+            .lock()
+            .unwrap()
+            .insert(episode.to_path_buf(), intro);
+    }
+}
+
+/// Start and end, in `episode`, of the longest stretch near the start that
+/// also appears in `other`.
+pub fn find(
+    episode: &Path,
+    other: &Path,
+    mut checkpoint: impl FnMut(),
+) -> BasicResult<Option<(f64, f64)>> {
+    let a = phash::timeline(episode, INTERVAL, SEARCH, &mut checkpoint)?;
+    let b = phash::timeline(other, INTERVAL, SEARCH, &mut checkpoint)?;
+
+    // (first, last) sample index in `a` of the best run
+    let mut best: Option<(usize, usize)> = None;
+    let length = |(first, last): (usize, usize)| a[last].0 - a[first].0;
+
+    // slide `b` along `a` and follow runs of matching frames at each offset
+    for offset in -(b.len() as isize)..a.len() as isize {
+        let mut run: Option<(usize, usize)> = None;
+        let mut misses = 0;
+        for i in offset.max(0) as usize..a.len() {
+            let Some(&(_, hb)) = usize::try_from(i as isize - offset)
+                .ok()
+                .and_then(|j| b.get(j))
+            else {
+                break;
+            };
+            if (a[i].1 ^ hb).count_ones() <= MATCH_BITS {
+                run = Some((run.map_or(i, |r| r.0), i));
+                misses = 0;
+            } else if run.is_some() {
+                misses += 1;
+                if misses > MAX_MISSES {
+                    if best.is_none_or(|b| length(run.unwrap()) > length(b)) {
+                        best = run;
+                    }
+                    run = None;
+                }
+            }
+        }
+        if let Some(run) = run {
+            if best.is_none_or(|b| length(run) > length(b)) {
+                best = Some(run);
+            }
+        }
+    }
+
+    Ok(best
+        .filter(|&run| length(run) >= MIN_LENGTH)
+        .map(|(first, last)| (a[first].0, a[last].0 + INTERVAL)))
+}
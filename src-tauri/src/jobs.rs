@@ -0,0 +1,133 @@
+use crate::avlog;
+use std::{
+    fmt::Display,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Condvar, Mutex,
+    },
+};
+
+/// How many captured log lines go into a failure report.
+const REPORT_LINES: usize = 5;
+
+/// Background ffmpeg work, lowest first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Conversions nobody is waiting for yet, e.g. from the watch folder.
+    Batch,
+    /// Seek bar previews: ahead of batch work, but never in the way of the
+    /// file being played.
+    Preview,
+    /// Conversion of the file the user just opened.
+    Current,
+}
+
+/// Keeps track of running jobs so that lower priority ones can step aside.
+/// Preemption is cooperative: jobs call [`Job::checkpoint`] regularly and
+/// block there while anything more important is running.
+#[derive(Default)]
+pub struct Scheduler {
+    running: Mutex<Vec<(u64, Priority)>>,
+    /// Files and folders running jobs are writing, by job id.
+    writing: Mutex<Vec<(u64, PathBuf)>>,
+    changed: Condvar,
+    next_id: AtomicU64,
+}
+
+impl Scheduler {
+    /// Registers a job running on the calling thread. libav messages logged
+    /// on this thread are collected into the job until it is dropped.
+    pub fn start(&self, priority: Priority) -> Job<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.running.lock().unwrap().push((id, priority));
+        self.changed.notify_all();
+
+        let log = avlog::Log::default();
+        avlog::capture(Some(log.clone()));
+
+        Job {
+            scheduler: self,
+            id,
+            priority,
+            log,
+        }
+    }
+
+    /// Deletes what unfinished jobs were writing. Called on the way out,
+    /// when their threads are about to die with the process and would
+    /// leave partial files behind.
+    pub fn abandon(&self) {
+        for (_, path) in self.writing.lock().unwrap().drain(..) {
+            let result = if path.is_dir() {
+                std::fs::remove_dir_all(&path)
+            } else {
+                std::fs::remove_file(&path)
+            };
+            match result {
+                Ok(()) => log::info!("removed unfinished {}", path.display()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => log::warn!("could not remove unfinished {}: {e}", path.display()),
+            }
+        }
+    }
+}
+
+/// A registered job; it is removed from the scheduler when dropped.
+pub struct Job<'a> {
+    scheduler: &'a Scheduler,
+    id: u64,
+    priority: Priority,
+    log: avlog::Log,
+}
+
+impl Job<'_> {
+    /// Blocks while a higher priority job is running.
+    pub fn checkpoint(&self) {
+        let mut running = self.scheduler.running.lock().unwrap();
+        while running.iter().any(|&(_, p)| p > self.priority) {
+            running = self.scheduler.changed.wait(running).unwrap();
+        }
+    }
+
+    /// Marks `path` as output of this job, for [`Scheduler::abandon`]. Once
+    /// the job ends, keeping or removing it is up to the caller again.
+    pub fn writes(&self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        self.scheduler.writing.lock().unwrap().push((self.id, path));
+    }
+
+    /// libav warnings and errors logged while the job ran, oldest first.
+    pub fn log(&self) -> Vec<String> {
+        self.log.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// `err` followed by the last few libav messages, which usually say more
+    /// about what went wrong than the error code does.
+    pub fn report(&self, err: impl Display) -> String {
+        let log = self.log();
+        let mut report = err.to_string();
+        for line in &log[log.len().saturating_sub(REPORT_LINES)..] {
+            report.push('\n');
+            report.push_str(line);
+        }
+        report
+    }
+}
+
+impl Drop for Job<'_> {
+    fn drop(&mut self) {
+        avlog::capture(None);
+        self.scheduler
+            .running
+            .lock()
+            .unwrap()
+            .retain(|&(id, _)| id != self.id);
+        self.scheduler
+            .writing
+            .lock()
+            .unwrap()
+            .retain(|(id, _)| *id != self.id);
+        self.scheduler.changed.notify_all();
+    }
+}
@@ -0,0 +1,50 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// A JSON file in the app data dir holding one value, read once at startup
+/// and written whole, atomically, whenever it changes.
+pub struct JsonStore {
+    /// `None` when there is no app data dir, in which case nothing is kept.
+    file: Option<PathBuf>,
+}
+
+impl JsonStore {
+    pub fn new(app: &AppHandle, name: &str) -> Self {
+        Self {
+            file: app.path().app_data_dir().ok().map(|dir| dir.join(name)),
+        }
+    }
+
+    /// The stored value, or the default when there is none or it can't be
+    /// read.
+    pub fn load<T: DeserializeOwned + Default>(&self) -> T {
+        self.file
+            .as_ref()
+            .and_then(|f| std::fs::read(f).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the stored value. Failing only costs the change, so it is
+    /// logged rather than returned.
+    pub fn save<T: Serialize + ?Sized>(&self, value: &T) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let result = std::fs::create_dir_all(file.parent().unwrap())
+            .and_then(|_| crate::write_atomically(file, serde_json::to_vec(value).unwrap()));
+        if let Err(e) = result {
+            log::warn!("could not save {}: {e}", file.display());
+        }
+    }
+}
+
+/// What files are stored under: their absolute path, so the same file
+/// opened through different relative paths is found again.
+pub fn path_key(source: &Path) -> String {
+    std::path::absolute(source)
+        .unwrap_or_else(|_| source.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
@@ -1,278 +1,179 @@
+use clap::Parser;
 use ffmpeg_next as ffmpeg;
 use http::{header::*, response::Builder as ResponseBuilder, status::StatusCode};
 use http_range::HttpRange;
 use std::{
+    collections::HashMap,
     error::Error,
     io::{Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
 };
-use tauri::{AppHandle, DragDropEvent, Emitter, Manager, WindowEvent};
-
-// Helper enum to hold state
-enum Transcoder {
-    Video(
-        ffmpeg::codec::decoder::Video,
-        ffmpeg::codec::encoder::Video,
-        usize,            // Output stream index
-        ffmpeg::Rational, // Input time base
-    ),
-    Audio(
-        ffmpeg::codec::decoder::Audio,
-        ffmpeg::codec::encoder::Audio,
-        usize,
-        ffmpeg::Rational,
-    ),
-}
-
-fn convert_to_mp4<PI: AsRef<std::path::Path> + ?Sized, PO: AsRef<std::path::Path> + ?Sized>(
-    input_path: &PI,
-    output_path: &PO,
-    win: AppHandle,
-) -> Result<(), Box<dyn Error>> {
-    // 1. Input Context
-    let mut ictx = ffmpeg::format::input(input_path)?;
-
-    // 2. Output Context
-    let mut octx = ffmpeg::format::output(output_path)?;
-
-    // Map input stream index to (Output Stream Index, Transcoder Context)
-    let mut streamer = std::collections::HashMap::new();
-
-    // 3. Setup Streams & Transcoders
-    for (stream_index, (istream, ostream_index)) in ictx
-        .streams()
-        .filter_map(|s| {
-            let medium = s.parameters().medium();
-            if medium == ffmpeg::media::Type::Video || medium == ffmpeg::media::Type::Audio {
-                Some((
-                    s.index(),
-                    (
-                        s,
-                        octx.add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::None))
-                            .unwrap()
-                            .index(),
-                    ),
-                ))
-            } else {
-                None // Ignore subtitles/data for this simple example
-            }
-        })
-        .collect::<Vec<_>>()
-        .into_iter()
-    {
-        let istream_params = istream.parameters();
-        let medium = istream_params.medium();
-
-        if medium == ffmpeg::media::Type::Video {
-            // -- VIDEO TRANSCODER (H.264) --
-
-            // Decoder
-            let context_decoder = ffmpeg::codec::context::Context::from_parameters(istream_params)?;
-            let decoder = context_decoder.decoder().video()?;
-
-            // Encoder (H.264)
-            let global_header = octx
-                .format()
-                .flags()
-                .contains(ffmpeg::format::flag::Flags::GLOBAL_HEADER);
-            let codec =
-                ffmpeg::encoder::find(ffmpeg::codec::Id::H264).expect("H.264 codec not found");
-            let context_encoder = ffmpeg::codec::context::Context::new_with_codec(codec);
-            let mut encoder = context_encoder.encoder().video()?;
-
-            // Set Encoder Parameters
-            encoder.set_height(decoder.height());
-            encoder.set_width(decoder.width());
-            encoder.set_aspect_ratio(decoder.aspect_ratio());
-            encoder.set_format(ffmpeg::format::Pixel::YUV420P); // Standard for MP4 compatibility
-            encoder.set_frame_rate(decoder.frame_rate());
-            encoder.set_time_base(istream.time_base()); // Use input timebase
-
-            if global_header {
-                encoder.set_flags(ffmpeg::codec::flag::Flags::GLOBAL_HEADER);
-            }
-
-            // Optional: Set H.264 specific options (presets)
-            let mut opts = ffmpeg::Dictionary::new();
-            opts.set("preset", "medium");
-            let encoder = encoder.open_with(opts)?;
+use tauri::{AppHandle, DragDropEvent, Emitter, Manager, State, WindowEvent};
+use tauri_plugin_deep_link::DeepLinkExt;
 
-            // Update output stream parameters to match encoder
-            let mut ostream = octx.stream_mut(ostream_index).unwrap();
-            ostream.set_parameters(&encoder);
+mod annotate;
+mod audioprint;
+mod avlog;
+mod bench;
+mod cache;
+mod chapters;
+mod cli;
+mod clip;
+mod compare;
+mod config;
+mod control;
+mod convert;
+mod deeplink;
+pub mod effects;
+mod ffhelp;
+mod filter;
+mod follow;
+mod hooks;
+mod instance;
+mod intro;
+mod jobs;
+mod json_store;
+mod lock;
+mod motion;
+mod naming;
+mod network;
+mod phash;
+mod pip;
+mod power;
+mod probe;
+mod readahead;
+mod recent;
+mod resume;
+mod screenshot;
+mod scripting;
+mod sequence;
+mod settings;
+mod sheet;
+mod subtitles;
+mod telemetry;
+mod thumbnail;
+mod tray;
+mod watch;
 
-            streamer.insert(
-                stream_index,
-                Transcoder::Video(decoder, encoder, ostream_index, istream.time_base()),
-            );
-        } else if medium == ffmpeg::media::Type::Audio {
-            // -- AUDIO TRANSCODER (AAC) --
-
-            let context_decoder = ffmpeg::codec::context::Context::from_parameters(istream_params)?;
-            let decoder = context_decoder.decoder().audio()?;
-
-            let global_header = octx
-                .format()
-                .flags()
-                .contains(ffmpeg::format::flag::Flags::GLOBAL_HEADER);
-            let codec = ffmpeg::encoder::find(ffmpeg::codec::Id::AAC).expect("AAC codec not found");
-            let context_encoder = ffmpeg::codec::context::Context::new_with_codec(codec);
-            let mut encoder = context_encoder.encoder().audio()?;
-
-            // Set Encoder Parameters
-            encoder.set_rate(decoder.rate() as i32);
-            // ffmpeg-next handling of channel layouts can be tricky; using default/stereo is safest for a mimic
-            encoder.set_channel_layout(ffmpeg::channel_layout::ChannelLayout::STEREO);
-            encoder.set_format(ffmpeg::format::Sample::F32(
-                ffmpeg::format::sample::Type::Planar,
-            )); // AAC usually likes planar floats
-            encoder.set_time_base(ffmpeg::Rational::new(1, decoder.rate() as i32));
-
-            if global_header {
-                encoder.set_flags(ffmpeg::codec::flag::Flags::GLOBAL_HEADER);
-            }
-
-            let encoder = encoder.open()?;
+#[derive(Default)]
+struct AppState {
+    /// The file the user dropped, before it was copied/converted to `v.mp4`.
+    source: Mutex<Option<PathBuf>>,
+    subtitles: Mutex<Option<subtitles::Selection>>,
+    /// A second track shown at the top, e.g. a translation of the first.
+    second_subtitles: Mutex<Option<subtitles::Selection>>,
+    /// User bookmarks for the current source, kept sorted by start.
+    bookmarks: Mutex<Vec<chapters::Marker>>,
+    playlist: Mutex<Playlist>,
+    /// Run conversions through [`filter::DEINTERLACE`].
+    deinterlace: AtomicBool,
+    /// Run conversions through [`config::Config::interpolation`].
+    interpolate: AtomicBool,
+    /// Where the player should start the next file it loads.
+    start_at: Mutex<Option<StartAt>>,
+    /// Audio track picked in the player for the current source.
+    audio_track: Mutex<Option<usize>>,
+    /// What the window's display shows, see [`filter::Gamut`].
+    display_gamut: Mutex<filter::Gamut>,
+    /// The playlist item being converted ahead, see [`prefetch_next`].
+    prefetching: Mutex<Option<PathBuf>>,
+}
 
-            // Update output stream parameters
-            let mut ostream = octx.stream_mut(ostream_index).unwrap();
-            ostream.set_parameters(&encoder);
+#[derive(Clone, Copy, serde::Serialize)]
+struct StartAt {
+    /// In seconds.
+    time: f64,
+    /// Wait there instead of playing on.
+    paused: bool,
+}
 
-            streamer.insert(
-                stream_index,
-                Transcoder::Audio(decoder, encoder, ostream_index, istream.time_base()),
-            );
+impl AppState {
+    /// The bottom subtitle track, or the top one if `secondary`.
+    fn subtitle_track(&self, secondary: Option<bool>) -> &Mutex<Option<subtitles::Selection>> {
+        match secondary {
+            Some(true) => &self.second_subtitles,
+            _ => &self.subtitles,
         }
     }
+}
 
-    // 4. Write Header
-    octx.write_header()?;
-
-    let mut fps = 0.0;
-    let mut fcount = 0;
-    let mut vlen = 0.0;
-    let mut i = 0;
-
-    // 5. Transcoding Loop
-    for (stream, packet) in ictx.packets() {
-        if fps == 0.0 && fcount == 0 && vlen == 0.0 && i == 0 {
-            fps = stream.avg_frame_rate().numerator() as f64
-                / stream.avg_frame_rate().denominator() as f64;
-            fcount = stream.frames();
-            vlen = fcount as f64 / fps;
-        }
-
-        if let Some(transcoder) = streamer.get_mut(&stream.index()) {
-            match transcoder {
-                Transcoder::Video(decoder, encoder, out_index, in_time_base) => {
-                    let mut sws_ctx = ffmpeg::software::scaling::context::Context::get(
-                        decoder.format(),
-                        decoder.width(),
-                        decoder.height(),
-                        ffmpeg::format::Pixel::YUV420P,
-                        decoder.width(),
-                        decoder.height(),
-                        ffmpeg::software::scaling::flag::Flags::BILINEAR,
-                    )?;
-                    // Decode
-                    decoder.send_packet(&packet)?;
-                    let mut decoded_frame = ffmpeg::frame::Video::empty();
-                    while decoder.receive_frame(&mut decoded_frame).is_ok() {
-                        let mut converted = ffmpeg::frame::Video::empty();
-                        converted.set_width(decoder.width());
-                        converted.set_height(decoder.height());
-                        converted.set_format(ffmpeg::format::Pixel::YUV420P);
-
-                        sws_ctx.run(&decoded_frame, &mut converted)?;
-                        // Rescale timestamps for the frame (Input -> Encoder)
-                        let pts = decoded_frame.pts();
-                        converted.set_pts(pts); // Often needs rescaling here if bases differ significantly
-
-                        i += 1;
-                        if i % 5 == 0 {
-                            win.emit("c-prog", i as f64 / fcount as f64).unwrap();
-                        }
-                        // Encode
-                        encoder.send_frame(&converted)?;
-                        let mut encoded_packet = ffmpeg::Packet::empty();
-                        while encoder.receive_packet(&mut encoded_packet).is_ok() {
-                            encoded_packet.set_stream(*out_index);
-                            // Rescale Packet Timestamp (Encoder -> Output)
-                            encoded_packet.rescale_ts(
-                                *in_time_base,
-                                octx.stream(*out_index).unwrap().time_base(),
-                            );
-                            encoded_packet.write_interleaved(&mut octx)?;
-                        }
-                    }
-                }
-                Transcoder::Audio(decoder, encoder, out_index, in_time_base) => {
-                    decoder.send_packet(&packet)?;
-                    let mut decoded_frame = ffmpeg::frame::Audio::empty();
-                    while decoder.receive_frame(&mut decoded_frame).is_ok() {
-                        encoder.send_frame(&decoded_frame)?;
-                        let mut encoded_packet = ffmpeg::Packet::empty();
-                        while encoder.receive_packet(&mut encoded_packet).is_ok() {
-                            encoded_packet.set_stream(*out_index);
-                            encoded_packet.rescale_ts(
-                                *in_time_base,
-                                octx.stream(*out_index).unwrap().time_base(),
-                            );
-                            encoded_packet.write_interleaved(&mut octx)?;
-                        }
-                    }
-                }
-            }
+#[derive(Default)]
+struct Playlist {
+    items: Vec<PathBuf>,
+    current: usize,
+}
+
+#[derive(serde::Serialize)]
+struct PlaylistInfo {
+    items: Vec<String>,
+    current: usize,
+}
+
+/// What the `stream` protocol serves. The request only picks one of these
+/// by name; nothing the webview sends is ever used as a file path.
+enum Route {
+    /// The playable copy of the open file.
+    Video,
+    /// The playable copy in extra window `n`, see [`compare`].
+    Window(usize),
+}
+
+impl Route {
+    fn parse(uri: &http::Uri) -> Option<Route> {
+        let name = percent_encoding::percent_decode_str(uri.path())
+            .decode_utf8()
+            .ok()?;
+        match name.strip_prefix('/')? {
+            "v.mp4" => Some(Route::Video),
+            // only plain digits, `parse` would take `+1` as well
+            name => name
+                .strip_prefix("v-")?
+                .strip_suffix(".mp4")
+                .filter(|n| n.bytes().all(|b| b.is_ascii_digit()))?
+                .parse()
+                .ok()
+                .map(Route::Window),
         }
     }
 
-    // 6. Flush Encoders
-    for (_, transcoder) in streamer.iter_mut() {
-        match transcoder {
-            Transcoder::Video(_, encoder, out_index, in_time_base) => {
-                encoder.send_eof()?;
-                let mut encoded_packet = ffmpeg::Packet::empty();
-                while encoder.receive_packet(&mut encoded_packet).is_ok() {
-                    encoded_packet.set_stream(*out_index);
-                    encoded_packet
-                        .rescale_ts(*in_time_base, octx.stream(*out_index).unwrap().time_base());
-                    encoded_packet.write_interleaved(&mut octx)?;
-                }
-            }
-            Transcoder::Audio(_, encoder, out_index, in_time_base) => {
-                encoder.send_eof()?;
-                let mut encoded_packet = ffmpeg::Packet::empty();
-                while encoder.receive_packet(&mut encoded_packet).is_ok() {
-                    encoded_packet.set_stream(*out_index);
-                    encoded_packet
-                        .rescale_ts(*in_time_base, octx.stream(*out_index).unwrap().time_base());
-                    encoded_packet.write_interleaved(&mut octx)?;
-                }
-            }
+    fn file(&self) -> PathBuf {
+        match self {
+            Route::Video => PathBuf::from("./v.mp4"),
+            Route::Window(n) => compare::output(*n),
         }
     }
-
-    println!();
-
-    // 7. Write Trailer
-    octx.write_trailer()?;
-
-    Ok(())
 }
 
 fn get_stream_response(
     request: http::Request<Vec<u8>>,
+    h: &AppHandle,
 ) -> Result<http::Response<Vec<u8>>, Box<dyn std::error::Error>> {
-    // skip leading `/`
-    let path = percent_encoding::percent_decode(&request.uri().path().as_bytes()[1..])
-        .decode_utf8_lossy()
-        .to_string();
-
-    // return error 404 if it's not our video
-    if path != "v.mp4" {
+    let Some(route) = Route::parse(request.uri()) else {
         return Ok(ResponseBuilder::new().status(404).body(Vec::new())?);
-    }
+    };
+    // every window reads at its own pace
+    let main_read_ahead = h.state::<readahead::ReadAhead>();
+    let window_read_ahead;
+    let read_ahead: &readahead::ReadAhead = match route {
+        // not even the stream of a locked file goes out before the PIN
+        Route::Video if locked_out(h) => {
+            return Ok(ResponseBuilder::new().status(403).body(Vec::new())?);
+        }
+        Route::Video => &main_read_ahead,
+        Route::Window(n) => match h.state::<compare::Windows>().read_ahead(n) {
+            Some(read_ahead) => {
+                window_read_ahead = read_ahead;
+                &window_read_ahead
+            }
+            None => return Ok(ResponseBuilder::new().status(404).body(Vec::new())?),
+        },
+    };
 
-    let mut file = std::fs::File::open(&path)?;
+    let mut file = std::fs::File::open(route.file())?;
 
     // get file length
     let len = {
@@ -282,7 +183,10 @@ fn get_stream_response(
         len
     };
 
-    let mut resp = ResponseBuilder::new().header(CONTENT_TYPE, "video/mp4");
+    let mut resp = ResponseBuilder::new()
+        .header(CONTENT_TYPE, "video/mp4")
+        // the page is another origin, and Web Audio only analyses audio it may read
+        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*");
 
     // if the webview sent a range header, we need to send a 206 in return
     let http_response = if let Some(range_header) = request.headers().get("range") {
@@ -304,7 +208,7 @@ fn get_stream_response(
             return Ok(not_satisfiable()?);
         };
 
-        /// The Maximum bytes we send in one range
+        /// The Maximum bytes we send in one range of a multipart response
         const MAX_LEN: u64 = 1000 * 1024;
 
         if ranges.len() == 1 {
@@ -318,8 +222,10 @@ fn get_stream_response(
                 return Ok(not_satisfiable()?);
             }
 
-            // adjust end byte for MAX_LEN
-            end = start + (end - start).min(len - start).min(MAX_LEN - 1);
+            // adjust end byte for how much playback is reading ahead
+            let chunk = read_ahead.chunk_for(start);
+            end = start + (end - start).min(len - start).min(chunk - 1);
+            read_ahead.served(end);
 
             // calculate number of bytes needed to be read
             let bytes_to_read = end + 1 - start;
@@ -411,12 +317,1498 @@ fn random_boundary() -> String {
         })
 }
 
+/// Deletes `./v.mp4` before something else is written there. Windows won't
+/// delete a file while it is open, as it is for a moment during each stream
+/// request, so it is tried a few times before giving up.
+fn remove_video() -> std::io::Result<()> {
+    const ATTEMPTS: u32 = 10;
+    let mut attempt = 1;
+    loop {
+        match std::fs::remove_file("./v.mp4") {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) if attempt == ATTEMPTS => return Err(e),
+            Err(_) => std::thread::sleep(std::time::Duration::from_millis(50)),
+            Ok(()) => return Ok(()),
+        }
+        attempt += 1;
+    }
+}
+
+fn open_source(h: AppHandle, path: PathBuf) {
+    if let Err(e) = remove_video() {
+        h.emit("c-error", format!("could not replace v.mp4: {e}"))
+            .unwrap();
+        return;
+    }
+
+    // libav is only ever given UTF-8 paths, see convert::preflight
+    if !network::is_url(&path) && path.to_str().is_none() {
+        let error = format!("{} is not valid Unicode", path.display());
+        h.emit("c-error", error).unwrap();
+        return;
+    }
+
+    set_source(&h, &path);
+    h.state::<readahead::ReadAhead>().reset();
+
+    let filtered = filtering(&h);
+    prefetch_next(&h);
+
+    if network::is_url(&path) {
+        network::spawn(h, path);
+    } else if plays_directly(&h, &path) {
+        if let Err(e) = std::fs::copy(&path, "./v.mp4") {
+            h.emit("c-error", format!("could not read {}: {e}", path.display()))
+                .unwrap();
+            return;
+        }
+        h.emit("refresh-mega", ()).unwrap();
+        follow::spawn(h, path);
+    } else if let Some(cached) = cache::converted_path(&h, &path)
+        .filter(|p| p.exists())
+        .filter(|_| !filtered)
+    {
+        // already converted in the background, by the watch folder or ahead
+        // of its turn in the playlist
+        match std::fs::copy(&cached, "./v.mp4") {
+            Ok(_) => {
+                cache::touch(&cached);
+                h.emit("refresh-mega", ()).unwrap();
+            }
+            // gone meanwhile, so convert it again
+            Err(_) => convert_source(h, path, convert::StreamMapping::default()),
+        }
+    } else {
+        convert_source(h, path, convert::StreamMapping::default());
+    }
+}
+
+/// Replaces the playlist and starts playing its first item.
+fn open_playlist(h: AppHandle, items: Vec<PathBuf>) {
+    let Some(first) = items.first().cloned() else {
+        return;
+    };
+
+    *h.state::<AppState>().playlist.lock().unwrap() = Playlist { items, current: 0 };
+    h.emit("playlist-changed", ()).unwrap();
+    open_source(h, first);
+}
+
+#[tauri::command]
+fn get_playlist(state: State<AppState>) -> PlaylistInfo {
+    let playlist = state.playlist.lock().unwrap();
+    PlaylistInfo {
+        items: playlist.items.iter().map(|p| file_label(p)).collect(),
+        current: playlist.current,
+    }
+}
+
+#[derive(serde::Serialize)]
+struct RecentFile {
+    path: PathBuf,
+    name: String,
+}
+
+#[tauri::command]
+fn get_recent(recent: State<recent::Recent>) -> Vec<RecentFile> {
+    recent
+        .items()
+        .into_iter()
+        .map(|path| RecentFile {
+            name: file_label(&path),
+            path,
+        })
+        .collect()
+}
+
+#[tauri::command]
+fn open_recent(app: AppHandle, path: PathBuf) {
+    open_playlist(app, vec![path]);
+}
+
+/// Opens a player window next to this one for comparing files, with the
+/// next playlist item in it if there is one.
+/// Async, as commands that build windows deadlock on Windows when they run
+/// on the main thread.
+#[tauri::command]
+async fn open_window(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let next = {
+        let playlist = state.playlist.lock().unwrap();
+        playlist.items.get(playlist.current + 1).cloned()
+    };
+    compare::open(&app, next)
+}
+
+/// The file name in the calling extra window once it can be played, for a
+/// window that missed `window-ready` while its page was loading.
+#[tauri::command]
+fn window_ready(window: tauri::WebviewWindow, windows: State<compare::Windows>) -> Option<String> {
+    windows.ready(compare::number(window.label())?)
+}
+
+/// Opens the item `offset` places from the current one. Returns false at
+/// either end of the playlist.
+#[tauri::command]
+fn playlist_step(app: AppHandle, state: State<AppState>, offset: isize) -> bool {
+    let mut playlist = state.playlist.lock().unwrap();
+
+    let Some(next) = playlist
+        .current
+        .checked_add_signed(offset)
+        .filter(|&i| i < playlist.items.len())
+    else {
+        return false;
+    };
+    playlist.current = next;
+    let path = playlist.items[next].clone();
+    drop(playlist);
+
+    open_source(app, path);
+    true
+}
+
+fn open_link(h: AppHandle, link: deeplink::Link) {
+    *h.state::<AppState>().start_at.lock().unwrap() = link.time.map(|time| StartAt {
+        time,
+        paused: false,
+    });
+    open_playlist(h, vec![link.path]);
+}
+
+/// A `simplevidview://` link that opens the current file at `time`.
+#[tauri::command]
+fn share_link(state: State<AppState>, time: f64) -> Result<String, String> {
+    let source = state.source.lock().unwrap();
+    let path = source.as_deref().ok_or("error.nothing-open")?;
+    // a link is only useful to others if it doesn't depend on our working dir
+    let path = std::path::absolute(path).map_err(|e| e.to_string())?;
+    Ok(deeplink::build(&path, time))
+}
+
+/// Where a link or the command line asked to start, if it hasn't been used
+/// yet.
+#[tauri::command]
+fn take_start_time(state: State<AppState>) -> Option<StartAt> {
+    state.start_at.lock().unwrap().take()
+}
+
+/// Where the current file was left off last time, if anywhere.
+#[tauri::command]
+fn get_resume_position(state: State<AppState>, positions: State<resume::Positions>) -> Option<f64> {
+    positions.get(state.source.lock().unwrap().as_deref()?)
+}
+
+/// Remembers how far the current file has been played; `None` once it is
+/// done.
+#[tauri::command]
+fn save_position(state: State<AppState>, positions: State<resume::Positions>, time: Option<f64>) {
+    if let Some(source) = state.source.lock().unwrap().as_deref() {
+        positions.set(source, time);
+    }
+}
+
+/// What of the current file needs the PIN to play.
+#[tauri::command]
+fn get_locks(state: State<AppState>, locks: State<lock::Locks>) -> lock::FileLock {
+    match state.source.lock().unwrap().as_deref() {
+        Some(source) => locks.get(source),
+        None => lock::FileLock::default(),
+    }
+}
+
+/// Whether `pin` unlocks what is locked. The right one unlocks the current
+/// file until another one opens.
+#[tauri::command]
+fn check_pin(state: State<AppState>, locks: State<lock::Locks>, pin: String) -> bool {
+    match state.source.lock().unwrap().as_deref() {
+        Some(source) => locks.unlock(source, &pin),
+        None => locks.check(&pin),
+    }
+}
+
+/// Refuses frames from `start` to `end` of the current file while they are
+/// locked, see [`lock::Locks::allows`].
+fn check_unlocked(h: &AppHandle, start: f64, end: f64) -> Result<(), String> {
+    let source = h.state::<AppState>().source.lock().unwrap().clone();
+    match source {
+        Some(source) if !h.state::<lock::Locks>().allows(&source, start, end) => {
+            Err("lock.locked".into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether the current file is locked as a whole and the PIN wasn't entered
+/// for it.
+fn locked_out(h: &AppHandle) -> bool {
+    let source = h.state::<AppState>().source.lock().unwrap().clone();
+    let locks = h.state::<lock::Locks>();
+    source.is_some_and(|source| locks.get(&source).whole && !locks.unlocked(&source))
+}
+
+/// Locks the whole current file, or lifts all its locks if it has any.
+#[tauri::command]
+fn toggle_file_lock(
+    state: State<AppState>,
+    locks: State<lock::Locks>,
+    pin: String,
+) -> Result<lock::FileLock, String> {
+    let source = state.source.lock().unwrap().clone().ok_or("lock.no-file")?;
+    locks.update(&source, &pin, |lock| {
+        if lock.whole || !lock.segments.is_empty() {
+            *lock = lock::FileLock::default();
+        } else {
+            lock.whole = true;
+        }
+    })
+}
+
+/// Locks `start` to `end` of the current file.
+#[tauri::command]
+fn lock_segment(
+    state: State<AppState>,
+    locks: State<lock::Locks>,
+    pin: String,
+    start: f64,
+    end: f64,
+) -> Result<lock::FileLock, String> {
+    let source = state.source.lock().unwrap().clone().ok_or("lock.no-file")?;
+    locks.update(&source, &pin, |lock| {
+        lock.segments.push(lock::Segment { start, end });
+        lock.segments.sort_by(|a, b| a.start.total_cmp(&b.start));
+    })
+}
+
+/// The UI language from the config; `None` means the system's.
+#[tauri::command]
+fn get_language(config: State<config::Config>) -> Option<String> {
+    config.language.clone()
+}
+
+#[tauri::command]
+fn get_keymap(config: State<config::Config>) -> HashMap<String, config::KeyBinding> {
+    config.keys.clone()
+}
+
+#[tauri::command]
+fn get_appearance(config: State<config::Config>) -> config::Appearance {
+    config.appearance
+}
+
+#[tauri::command]
+fn get_osd_theme(config: State<config::Config>) -> config::OsdTheme {
+    config.osd.clone()
+}
+
+#[derive(serde::Serialize)]
+struct LaunchOptions {
+    speed: Option<f64>,
+    loop_mode: Option<&'static str>,
+}
+
+/// Playback options given on the command line, applied to every file.
+#[tauri::command]
+fn get_launch_options(player: State<cli::PlayerArgs>, state: State<AppState>) -> LaunchOptions {
+    LaunchOptions {
+        speed: player.speed,
+        loop_mode: player.loop_mode(state.playlist.lock().unwrap().items.len()),
+    }
+}
+
+#[tauri::command]
+fn get_power_saver(saver: State<power::PowerSaver>) -> bool {
+    saver.active()
+}
+
+/// Turns power saving on or off by hand, which stops it following the
+/// power source. Returns whether it is on now.
+#[tauri::command]
+fn toggle_power_saver(
+    app: AppHandle,
+    saver: State<power::PowerSaver>,
+    settings: State<settings::Store>,
+) -> bool {
+    let active = saver.toggle(&app);
+    settings.update(|s| s.power_saver = Some(active));
+    active
+}
+
+/// Called by the player when it takes or loses its screen wake lock.
+#[tauri::command]
+fn set_awake(app: AppHandle, active: bool) {
+    if let Some(tray) = app.try_state::<tray::Tray>() {
+        tray.set_awake(active);
+    }
+}
+
+/// Switches the window in and out of picture-in-picture, see [`pip::Pip`].
+/// Returns whether it is on now.
+#[tauri::command]
+fn toggle_pip(
+    window: tauri::WebviewWindow,
+    pip: State<pip::Pip>,
+    aspect: f64,
+) -> Result<bool, String> {
+    pip.toggle(&window, aspect).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_settings(settings: State<settings::Store>) -> settings::Settings {
+    settings.get()
+}
+
+/// Remembers player state for the next start; whatever is `None` stays.
+#[tauri::command]
+fn update_settings(
+    settings: State<settings::Store>,
+    volume: Option<f64>,
+    muted: Option<bool>,
+    loop_mode: Option<String>,
+) {
+    settings.update(|s| {
+        if let Some(volume) = volume {
+            s.volume = volume.clamp(0.0, 1.0);
+        }
+        if let Some(muted) = muted {
+            s.muted = muted;
+        }
+        if let Some(loop_mode) = loop_mode {
+            s.loop_mode = loop_mode;
+        }
+    });
+}
+
+#[tauri::command]
+fn get_seek_steps(config: State<config::Config>) -> config::SeekSteps {
+    config.seek_steps
+}
+
+/// Opens a pasted path or URL. Several lines become a playlist.
+#[tauri::command]
+fn open_media(app: AppHandle, target: String) -> Result<(), String> {
+    let items = target
+        .lines()
+        .map(|line| line.trim().trim_matches('"'))
+        .filter(|line| !line.is_empty())
+        .map(media_target)
+        .collect::<Option<Vec<_>>>()
+        .filter(|items| !items.is_empty())
+        .ok_or("error.nothing-playable")?;
+
+    open_playlist(app, items);
+    Ok(())
+}
+
+/// Turns a pasted line into something ffmpeg can open: an existing file, or
+/// a URL which is passed on as is.
+fn media_target(line: &str) -> Option<PathBuf> {
+    match tauri::Url::parse(line) {
+        Ok(url) if url.scheme() == "file" => url.to_file_path().ok().filter(|p| p.is_file()),
+        // `C:\...` parses as a URL with a one letter scheme
+        Ok(url) if url.scheme().len() > 1 => Some(PathBuf::from(line)),
+        _ => Some(PathBuf::from(line)).filter(|p| p.is_file()),
+    }
+}
+
+/// Like [`media_target`], but also picks up a start time from the end of
+/// the argument; see [`deeplink::split_time`].
+fn launch_target(arg: &str) -> Option<(PathBuf, Option<f64>)> {
+    // a file whose name happens to end in `#t=...` wins
+    if let Some(path) = media_target(arg).filter(|p| p.is_file()) {
+        return Some((path, None));
+    }
+
+    match deeplink::split_time(arg) {
+        // URLs keep their query, the server might need it
+        Some((rest, time)) => media_target(arg)
+            .or_else(|| media_target(rest))
+            .map(|target| (target, Some(time))),
+        None => media_target(arg).map(|target| (target, None)),
+    }
+}
+
+/// Resets everything that belongs to the previously opened file.
+fn set_source(h: &AppHandle, path: &std::path::Path) {
+    let state = h.state::<AppState>();
+    *state.subtitles.lock().unwrap() = Some(path)
+        .filter(|p| !network::is_url(p))
+        .and_then(|p| subtitles::find_sidecar(&p))
+        .map(subtitles::Selection::File);
+    *state.second_subtitles.lock().unwrap() = None;
+    *state.audio_track.lock().unwrap() = None;
+    *state.source.lock().unwrap() = Some(path.to_path_buf());
+    state.bookmarks.lock().unwrap().clear();
+    h.state::<lock::Locks>().relock();
+
+    hooks::run(
+        h,
+        hooks::Event::FileOpen,
+        serde_json::json!({ "path": path }),
+    );
+    h.state::<recent::Recent>().push(path);
+    scripting::call(h, "on_open", (path.display().to_string(),));
+}
+
+/// Whether conversions run through filters. Cached copies are made without
+/// them, so they can't be used then. Deinterlacing is left to the cache
+/// key, see [`cache::converted_path`].
+fn filtering(h: &AppHandle) -> bool {
+    interpolating(h) || custom_filter(h).is_some() || !frame_filters(h).is_empty()
+}
+
+/// The configured [`effects`], unless power saving drops them; they are
+/// only looks.
+fn frame_filters(h: &AppHandle) -> Vec<String> {
+    if h.state::<power::PowerSaver>().active() {
+        Vec::new()
+    } else {
+        h.state::<config::Config>().frame_filters.clone()
+    }
+}
+
+/// Whether `path` is an mp4 the webview can be given as is.
+fn plays_directly(h: &AppHandle, path: &std::path::Path) -> bool {
+    let deinterlace = h.state::<AppState>().deinterlace.load(Ordering::Relaxed);
+    path.extension().is_some_and(|ext| ext == "mp4")
+        && path.is_file()
+        && !filtering(h)
+        // interlaced mp4s have to go through the deinterlacer like everything else
+        && !(deinterlace && filter::is_interlaced(path))
+        // HDR only looks right after tone mapping
+        && !(filter::is_hdr_file(path) && filter::tonemap().is_some())
+        && !(mapping_gamut(h) && filter::is_wide_gamut_file(path))
+}
+
+/// Converts the next playlist item into the cache in the background, so it
+/// starts right away when its turn comes. It runs as batch work, which waits
+/// while the current file converts.
+fn prefetch_next(h: &AppHandle) {
+    let state = h.state::<AppState>();
+    let next = {
+        let playlist = state.playlist.lock().unwrap();
+        playlist.items.get(playlist.current + 1).cloned()
+    };
+    let Some(next) = next.filter(|p| !network::is_url(p) && p.is_file()) else {
+        return;
+    };
+    if filtering(h)
+        || plays_directly(h, &next)
+        || cache::converted_path(h, &next).is_some_and(|p| p.exists())
+    {
+        return;
+    }
+    {
+        let mut prefetching = state.prefetching.lock().unwrap();
+        if prefetching.as_ref() == Some(&next) {
+            return;
+        }
+        *prefetching = Some(next.clone());
+    }
+
+    let h = h.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = cache::convert(&h, &next) {
+            log::warn!("could not prefetch {}: {e}", next.display());
+        }
+        let mut prefetching = h.state::<AppState>().prefetching.lock().unwrap();
+        if prefetching.as_ref() == Some(&next) {
+            *prefetching = None;
+        }
+    });
+}
+
+/// Last things before the process ends, also when the OS ends the session
+/// and the window never gets a close request. Positions, settings and
+/// locks are saved as they change, so only the session is left to keep.
+fn shutdown(h: &AppHandle) {
+    h.state::<jobs::Scheduler>().abandon();
+    save_session(h);
+    compare::clean_up(h);
+    if let Err(e) = remove_video() {
+        log::warn!("could not remove v.mp4: {e}");
+    }
+}
+
+/// Remembers the playlist and the chosen tracks for [`restore_session`].
+fn save_session(h: &AppHandle) {
+    let state = h.state::<AppState>();
+    let playlist = state.playlist.lock().unwrap();
+    let session = (!playlist.items.is_empty()).then(|| settings::Session {
+        items: playlist.items.clone(),
+        current: playlist.current,
+        audio_track: *state.audio_track.lock().unwrap(),
+        subtitles: state.subtitles.lock().unwrap().clone(),
+        second_subtitles: state.second_subtitles.lock().unwrap().clone(),
+    });
+    h.state::<settings::Store>().update(|s| s.session = session);
+}
+
+/// The session left open last time, if the player started without files.
+#[tauri::command]
+fn get_session(
+    state: State<AppState>,
+    settings: State<settings::Store>,
+) -> Option<settings::Session> {
+    if !state.playlist.lock().unwrap().items.is_empty() {
+        return None;
+    }
+    settings.get().session
+}
+
+/// Reopens the playlist from last time where it was left, with the same
+/// tracks.
+#[tauri::command]
+fn restore_session(
+    app: AppHandle,
+    state: State<AppState>,
+    settings: State<settings::Store>,
+    positions: State<resume::Positions>,
+) -> Result<(), String> {
+    let session = settings.get().session.ok_or("session.none")?;
+    let path = session
+        .items
+        .get(session.current)
+        .cloned()
+        .ok_or("session.none")?;
+
+    *state.start_at.lock().unwrap() = Some(StartAt {
+        time: positions.get(&path).unwrap_or(0.0),
+        paused: false,
+    });
+    *state.playlist.lock().unwrap() = Playlist {
+        items: session.items,
+        current: session.current,
+    };
+    app.emit("playlist-changed", ()).unwrap();
+    match session.audio_track {
+        Some(track) => {
+            set_source(&app, &path);
+            app.state::<readahead::ReadAhead>().reset();
+            switch_audio_track(app.clone(), path, track)?;
+        }
+        None => open_source(app.clone(), path),
+    }
+
+    // opening picks subtitles of its own, and the page may already have
+    // loaded those
+    *state.subtitles.lock().unwrap() = session.subtitles;
+    *state.second_subtitles.lock().unwrap() = session.second_subtitles;
+    app.emit("subs-changed", ()).unwrap();
+    Ok(())
+}
+
+/// How an audio track is announced on the OSD.
+#[derive(serde::Serialize)]
+struct AudioTrack {
+    /// Counting from 1.
+    n: usize,
+    count: usize,
+    name: String,
+}
+
+/// Switches to the next audio track of the open file and continues at
+/// `time`. Webviews don't let the page pick a track, so the file is
+/// converted again with only that one. `None` when there is nothing to
+/// switch to.
+#[tauri::command]
+fn cycle_audio_track(
+    app: AppHandle,
+    state: State<AppState>,
+    time: f64,
+    paused: bool,
+) -> Result<Option<AudioTrack>, String> {
+    let path = state
+        .source
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("error.nothing-open")?;
+    if network::is_url(&path) {
+        return Ok(None);
+    }
+    let tracks = convert::probe_streams(&path)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|s| s.medium == "audio")
+        .collect::<Vec<_>>();
+    if tracks.len() < 2 {
+        return Ok(None);
+    }
+
+    // the webview plays the first track until one was picked
+    let next = state
+        .audio_track
+        .lock()
+        .unwrap()
+        .map_or(1, |i| (i + 1) % tracks.len());
+    *state.start_at.lock().unwrap() = Some(StartAt { time, paused });
+    switch_audio_track(app, path, next)?;
+
+    let track = &tracks[next];
+    Ok(Some(AudioTrack {
+        n: next + 1,
+        count: tracks.len(),
+        name: [&track.language, &track.title]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" "),
+    }))
+}
+
+/// Converts `path` again with only its `track`th audio stream, and keeps the
+/// choice for the session.
+fn switch_audio_track(h: AppHandle, path: PathBuf, track: usize) -> Result<(), String> {
+    let mapping = convert::StreamMapping::audio_track(&path, track).map_err(|e| e.to_string())?;
+    remove_video().map_err(|e| format!("could not replace v.mp4: {e}"))?;
+    *h.state::<AppState>().audio_track.lock().unwrap() = Some(track);
+    convert_source(h, path, mapping);
+    Ok(())
+}
+
+/// Called by the player when it reaches the end of the current file.
+#[tauri::command]
+fn playback_finished(app: AppHandle, state: State<AppState>, positions: State<resume::Positions>) {
+    let Some(path) = state.source.lock().unwrap().clone() else {
+        return;
+    };
+    positions.set(&path, None);
+    hooks::run(
+        &app,
+        hooks::Event::PlaybackFinished,
+        serde_json::json!({ "path": path, "duration": media_duration(&path) }),
+    );
+    scripting::call(&app, "on_end", ());
+}
+
+/// Called by the player as playback moves along, for scripts' `on_time`.
+#[tauri::command]
+fn script_time(app: AppHandle, time: f64) {
+    scripting::call(&app, "on_time", (time,));
+}
+
+/// The user's own filter chain, from `--vf` or the config.
+fn custom_filter(h: &AppHandle) -> Option<String> {
+    h.state::<cli::PlayerArgs>()
+        .vf
+        .clone()
+        .or_else(|| h.state::<config::Config>().video_filter.clone())
+}
+
+/// Whether conversions should raise the frame rate. Power saving skips it,
+/// as it is by far the most expensive filter.
+fn interpolating(h: &AppHandle) -> bool {
+    h.state::<AppState>().interpolate.load(Ordering::Relaxed)
+        && !h.state::<power::PowerSaver>().active()
+}
+
+/// Whether wide-gamut video has to be mapped for the display.
+fn mapping_gamut(h: &AppHandle) -> bool {
+    h.state::<AppState>()
+        .display_gamut
+        .lock()
+        .unwrap()
+        .needs_mapping()
+}
+
+/// Conversion settings from the config and the current toggles.
+fn convert_options(h: &AppHandle, mapping: convert::StreamMapping) -> convert::ConvertOptions {
+    let mut options = unfiltered_options(h, mapping);
+    // after deinterlacing, so it sees whole frames
+    options.video_filters.extend(custom_filter(h));
+    // after deinterlacing, which would otherwise be fed made up frames
+    if interpolating(h) {
+        options
+            .video_filters
+            .push(h.state::<config::Config>().interpolation.spec());
+    }
+    options.frame_filters = frame_filters(h);
+    options
+}
+
+/// [`convert_options`] without anything [`filtering`] counts, for copies
+/// that go into the cache.
+fn unfiltered_options(h: &AppHandle, mapping: convert::StreamMapping) -> convert::ConvertOptions {
+    let mut video_filters = Vec::new();
+    if h.state::<AppState>().deinterlace.load(Ordering::Relaxed) {
+        video_filters.push(filter::DEINTERLACE.to_string());
+    }
+
+    convert::ConvertOptions {
+        mapping,
+        errors: h.state::<config::Config>().decode_errors,
+        video_filters,
+        frame_filters: Vec::new(),
+        low_power: h.state::<power::PowerSaver>().active(),
+        map_gamut: mapping_gamut(h),
+        max_height: None,
+        tee: None,
+    }
+}
+
+/// Writes `contents` to `path` through a temporary file, so that being
+/// killed halfway leaves the old file instead of part of the new one.
+fn write_atomically(path: &std::path::Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// What a caught panic said, for showing to the user.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown error".into())
+}
+
+fn convert_source(h: AppHandle, path: PathBuf, mapping: convert::StreamMapping) {
+    std::thread::spawn(move || {
+        // damaged files can trip up more than the error paths expect; the
+        // player should say so instead of waiting for a file that never comes
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            convert_source_blocking(&h, &path, mapping)
+        }));
+        if let Err(payload) = result {
+            let _ = std::fs::remove_file("./v.mp4");
+            h.emit("c-error", panic_message(payload.as_ref())).unwrap();
+        }
+    });
+}
+
+/// Converts `path` into `./v.mp4` for playback, telling the player how it
+/// goes through events.
+fn convert_source_blocking(h: &AppHandle, path: &std::path::Path, mapping: convert::StreamMapping) {
+    if let Err(e) = convert::preflight(path, std::path::Path::new("."), &mapping) {
+        h.emit("c-error", e.to_string()).unwrap();
+        return;
+    }
+
+    let config = h.state::<config::Config>();
+    let mut options = convert_options(h, mapping);
+    // a small copy to play now and, from the same decode, the full one
+    // for the cache, which plays the next time. Filtered and hand mapped
+    // conversions are never cached.
+    let unfiltered = !filtering(h);
+    let archive = config
+        .preview_height
+        .filter(|_| unfiltered && options.mapping.streams.is_empty())
+        .and_then(|_| cache::converted_path(h, path))
+        .filter(|p| std::fs::create_dir_all(p.parent().unwrap()).is_ok());
+    if let Some(archive) = &archive {
+        options.max_height = config.preview_height;
+        options.tee = Some(convert::Tee {
+            // under a temporary name so a half-finished file is never picked up
+            path: archive.with_extension("tmp.mp4"),
+            max_height: None,
+        });
+    }
+    let scheduler = h.state::<jobs::Scheduler>();
+    let job = scheduler.start(jobs::Priority::Current);
+    job.writes("./v.mp4");
+    if let Some(tee) = &options.tee {
+        job.writes(&tee.path);
+    }
+
+    let result = convert::convert_to_mp4(path, "./v.mp4", &options, |p| {
+        job.checkpoint();
+        h.emit("c-prog", p).unwrap()
+    });
+    match result {
+        Ok(0) => {}
+        Ok(dropped) => h.emit("c-damaged", dropped).unwrap(),
+        Err(e) => {
+            let _ = std::fs::remove_file("./v.mp4");
+            if let Some(tee) = &options.tee {
+                let _ = std::fs::remove_file(&tee.path);
+            }
+            h.emit("c-error", job.report(e)).unwrap();
+            return;
+        }
+    }
+    if let (Some(tee), Some(archive)) = (&options.tee, &archive) {
+        match std::fs::rename(&tee.path, archive) {
+            Ok(()) => cache::evict(h, archive),
+            Err(e) => log::warn!("could not keep the full quality copy: {e}"),
+        }
+    }
+
+    if config.verify_conversions {
+        match convert::verify_output(path, "./v.mp4") {
+            Ok(report) => h.emit("c-verify", report).unwrap(),
+            Err(e) => log::warn!("could not verify conversion: {e}"),
+        }
+    }
+
+    h.emit("refresh-mega", ()).unwrap();
+}
+
+/// What the webview found out about the display, unless the config says.
+#[tauri::command]
+fn set_display_gamut(config: State<config::Config>, state: State<AppState>, gamut: filter::Gamut) {
+    if config.display_gamut.is_none() {
+        *state.display_gamut.lock().unwrap() = gamut;
+    }
+}
+
+/// Turns deinterlacing on or off and converts the current file again with
+/// the new setting if it is interlaced, keeping the audio track picked in
+/// the player. Returns whether it is on now.
+#[tauri::command]
+fn toggle_deinterlace(app: AppHandle, state: State<AppState>) -> bool {
+    let enabled = !state.deinterlace.fetch_xor(true, Ordering::Relaxed);
+
+    let source = state.source.lock().unwrap().clone();
+    // progressive files look the same either way, no need to convert again
+    if let Some(path) = source.filter(|p| !network::is_url(p) && filter::is_interlaced(p)) {
+        let mapping = match *state.audio_track.lock().unwrap() {
+            Some(track) => convert::StreamMapping::audio_track(&path, track),
+            None => Ok(convert::StreamMapping::default()),
+        };
+        let result = mapping.map_err(|e| e.to_string()).and_then(|mapping| {
+            remove_video().map_err(|e| format!("could not replace v.mp4: {e}"))?;
+            convert_source(app.clone(), path, mapping);
+            Ok(())
+        });
+        if let Err(e) = result {
+            app.emit("c-error", e).unwrap();
+        }
+    }
+    enabled
+}
+
+/// Turns frame interpolation on or off and reopens the current file with the
+/// new setting. Returns whether it is on now.
+#[tauri::command]
+fn toggle_interpolation(app: AppHandle, state: State<AppState>) -> bool {
+    let enabled = !state.interpolate.fetch_xor(true, Ordering::Relaxed);
+
+    if let Some(path) = state.source.lock().unwrap().clone() {
+        open_source(app, path);
+    }
+    enabled
+}
+
+/// Lists the streams of `path`, or of the open file when no path is given.
+#[tauri::command]
+async fn probe_streams(
+    state: State<'_, AppState>,
+    path: Option<PathBuf>,
+) -> Result<Vec<convert::StreamInfo>, String> {
+    let path = path
+        .or_else(|| state.source.lock().unwrap().clone())
+        .ok_or("error.nothing-open")?;
+    convert::probe_streams(&path).map_err(|e| e.to_string())
+}
+
+/// Converts `path` (or the open file) with an explicit stream selection and
+/// plays the result, even for files that would otherwise be played as-is.
+#[tauri::command]
+fn convert_with_mapping(
+    app: AppHandle,
+    state: State<AppState>,
+    path: Option<PathBuf>,
+    mapping: convert::StreamMapping,
+) -> Result<(), String> {
+    let current = state.source.lock().unwrap().clone();
+    let path = path.or(current.clone()).ok_or("error.nothing-open")?;
+
+    if current.as_ref() != Some(&path) {
+        set_source(&app, &path);
+    }
+    remove_video().map_err(|e| format!("could not replace v.mp4: {e}"))?;
+    convert_source(app, path, mapping);
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_subtitles(
+    state: State<'_, AppState>,
+    secondary: Option<bool>,
+) -> Result<Vec<subtitles::Cue>, String> {
+    match state.subtitle_track(secondary).lock().unwrap().as_ref() {
+        Some(selection) => selection.load().map_err(|e| e.to_string()),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Steps through the sidecar file, then every embedded track, then off.
+/// Returns a description of the new selection.
+#[tauri::command]
+async fn cycle_subtitles(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    secondary: Option<bool>,
+) -> Result<String, String> {
+    let Some(source) = state.source.lock().unwrap().clone() else {
+        return Ok("subs.none".into());
+    };
+
+    let mut current = state.subtitle_track(secondary).lock().unwrap();
+
+    let mut options = Vec::new();
+    match current.as_ref() {
+        // keep a manually dropped file in the rotation
+        Some(subtitles::Selection::File(path)) => {
+            options.push((subtitles::Selection::File(path.clone()), file_label(path)))
+        }
+        _ => {
+            if let Some(path) = subtitles::find_sidecar(&source) {
+                options.push((subtitles::Selection::File(path.clone()), file_label(&path)));
+            }
+        }
+    }
+    for track in subtitles::embedded_tracks(&source).map_err(|e| e.to_string())? {
+        options.push((
+            subtitles::Selection::Embedded(source.clone(), track.index),
+            track.label(),
+        ));
+    }
+
+    let next = match current.as_ref() {
+        Some(sel) => options
+            .iter()
+            .position(|(o, _)| o == sel)
+            .map_or(0, |i| i + 1),
+        None => 0,
+    };
+
+    let label = match options.into_iter().nth(next) {
+        Some((selection, label)) => {
+            *current = Some(selection);
+            label
+        }
+        None => {
+            *current = None;
+            "subs.off".into()
+        }
+    };
+
+    app.emit("subs-changed", ()).unwrap();
+    Ok(label)
+}
+
+#[tauri::command]
+async fn get_chapters(state: State<'_, AppState>) -> Result<Vec<chapters::Marker>, String> {
+    match state.source.lock().unwrap().as_ref() {
+        Some(source) => chapters::read_chapters(source).map_err(|e| e.to_string()),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[tauri::command]
+fn get_bookmarks(state: State<AppState>) -> Vec<chapters::Marker> {
+    state.bookmarks.lock().unwrap().clone()
+}
+
+#[tauri::command]
+fn add_bookmark(app: AppHandle, state: State<AppState>, time: f64) {
+    let mut bookmarks = state.bookmarks.lock().unwrap();
+    let title = format!("Bookmark {}", bookmarks.len() + 1);
+    bookmarks.push(chapters::Marker {
+        start: time,
+        end: None,
+        title,
+    });
+    bookmarks.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    app.emit("markers-changed", ()).unwrap();
+}
+
+/// Writes the container chapters and the bookmarks next to the source file,
+/// returning the path written.
+#[tauri::command]
+async fn export_markers(
+    state: State<'_, AppState>,
+    format: chapters::Format,
+) -> Result<PathBuf, String> {
+    let source = state
+        .source
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("error.nothing-open")?;
+
+    let mut markers = chapters::read_chapters(&source).map_err(|e| e.to_string())?;
+    markers.extend(state.bookmarks.lock().unwrap().iter().cloned());
+    markers.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let text = chapters::export(
+        &markers,
+        format,
+        &file_label(&source),
+        media_duration(&source).unwrap_or(0.0),
+    );
+    let out = source.with_extension(format.extension());
+    std::fs::write(&out, text).map_err(|e| e.to_string())?;
+
+    Ok(out)
+}
+
+/// Writes the subtitles shown at the bottom as a transcript next to the
+/// source file, returning the path written.
+#[tauri::command]
+async fn export_transcript(
+    state: State<'_, AppState>,
+    format: subtitles::TranscriptFormat,
+) -> Result<PathBuf, String> {
+    let source = state
+        .source
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("error.nothing-open")?;
+    let selection = state.subtitles.lock().unwrap().clone().ok_or("subs.none")?;
+
+    let cues = selection.load().map_err(|e| e.to_string())?;
+    let text = subtitles::transcript(&cues, format, &file_label(&source));
+    let out = source.with_extension(format.extension());
+    std::fs::write(&out, text).map_err(|e| e.to_string())?;
+
+    Ok(out)
+}
+
+/// Replaces the bookmarks with the markers in a dropped chapter file.
+fn import_markers(app: &AppHandle, path: &std::path::Path, format: chapters::Format) {
+    match std::fs::read_to_string(path) {
+        Ok(text) => {
+            *app.state::<AppState>().bookmarks.lock().unwrap() = chapters::import(&text, format);
+            app.emit("markers-changed", ()).unwrap();
+        }
+        Err(e) => log::warn!("could not read {}: {e}", path.display()),
+    }
+}
+
+#[derive(serde::Serialize)]
+struct VideoInfo {
+    fps: f64,
+    width: u32,
+    height: u32,
+    /// See [`telemetry::codec_string`].
+    codec: Option<String>,
+}
+
+/// Describes the video stream of the file being played.
+#[tauri::command]
+async fn get_video_info() -> Result<VideoInfo, String> {
+    let ictx = ffmpeg::format::input("./v.mp4").map_err(|e| e.to_string())?;
+    let stream = ictx
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or("no video stream")?;
+    let decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())
+        .and_then(|c| c.decoder().video())
+        .map_err(|e| e.to_string())?;
+
+    Ok(VideoInfo {
+        fps: f64::from(stream.avg_frame_rate()),
+        width: decoder.width(),
+        height: decoder.height(),
+        codec: telemetry::codec_string("./v.mp4"),
+    })
+}
+
+/// Writes a report on the playback session to the log folder, for
+/// attaching to bug reports, and returns where it went.
+#[tauri::command]
+async fn export_telemetry(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    playback: telemetry::Playback,
+) -> Result<PathBuf, String> {
+    let source = state.source.lock().unwrap().clone();
+    let report = telemetry::report(
+        source.as_deref(),
+        std::path::Path::new("./v.mp4"),
+        state.deinterlace.load(Ordering::Relaxed),
+        playback,
+    );
+
+    let folder = app.path().app_log_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = folder.join(format!("telemetry-{stamp}.json"));
+
+    let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Cover picture embedded in the open file, for music without video.
+#[tauri::command]
+async fn get_cover_art(state: State<'_, AppState>) -> Result<tauri::ipc::Response, String> {
+    let path = state
+        .source
+        .lock()
+        .unwrap()
+        .clone()
+        .filter(|p| !network::is_url(p))
+        .ok_or("error.nothing-open")?;
+    let image = convert::cover_art(&path)
+        .map_err(|e| e.to_string())?
+        .ok_or("cover.none")?;
+    Ok(tauri::ipc::Response::new(image))
+}
+
+/// Runs `f` on the blocking thread pool, so decoding and waiting for the
+/// job scheduler don't hold up one of the few async workers.
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Seek bar preview for `time`, as PNG bytes.
+#[tauri::command]
+async fn get_thumbnail(
+    app: AppHandle,
+    time: f64,
+    scale: Option<f64>,
+) -> Result<tauri::ipc::Response, String> {
+    check_unlocked(&app, time, time)?;
+    let png = blocking(move || {
+        app.state::<thumbnail::Thumbnailer>()
+            .get(&"./v.mp4", time, scale.unwrap_or(1.0))
+    })
+    .await?;
+    Ok(tauri::ipc::Response::new(png))
+}
+
+/// Start and end of the intro the current file shares with its neighbours
+/// in the playlist, if there is one.
+#[tauri::command]
+async fn detect_intro(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    intros: State<'_, intro::Intros>,
+) -> Result<Option<(f64, f64)>, String> {
+    let (episode, other) = {
+        let playlist = state.playlist.lock().unwrap();
+        let current = playlist.current;
+        let Some(episode) = playlist.items.get(current).cloned() else {
+            return Ok(None);
+        };
+        // the next episode, or the previous one for the last
+        let other = playlist
+            .items
+            .get(current + 1)
+            .or(current.checked_sub(1).and_then(|i| playlist.items.get(i)))
+            .cloned();
+        let Some(other) = other else {
+            return Ok(None);
+        };
+        (episode, other)
+    };
+    if let Some(known) = intros.get(&episode) {
+        return Ok(known);
+    }
+
+    blocking(move || {
+        // nobody is waiting on this, so it steps aside for everything else
+        let scheduler = app.state::<jobs::Scheduler>();
+        let job = scheduler.start(jobs::Priority::Batch);
+        let intro =
+            intro::find(&episode, &other, || job.checkpoint()).map_err(|e| job.report(e))?;
+        app.state::<intro::Intros>().insert(&episode, intro);
+        Ok(intro)
+    })
+    .await
+}
+
+/// Times at which motion starts in the current file. Progress is reported
+/// through `motion-progress` events.
+#[tauri::command]
+async fn detect_motion(app: AppHandle) -> Result<Vec<f64>, String> {
+    blocking(move || {
+        let scheduler = app.state::<jobs::Scheduler>();
+        let job = scheduler.start(jobs::Priority::Current);
+        motion::detect("./v.mp4", &app.state::<config::Config>().motion, |p| {
+            job.checkpoint();
+            app.emit("motion-progress", p).unwrap();
+        })
+        .map_err(|e| job.report(e))
+    })
+    .await
+}
+
+/// Saves the frame at `time` as a PNG, with `shapes` drawn on it if there
+/// are any, and returns where it went.
+#[tauri::command]
+async fn save_screenshot(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    config: State<'_, config::Config>,
+    time: f64,
+    shapes: Option<Vec<annotate::Shape>>,
+) -> Result<PathBuf, String> {
+    check_unlocked(&app, time, time)?;
+    let shapes = shapes.unwrap_or_default();
+    let png =
+        blocking(move || screenshot::capture(&"./v.mp4", time, &shapes).map_err(|e| e.to_string()))
+            .await?;
+
+    let folder = config
+        .screenshot_folder
+        .clone()
+        .or_else(|| app.path().picture_dir().ok())
+        .ok_or("no screenshot folder")?;
+    std::fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+
+    let filename = state
+        .source
+        .lock()
+        .unwrap()
+        .as_deref()
+        .and_then(|p| p.file_stem())
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "v".into());
+
+    let path = naming::free_path(&folder, &config.screenshot_template, &filename, time)
+        .ok_or("could not find a free screenshot name")?;
+
+    std::fs::write(&path, png).map_err(|e| e.to_string())?;
+
+    hooks::run(
+        &app,
+        hooks::Event::Screenshot,
+        serde_json::json!({
+            "path": state.source.lock().unwrap().clone(),
+            "screenshot": path,
+            "time": time,
+        }),
+    );
+    Ok(path)
+}
+
+/// The frame at `time` as PNG bytes, with `shapes` drawn on it, for the
+/// player to put on the clipboard.
+#[tauri::command]
+async fn capture_frame(
+    app: AppHandle,
+    time: f64,
+    shapes: Option<Vec<annotate::Shape>>,
+) -> Result<tauri::ipc::Response, String> {
+    check_unlocked(&app, time, time)?;
+    let png = blocking(move || {
+        screenshot::capture(&"./v.mp4", time, &shapes.unwrap_or_default())
+            .map_err(|e| e.to_string())
+    })
+    .await?;
+    Ok(tauri::ipc::Response::new(png))
+}
+
+/// Saves every frame from `start` to `end` as numbered images in a new
+/// folder and returns the folder. Progress is reported through
+/// `sequence-progress` events.
+#[tauri::command]
+async fn export_sequence(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    config: State<'_, config::Config>,
+    start: f64,
+    end: f64,
+) -> Result<PathBuf, String> {
+    check_unlocked(&app, start, end)?;
+    let parent = config
+        .screenshot_folder
+        .clone()
+        .or_else(|| app.path().picture_dir().ok())
+        .ok_or("no screenshot folder")?;
+    let stem = state
+        .source
+        .lock()
+        .unwrap()
+        .as_deref()
+        .and_then(|p| p.file_stem())
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "v".into());
+    let folder = sequence::folder_for(&parent, &config.export_template, &stem, start)
+        .ok_or("could not find a free folder name")?;
+    let format = config.sequence_format;
+
+    blocking(move || {
+        let scheduler = app.state::<jobs::Scheduler>();
+        let job = scheduler.start(jobs::Priority::Batch);
+        // always a new folder, so all of it goes if the export can't finish
+        job.writes(&folder);
+        let count = sequence::export("./v.mp4", start, end, format, &folder, &stem, |p| {
+            job.checkpoint();
+            app.emit("sequence-progress", p).unwrap();
+        })
+        .map_err(|e| job.report(e))?;
+        if count == 0 {
+            return Err("sequence.empty".into());
+        }
+        Ok(folder)
+    })
+    .await
+}
+
+/// Saves `start` to `end` as an animated GIF or WebP next to the
+/// screenshots and returns where it went. Progress is reported through
+/// `clip-progress` events.
+#[tauri::command]
+async fn export_clip(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    config: State<'_, config::Config>,
+    start: f64,
+    end: f64,
+) -> Result<PathBuf, String> {
+    check_unlocked(&app, start, end)?;
+    let folder = config
+        .screenshot_folder
+        .clone()
+        .or_else(|| app.path().picture_dir().ok())
+        .ok_or("no screenshot folder")?;
+    std::fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+    let stem = state
+        .source
+        .lock()
+        .unwrap()
+        .as_deref()
+        .and_then(|p| p.file_stem())
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "v".into());
+    let extension = config.clip.format.extension();
+    let template = format!("{}.{extension}", config.export_template);
+    let path = naming::free_path(&folder, &template, &stem, start)
+        .ok_or("could not find a free clip name")?;
+
+    let options = config.clip;
+
+    blocking(move || {
+        let scheduler = app.state::<jobs::Scheduler>();
+        let job = scheduler.start(jobs::Priority::Batch);
+        job.writes(&path);
+        let result = clip::export("./v.mp4", start, end, &options, &path, |p| {
+            job.checkpoint();
+            app.emit("clip-progress", p).unwrap();
+        });
+        match result {
+            Ok(0) => {
+                let _ = std::fs::remove_file(&path);
+                Err("clip.empty".into())
+            }
+            Ok(_) => Ok(path),
+            Err(e) => {
+                let _ = std::fs::remove_file(&path);
+                Err(job.report(e))
+            }
+        }
+    })
+    .await
+}
+
+fn media_duration<P: AsRef<std::path::Path> + ?Sized>(path: &P) -> Option<f64> {
+    let ictx = probe::open(path).ok()?;
+    let duration = ictx.duration();
+    (duration > 0).then(|| duration as f64 / ffmpeg::ffi::AV_TIME_BASE as f64)
+}
+
+fn file_label(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Opens what a command line names: files, folders, `file#t=90` targets or
+/// a link, starting the first file where the flags say.
+fn open_inputs(h: &AppHandle, player: &cli::PlayerArgs) {
+    // Windows and Linux pass links as plain arguments
+    let link = player
+        .inputs
+        .iter()
+        .filter_map(|arg| tauri::Url::parse(&arg.to_string_lossy()).ok())
+        .find_map(|url| deeplink::parse(&url));
+    let mut args = Vec::new();
+    for arg in &player.inputs {
+        let path = PathBuf::from(arg);
+        if path.is_dir() {
+            match cli::video_files(vec![path]) {
+                Ok(files) => args.extend(files.into_iter().map(|p| (p, None))),
+                Err(e) => log::warn!("{}: {e}", arg.to_string_lossy()),
+            }
+        } else if path.is_file() {
+            args.push((path, None));
+        } else if let Some(target) = launch_target(&arg.to_string_lossy()) {
+            args.push(target);
+        }
+    }
+    if let Some(link) = link {
+        open_link(h.clone(), link);
+    } else if let Some((first, time)) = args.first() {
+        // only the first item starts playing right away
+        let start = match player.pause_at(first) {
+            Some(time) => Some(StartAt { time, paused: true }),
+            None => player.start.or(*time).map(|time| StartAt {
+                time,
+                paused: false,
+            }),
+        };
+        *h.state::<AppState>().start_at.lock().unwrap() = start;
+        open_playlist(h.clone(), args.into_iter().map(|(path, _)| path).collect());
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     ffmpeg::init().expect("ffmpeg libraries failed to initialize.");
-    tauri::Builder::default()
-        .register_asynchronous_uri_scheme_protocol("stream", move |_ctx, request, responder| {
-            match get_stream_response(request) {
+    avlog::install();
+    effects::register_builtins();
+
+    let cli = match cli::Cli::try_parse() {
+        Ok(cli) => cli,
+        // --help, --version and mistakes are printed
+        Err(e) => {
+            cli::attach_console();
+            e.exit();
+        }
+    };
+    if let Some(command) = cli.command {
+        cli::attach_console();
+        if let Err(e) = command.run() {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    let player = cli.player;
+    // the config's [probe] table is merged in once it is loaded
+    probe::set(player.probe_options());
+    if let Some(out) = &player.convert {
+        cli::attach_console();
+        if let Err(e) = player.convert(out) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    // WebView2 picks up extra Chromium switches from the environment
+    #[cfg(windows)]
+    if player.no_hwaccel {
+        std::env::set_var(
+            "WEBVIEW2_ADDITIONAL_BROWSER_ARGUMENTS",
+            "--disable-accelerated-video-decode",
+        );
+    }
+
+    let mut builder = tauri::Builder::default();
+    // a file opened from the file manager plays in the window already open;
+    // this has to be the first plugin
+    if !player.new_instance {
+        builder = builder.plugin(instance::plugin());
+    }
+    builder
+        .register_asynchronous_uri_scheme_protocol("stream", move |ctx, request, responder| {
+            match get_stream_response(request, ctx.app_handle()) {
                 Ok(http_response) => responder.respond(http_response),
                 Err(e) => responder.respond(
                     ResponseBuilder::new()
@@ -428,38 +1820,269 @@ pub fn run() {
             }
         })
         .on_window_event(|win, ev| match ev {
+            _ if compare::number(win.label()).is_some() => compare::window_event(win, ev),
             WindowEvent::DragDrop(ev) => match ev {
                 DragDropEvent::Drop { paths, .. } => {
+                    let h = win.app_handle().clone();
+
                     if paths.len() == 1 {
-                        if std::fs::exists("./v.mp4").unwrap() {
-                            std::fs::remove_file("./v.mp4").unwrap();
-                        }
-                        let h = win.app_handle().clone();
-                        let copy_path = paths[0].clone();
-
-                        if copy_path.extension().is_some()
-                            && copy_path.extension().unwrap() == "mp4"
-                        {
-                            std::fs::copy(copy_path, "./v.mp4").unwrap();
-                            h.emit("refresh-mega", ()).unwrap();
+                        // dropping a subtitle file loads it over the current video
+                        if subtitles::is_subtitle_file(&paths[0]) {
+                            *h.state::<AppState>().subtitles.lock().unwrap() =
+                                Some(subtitles::Selection::File(paths[0].clone()));
+                            h.emit("subs-changed", ()).unwrap();
+                        } else if let Some(format) = chapters::Format::from_path(&paths[0]) {
+                            import_markers(&h, &paths[0], format);
                         } else {
-                            std::thread::spawn(move || {
-                                convert_to_mp4(&copy_path, "./v.mp4", h.clone()).unwrap();
-                                h.emit("refresh-mega", ()).unwrap();
-                            });
+                            open_playlist(h, paths.clone());
                         }
+                    } else if !paths.is_empty() {
+                        // episodes are usually named in order
+                        let mut paths = paths.clone();
+                        paths.sort();
+                        open_playlist(h, paths);
                     }
                 }
                 _ => {}
             },
-            WindowEvent::CloseRequested { .. } => {
-                if std::fs::exists("./v.mp4").unwrap() {
-                    std::fs::remove_file("./v.mp4").unwrap();
+            WindowEvent::CloseRequested { api, .. } => {
+                // keeps playing hidden, for music and podcasts
+                let to_tray = win
+                    .try_state::<tray::Tray>()
+                    .is_some_and(|tray| !tray.quitting());
+                if to_tray && win.state::<config::Config>().close_to_tray {
+                    api.prevent_close();
+                    if let Err(e) = win.hide() {
+                        log::warn!("could not hide the window: {e}");
+                    }
+                    return;
+                }
+                // a fullscreen, minimized or picture-in-picture size is not
+                // one to come back to
+                let fullscreen =
+                    win.is_fullscreen().unwrap_or(true) || win.state::<pip::Pip>().active();
+                let minimized = win.is_minimized().unwrap_or(true);
+                let size = win
+                    .inner_size()
+                    .and_then(|s| Ok(s.to_logical::<f64>(win.scale_factor()?)));
+                if let (false, false, Ok(size)) = (fullscreen, minimized, size) {
+                    win.state::<settings::Store>().update(|s| {
+                        s.window = Some(settings::WindowSize {
+                            width: size.width,
+                            height: size.height,
+                        })
+                    });
+                }
+                save_session(win.app_handle());
+                if let Err(e) = remove_video() {
+                    log::warn!("could not remove v.mp4: {e}");
                 }
             }
             _ => {}
         })
+        .manage(AppState::default())
+        .manage(jobs::Scheduler::default())
+        .manage(readahead::ReadAhead::default())
+        .manage(compare::Windows::default())
+        .manage(pip::Pip::default())
+        .manage(intro::Intros::default())
+        .setup(move |app| {
+            app.manage(thumbnail::Thumbnailer::spawn(app.handle().clone()));
+            // not every desktop has a tray, and the player works fine without
+            match tray::create(app.handle()) {
+                Ok(tray) => {
+                    app.manage(tray);
+                }
+                Err(e) => log::warn!("no tray icon: {e}"),
+            }
+            let config = config::Config::load(app.handle());
+            avlog::set_level(&config.ffmpeg_log_level);
+            probe::set(config.probe.merge(&player.probe_options()));
+            app.state::<AppState>()
+                .deinterlace
+                .store(config.deinterlace, Ordering::Relaxed);
+            app.state::<AppState>()
+                .interpolate
+                .store(config.interpolation.enabled, Ordering::Relaxed);
+            if let Some(gamut) = config.display_gamut {
+                *app.state::<AppState>().display_gamut.lock().unwrap() = gamut;
+            }
+            let settings = settings::Store::load(app.handle());
+            let window = app.get_webview_window("main");
+            if let (Some(size), Some(window)) = (settings.get().window, window) {
+                let _ = window.set_size(tauri::LogicalSize::new(size.width, size.height));
+            }
+
+            // a manual toggle from last time wins over the config
+            let power_mode = match settings.get().power_saver {
+                Some(true) => power::Mode::On,
+                Some(false) => power::Mode::Off,
+                None => config.power_saver,
+            };
+            // before the watch folder, whose conversions ask for it
+            app.manage(power::PowerSaver::new(power_mode));
+            if power_mode == power::Mode::Auto {
+                power::spawn_auto(app.handle().clone());
+            }
+            app.manage(settings);
+            app.manage(resume::Positions::load(app.handle()));
+            app.manage(lock::Locks::load(app.handle()));
+            app.manage(recent::Recent::load(app.handle()));
+            app.manage(scripting::Scripts::load(app.handle(), &config.scripts));
+            app.manage(config);
+
+            // only now, as their threads ask for all of the above
+            let config = app.state::<config::Config>();
+            if let Some(dir) = &config.watch_folder {
+                match watch::spawn(app.handle().clone(), dir) {
+                    // the watcher stops when dropped, so hand it to tauri to keep alive
+                    Ok(watcher) => {
+                        app.manage(Mutex::new(watcher));
+                    }
+                    Err(e) => log::warn!("could not watch {}: {e}", dir.display()),
+                }
+            }
+            if let Some(port) = config.control_port {
+                if let Err(e) = control::spawn(app.handle().clone(), port) {
+                    log::warn!("could not listen on control port {port}: {e}");
+                }
+            }
+
+            // installers register the scheme, dev builds have to do it themselves
+            #[cfg(all(debug_assertions, any(windows, target_os = "linux")))]
+            if let Err(e) = app.deep_link().register_all() {
+                log::warn!("could not register {} links: {e}", deeplink::SCHEME);
+            }
+            let handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                if let Some(link) = event.urls().iter().find_map(deeplink::parse) {
+                    open_link(handle.clone(), link);
+                }
+            });
+
+            app.manage(player.clone());
+            if player.fullscreen {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.set_fullscreen(true);
+                }
+            }
+
+            open_inputs(app.handle(), &player);
+
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            get_subtitles,
+            cycle_subtitles,
+            get_chapters,
+            get_bookmarks,
+            add_bookmark,
+            export_markers,
+            export_transcript,
+            probe_streams,
+            convert_with_mapping,
+            get_video_info,
+            export_telemetry,
+            get_playlist,
+            get_recent,
+            open_recent,
+            playlist_step,
+            open_window,
+            window_ready,
+            save_screenshot,
+            capture_frame,
+            export_sequence,
+            export_clip,
+            get_session,
+            get_locks,
+            check_pin,
+            toggle_file_lock,
+            lock_segment,
+            restore_session,
+            cycle_audio_track,
+            open_media,
+            set_display_gamut,
+            toggle_deinterlace,
+            toggle_interpolation,
+            share_link,
+            take_start_time,
+            get_resume_position,
+            save_position,
+            get_language,
+            get_appearance,
+            get_osd_theme,
+            get_thumbnail,
+            get_cover_art,
+            detect_motion,
+            detect_intro,
+            get_keymap,
+            get_seek_steps,
+            get_power_saver,
+            toggle_power_saver,
+            toggle_pip,
+            set_awake,
+            get_settings,
+            get_launch_options,
+            update_settings,
+            playback_finished,
+            script_time
+        ])
         .plugin(tauri_plugin_fs::init())
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .plugin(tauri_plugin_log::Builder::new().build())
+        .plugin(tauri_plugin_deep_link::init())
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                shutdown(app);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Route;
+
+    fn parse(path: &str) -> Option<Route> {
+        let uri = format!("stream://localhost{path}")
+            .parse::<http::Uri>()
+            .unwrap();
+        Route::parse(&uri)
+    }
+
+    #[test]
+    fn serves_the_main_video() {
+        assert!(matches!(parse("/v.mp4"), Some(Route::Video)));
+        assert!(matches!(parse("/v%2Emp4"), Some(Route::Video)));
+    }
+
+    #[test]
+    fn serves_extra_windows_by_number() {
+        assert!(matches!(parse("/v-1.mp4"), Some(Route::Window(1))));
+        assert!(matches!(parse("/v%2D12.mp4"), Some(Route::Window(12))));
+        assert!(parse("/v-.mp4").is_none());
+        assert!(parse("/v-+1.mp4").is_none());
+        assert!(parse("/v--1.mp4").is_none());
+        assert!(parse("/v-1x.mp4").is_none());
+    }
+
+    #[test]
+    fn rejects_spaces_and_unicode() {
+        assert!(parse("/v%20.mp4").is_none());
+        assert!(parse("/%20v.mp4").is_none());
+        assert!(parse("/v%C3%A9.mp4").is_none());
+        // not UTF-8 at all
+        assert!(parse("/v%FF.mp4").is_none());
+    }
+
+    #[test]
+    fn rejects_other_paths() {
+        assert!(parse("/").is_none());
+        assert!(parse("/../v.mp4").is_none());
+        assert!(parse("/%2E%2E/v.mp4").is_none());
+        assert!(parse("/..%2Fv.mp4").is_none());
+        assert!(parse("/sub/v.mp4").is_none());
+        assert!(parse("/etc/passwd").is_none());
+        assert!(parse("/v.mp4.part").is_none());
+    }
 }
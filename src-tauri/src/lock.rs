@@ -0,0 +1,167 @@
+use crate::json_store::{path_key, JsonStore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, fmt::Write, path::Path, sync::Mutex};
+use tauri::AppHandle;
+
+/// PINs shorter than this are too easy to guess by watching.
+const MIN_PIN_LENGTH: usize = 4;
+
+/// A stretch of a file that needs the PIN to play through.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// What is locked in one file.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct FileLock {
+    /// Nothing plays without the PIN.
+    pub whole: bool,
+    pub segments: Vec<Segment>,
+}
+
+impl FileLock {
+    fn is_empty(&self) -> bool {
+        !self.whole && self.segments.is_empty()
+    }
+
+    /// Whether anything from `start` to `end` is locked. Segments include
+    /// their start but not their end, as in the player.
+    fn covers(&self, start: f64, end: f64) -> bool {
+        self.whole
+            || self
+                .segments
+                .iter()
+                .any(|s| s.start <= end && start < s.end)
+    }
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct Data {
+    /// `salt:hash` in hex; `None` until the first lock sets it.
+    pin: Option<String>,
+    files: HashMap<String, FileLock>,
+}
+
+/// Locked files and segments for shared machines, kept in `locks.json` in
+/// the app data dir along with a salted hash of the PIN. One PIN covers all
+/// of them.
+pub struct Locks {
+    store: JsonStore,
+    data: Mutex<Data>,
+    /// The file the PIN was entered for since it was opened, by
+    /// [`path_key`].
+    unlocked: Mutex<Option<String>>,
+}
+
+impl Locks {
+    pub fn load(app: &AppHandle) -> Self {
+        let store = JsonStore::new(app, "locks.json");
+        let data = store.load();
+
+        Self {
+            store,
+            data: Mutex::new(data),
+            unlocked: Mutex::new(None),
+        }
+    }
+
+    pub fn get(&self, source: &Path) -> FileLock {
+        let data = self.data.lock().unwrap();
+        data.files
+            .get(&path_key(source))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Whether `pin` is the PIN. Always false before one was set.
+    pub fn check(&self, pin: &str) -> bool {
+        let data = self.data.lock().unwrap();
+        let Some((salt, hash)) = data.pin.as_deref().and_then(|p| p.split_once(':')) else {
+            return false;
+        };
+        digest(salt, pin) == hash
+    }
+
+    /// Checks `pin` and, if it is the PIN, lets all of `source` through
+    /// [`Locks::allows`] until [`Locks::relock`].
+    pub fn unlock(&self, source: &Path, pin: &str) -> bool {
+        let ok = self.check(pin);
+        if ok {
+            *self.unlocked.lock().unwrap() = Some(path_key(source));
+        }
+        ok
+    }
+
+    /// Forgets the PIN that was entered, for when another file opens.
+    pub fn relock(&self) {
+        *self.unlocked.lock().unwrap() = None;
+    }
+
+    /// Whether the PIN was entered for `source`.
+    pub fn unlocked(&self, source: &Path) -> bool {
+        self.unlocked.lock().unwrap().as_deref() == Some(&*path_key(source))
+    }
+
+    /// Whether frames from `start` to `end` of `source` may be shown:
+    /// nothing there is locked, or the PIN was entered for it.
+    pub fn allows(&self, source: &Path, start: f64, end: f64) -> bool {
+        if self.unlocked(source) {
+            return true;
+        }
+        let data = self.data.lock().unwrap();
+        !data
+            .files
+            .get(&path_key(source))
+            .is_some_and(|lock| lock.covers(start, end))
+    }
+
+    /// Changes the locks of `source` after checking `pin`. The first change
+    /// ever makes `pin` the PIN.
+    pub fn update(
+        &self,
+        source: &Path,
+        pin: &str,
+        f: impl FnOnce(&mut FileLock),
+    ) -> Result<FileLock, String> {
+        let mut data = self.data.lock().unwrap();
+        match data.pin.as_deref().and_then(|p| p.split_once(':')) {
+            Some((salt, hash)) if digest(salt, pin) != hash => return Err("lock.wrong-pin".into()),
+            Some(_) => {}
+            None if pin.chars().count() < MIN_PIN_LENGTH => return Err("lock.short-pin".into()),
+            None => {
+                let mut salt = [0u8; 16];
+                getrandom::fill(&mut salt).map_err(|e| e.to_string())?;
+                let salt = hex(&salt);
+                data.pin = Some(format!("{salt}:{}", digest(&salt, pin)));
+            }
+        }
+
+        let key = path_key(source);
+        let mut lock = data.files.remove(&key).unwrap_or_default();
+        f(&mut lock);
+        if !lock.is_empty() {
+            data.files.insert(key.clone(), lock.clone());
+        }
+        self.store.save(&*data);
+        // whoever just changed the locks knows the PIN
+        *self.unlocked.lock().unwrap() = Some(key);
+        Ok(lock)
+    }
+}
+
+fn digest(salt: &str, pin: &str) -> String {
+    hex(&Sha256::new()
+        .chain_update(salt)
+        .chain_update(pin)
+        .finalize())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
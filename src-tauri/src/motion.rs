@@ -0,0 +1,127 @@
+use ffmpeg_next::{self as ffmpeg, format::Pixel, frame::Video, media, software::scaling};
+use serde::Deserialize;
+use std::{error::Error, path::Path};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Frames are compared at this size, which is plenty to notice movement and
+/// keeps noise from counting.
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 36;
+/// Seconds between compared frames.
+const SAMPLE_INTERVAL: f64 = 0.25;
+
+/// `[motion]` table: how much has to change for a frame to count as motion.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct MotionOptions {
+    /// A pixel changed when its brightness moved by more than this (0-255).
+    pub pixel_threshold: u8,
+    /// Fraction of the picture that has to change.
+    pub min_area: f64,
+    /// Motion closer together than this many seconds is one event.
+    pub merge_gap: f64,
+}
+
+impl Default for MotionOptions {
+    fn default() -> Self {
+        Self {
+            pixel_threshold: 25,
+            min_area: 0.005,
+            merge_gap: 3.0,
+        }
+    }
+}
+
+/// Scans the whole file and returns when each stretch of motion starts.
+/// `on_progress` gets the fraction done after every sampled frame.
+pub fn detect<P: AsRef<Path> + ?Sized>(
+    path: &P,
+    options: &MotionOptions,
+    mut on_progress: impl FnMut(f64),
+) -> BasicResult<Vec<f64>> {
+    let mut ictx = ffmpeg::format::input(path)?;
+    let stream = ictx
+        .streams()
+        .best(media::Type::Video)
+        .ok_or("no video stream")?;
+    let index = stream.index();
+    let time_base = f64::from(stream.time_base());
+    let duration = ictx.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64;
+
+    let context_decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+    let mut decoder = context_decoder.decoder().video()?;
+    let mut scaler = scaling::Context::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        Pixel::GRAY8,
+        WIDTH,
+        HEIGHT,
+        scaling::Flags::AREA,
+    )?;
+
+    let mut events = Vec::new();
+    let mut previous: Option<Vec<u8>> = None;
+    let mut last_sample = f64::NEG_INFINITY;
+    let mut last_motion = f64::NEG_INFINITY;
+
+    let mut compare = |frame: &Video| -> BasicResult<()> {
+        let time = frame.pts().unwrap_or(0) as f64 * time_base;
+        if time - last_sample < SAMPLE_INTERVAL {
+            return Ok(());
+        }
+        last_sample = time;
+
+        let mut gray = Video::empty();
+        scaler.run(frame, &mut gray)?;
+        let stride = gray.stride(0);
+        let pixels = gray
+            .data(0)
+            .chunks(stride)
+            .take(HEIGHT as usize)
+            .flat_map(|line| &line[..WIDTH as usize])
+            .copied()
+            .collect::<Vec<_>>();
+
+        if let Some(previous) = &previous {
+            let changed = pixels
+                .iter()
+                .zip(previous)
+                .filter(|(a, b)| a.abs_diff(**b) > options.pixel_threshold)
+                .count();
+            if changed as f64 / pixels.len() as f64 >= options.min_area {
+                if time - last_motion > options.merge_gap {
+                    events.push(time);
+                }
+                last_motion = time;
+            }
+        }
+        previous = Some(pixels);
+
+        if duration > 0.0 {
+            on_progress((time / duration).min(1.0));
+        }
+        Ok(())
+    };
+
+    let mut frame = Video::empty();
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != index {
+            continue;
+        }
+        // damaged footage is common with cameras, skip what doesn't decode
+        if decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+        while decoder.receive_frame(&mut frame).is_ok() {
+            compare(&frame)?;
+        }
+    }
+    decoder.send_eof()?;
+    while decoder.receive_frame(&mut frame).is_ok() {
+        compare(&frame)?;
+    }
+
+    Ok(events)
+}
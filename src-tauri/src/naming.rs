@@ -0,0 +1,103 @@
+use std::path::{Path, PathBuf};
+
+/// How many names [`free_path`] tries before giving up.
+const MAX_TRIES: usize = 10_000;
+
+/// Values available to filename templates.
+pub struct NameContext<'a> {
+    /// Source file name without its extension.
+    pub filename: &'a str,
+    /// Playback position in seconds.
+    pub time: f64,
+    /// Running counter, so repeated exports of the same instant don't collide.
+    pub n: usize,
+}
+
+/// Expands `{filename}`, `{timecode}` and `{n}` in `template`. Unknown
+/// placeholders are kept as written, and characters that are not allowed in
+/// file names are replaced with `_`.
+pub fn render(template: &str, ctx: &NameContext) -> String {
+    let mut out = String::with_capacity(template.len() + ctx.filename.len());
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        let Some(close) = rest.find('}') else {
+            break;
+        };
+        match &rest[1..close] {
+            "filename" => out.push_str(ctx.filename),
+            "timecode" => out.push_str(&timecode(ctx.time)),
+            "n" => out.push_str(&ctx.n.to_string()),
+            _ => out.push_str(&rest[..=close]),
+        }
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+
+    out.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// The first name from `template` in `folder` that isn't taken, counting
+/// `{n}` up from 1. `None` when all tries are taken, as they are at once when
+/// the template has no `{n}` and its one name exists.
+pub fn free_path(folder: &Path, template: &str, filename: &str, time: f64) -> Option<PathBuf> {
+    (1..=MAX_TRIES)
+        .map(|n| folder.join(render(template, &NameContext { filename, time, n })))
+        .find(|p| !p.exists())
+}
+
+/// `HH-MM-SS.mmm`, using dashes since `:` is not valid in Windows file names.
+fn timecode(time: f64) -> String {
+    let millis = (time.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}-{:02}-{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, NameContext};
+
+    fn name(template: &str, filename: &str, time: f64) -> String {
+        let ctx = NameContext {
+            filename,
+            time,
+            n: 2,
+        };
+        render(template, &ctx)
+    }
+
+    #[test]
+    fn fills_in_placeholders() {
+        assert_eq!(
+            name("{filename}_{timecode}_{n}.png", "clip", 3723.5),
+            "clip_01-02-03.500_2.png"
+        );
+        assert_eq!(name("{timecode}", "clip", -1.0), "00-00-00.000");
+    }
+
+    #[test]
+    fn keeps_unknown_and_unclosed_placeholders() {
+        assert_eq!(name("{filename}-{foo}", "clip", 0.0), "clip-{foo}");
+        assert_eq!(name("a{filename", "clip", 0.0), "a{filename");
+    }
+
+    #[test]
+    fn replaces_characters_not_allowed_in_file_names() {
+        assert_eq!(name("{filename}.png", "a/b:c?", 0.0), "a_b_c_.png");
+        assert_eq!(name("x\ty|z", "clip", 0.0), "x_y_z");
+    }
+}
@@ -0,0 +1,156 @@
+use crate::probe::ProbeOptions;
+use ffmpeg_next::{self as ffmpeg, codec, media, Dictionary};
+use std::{collections::HashMap, error::Error, path::Path};
+use tauri::{AppHandle, Emitter, Manager};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Seconds downloaded before playback starts.
+const START_BUFFER: f64 = 3.0;
+
+/// Codecs the webview plays straight out of an MP4. Streams in anything else
+/// go through the regular conversion, which only works for ones that end.
+const PLAYABLE: [codec::Id; 5] = [
+    codec::Id::H264,
+    codec::Id::AV1,
+    codec::Id::VP9,
+    codec::Id::AAC,
+    codec::Id::MP3,
+];
+
+/// Whether `path` is really a URL ffmpeg should fetch, like
+/// `https://...`, `rtsp://...` or `rtmp://...`.
+pub fn is_url(path: &Path) -> bool {
+    path.to_str()
+        .and_then(|s| s.split_once("://"))
+        .is_some_and(|(scheme, _)| {
+            scheme.len() > 1 && scheme.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+/// Plays a network stream while it downloads. Its packets are copied into
+/// `./v.mp4` as fragmented MP4. Once a few seconds are in, `refresh-mega`
+/// starts playback, and `source-grew` and `source-done` follow as for
+/// [`crate::follow`]. The download stops when another file is opened.
+pub fn spawn(app: AppHandle, url: std::path::PathBuf) {
+    std::thread::spawn(move || match remux(&app, &url) {
+        Ok(true) => {}
+        Ok(false) => crate::convert_source(app, url, Default::default()),
+        Err(e) => app.emit("c-error", e.to_string()).unwrap(),
+    });
+}
+
+fn input_options(url: &str) -> Dictionary<'static> {
+    // live streams have no end to read up to, a second is enough to start
+    // unless the `[probe]` table says otherwise
+    let network = ProbeOptions {
+        probesize: Some(1_000_000),
+        analyzeduration: Some(1_000_000),
+        fflags: None,
+    };
+    let mut opts = network.merge(&crate::probe::options()).dictionary();
+    // give up on a dead server instead of hanging, in microseconds
+    opts.set("rw_timeout", "15000000");
+    if url.starts_with("http") {
+        opts.set("reconnect", "1");
+        opts.set("reconnect_streamed", "1");
+    }
+    if url.starts_with("rtsp") {
+        // UDP drops packets behind most NATs
+        opts.set("rtsp_transport", "tcp");
+    }
+    opts
+}
+
+/// Returns false without writing anything when the stream needs converting.
+fn remux(app: &AppHandle, url: &Path) -> BasicResult<bool> {
+    let url_str = url.to_string_lossy();
+    let mut ictx = ffmpeg::format::input_with_dictionary(&url, input_options(&url_str))?;
+
+    let streams = [media::Type::Video, media::Type::Audio]
+        .into_iter()
+        .filter_map(|kind| ictx.streams().best(kind))
+        .collect::<Vec<_>>();
+    if streams.is_empty() {
+        return Err("no audio or video in stream".into());
+    }
+    if !streams
+        .iter()
+        .all(|s| PLAYABLE.contains(&s.parameters().id()))
+    {
+        return Ok(false);
+    }
+
+    let mut octx = ffmpeg::format::output_as(&"./v.mp4", "mp4")?;
+    // input stream index -> output stream index and input time base
+    let mut map = HashMap::new();
+    for stream in &streams {
+        let mut out = octx.add_stream(ffmpeg::encoder::find(codec::Id::None))?;
+        out.set_parameters(stream.parameters());
+        // the input container's codec tag is usually meaningless in mp4
+        unsafe {
+            (*out.parameters().as_mut_ptr()).codec_tag = 0;
+        }
+        map.insert(stream.index(), (out.index(), stream.time_base()));
+    }
+    // the first stream is video if there is any; its clock is the one shown
+    let clock = streams[0].index();
+    drop(streams);
+
+    let mut opts = Dictionary::new();
+    // a header up front and a fragment per keyframe, so the file plays while
+    // it is being written
+    opts.set("movflags", "frag_keyframe+empty_moov+default_base_moof");
+    octx.write_header_with(opts)?;
+
+    // live streams start at whatever their clock says; shift that to zero
+    let mut origin = None;
+    let mut started = false;
+    let mut reported = 0.0;
+
+    for (stream, mut packet) in ictx.packets() {
+        let state = app.state::<crate::AppState>();
+        // another file is open and its events are not ours to send; the
+        // unfinished v.mp4 is already being replaced
+        if state.source.lock().unwrap().as_deref() != Some(url) {
+            return Ok(true);
+        }
+        let Some(&(out_index, in_tb)) = map.get(&stream.index()) else {
+            continue;
+        };
+        let Some(dts) = packet.dts().or(packet.pts()) else {
+            continue;
+        };
+
+        let time = dts as f64 * f64::from(in_tb);
+        let origin = *origin.get_or_insert(time);
+        let offset = (origin / f64::from(in_tb)) as i64;
+        packet.set_pts(packet.pts().map(|t| t - offset));
+        packet.set_dts(packet.dts().map(|t| t - offset));
+
+        if stream.index() == clock {
+            let buffered = time - origin;
+            if !started && buffered >= START_BUFFER {
+                started = true;
+                app.emit("refresh-mega", ()).unwrap();
+            } else if started && buffered - reported >= 1.0 {
+                reported = buffered;
+                app.emit("source-grew", buffered).unwrap();
+            }
+        }
+
+        packet.rescale_ts(in_tb, octx.stream(out_index).unwrap().time_base());
+        packet.set_stream(out_index);
+        packet.set_position(-1);
+        packet.write_interleaved(&mut octx)?;
+    }
+    octx.write_trailer()?;
+
+    if started {
+        app.emit("source-done", ()).unwrap();
+    } else {
+        // shorter than the start buffer, so nothing is playing yet
+        app.emit("refresh-mega", ()).unwrap();
+    }
+    Ok(true)
+}
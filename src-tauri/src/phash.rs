@@ -0,0 +1,259 @@
+use ffmpeg_next::{self as ffmpeg, decoder, format::Pixel, frame::Video, media, software::scaling};
+use std::{
+    error::Error,
+    f64::consts::PI,
+    path::{Path, PathBuf},
+};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Frames are shrunk to `SIZE`x`SIZE` grey and the lowest `LOW`x`LOW`
+/// frequencies of their DCT make up the 64 bit hash.
+const SIZE: usize = 32;
+const LOW: usize = 8;
+/// Where in each file frames are hashed, as fractions of its length.
+const SAMPLES: [f64; 5] = [0.1, 0.3, 0.5, 0.7, 0.9];
+/// Files whose lengths differ by more than this fraction can't be copies.
+const DURATION_TOLERANCE: f64 = 0.02;
+/// Average number of differing hash bits below which two files match.
+const DEFAULT_THRESHOLD: f64 = 10.0;
+
+/// Perceptual hashes of a few frames spread over a video. Re-encodes,
+/// resizes and small quality changes barely move them.
+pub struct Fingerprint {
+    pub duration: f64,
+    pub hashes: Vec<u64>,
+}
+
+impl Fingerprint {
+    pub fn of<P: AsRef<Path> + ?Sized>(path: &P) -> BasicResult<Self> {
+        let mut ictx = ffmpeg::format::input(path)?;
+        let stream = ictx
+            .streams()
+            .best(media::Type::Video)
+            .ok_or("no video stream")?;
+        let index = stream.index();
+        let duration = ictx.duration().max(0) as f64 / ffmpeg::ffi::AV_TIME_BASE as f64;
+
+        let context_decoder =
+            ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+        let mut decoder = context_decoder.decoder().video()?;
+        let mut hasher = FrameHasher::new(&decoder)?;
+
+        let mut hashes = Vec::with_capacity(SAMPLES.len());
+        for fraction in SAMPLES {
+            let ts = (duration * fraction * ffmpeg::ffi::AV_TIME_BASE as f64) as i64;
+            ictx.seek(ts, ..=ts)?;
+            decoder.flush();
+
+            let mut frame = Video::empty();
+            let mut found = false;
+            for (stream, packet) in ictx.packets() {
+                if stream.index() != index || decoder.send_packet(&packet).is_err() {
+                    continue;
+                }
+                if decoder.receive_frame(&mut frame).is_ok() {
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                return Err(format!("no frame near {:.0}%", fraction * 100.0).into());
+            }
+
+            hashes.push(hasher.hash(&frame)?);
+        }
+
+        Ok(Self { duration, hashes })
+    }
+
+    /// Average differing bits per sampled frame, or `None` when the lengths
+    /// already rule out a match.
+    pub fn distance(&self, other: &Fingerprint) -> Option<f64> {
+        let longer = self.duration.max(other.duration);
+        if longer <= 0.0 || (self.duration - other.duration).abs() / longer > DURATION_TOLERANCE {
+            return None;
+        }
+        let bits = self
+            .hashes
+            .iter()
+            .zip(&other.hashes)
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum::<u32>();
+        Some(bits as f64 / self.hashes.len().max(1) as f64)
+    }
+}
+
+/// Hashes of one frame per `interval` seconds over the first `limit`
+/// seconds of a video, with the time of each.
+pub fn timeline<P: AsRef<Path> + ?Sized>(
+    path: &P,
+    interval: f64,
+    limit: f64,
+    mut checkpoint: impl FnMut(),
+) -> BasicResult<Vec<(f64, u64)>> {
+    let mut ictx = ffmpeg::format::input(path)?;
+    let stream = ictx
+        .streams()
+        .best(media::Type::Video)
+        .ok_or("no video stream")?;
+    let index = stream.index();
+    let time_base = f64::from(stream.time_base());
+
+    let context_decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+    let mut decoder = context_decoder.decoder().video()?;
+    let mut hasher = FrameHasher::new(&decoder)?;
+
+    let mut out = Vec::new();
+    let mut frame = Video::empty();
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != index || decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+        while decoder.receive_frame(&mut frame).is_ok() {
+            let time = frame.pts().unwrap_or(0) as f64 * time_base;
+            if time > limit {
+                return Ok(out);
+            }
+            if out.last().is_some_and(|&(last, _)| time - last < interval) {
+                continue;
+            }
+            checkpoint();
+            out.push((time, hasher.hash(&frame)?));
+        }
+    }
+    Ok(out)
+}
+
+/// Shrinks decoded frames for [`hash`].
+struct FrameHasher {
+    scaler: scaling::Context,
+}
+
+impl FrameHasher {
+    fn new(decoder: &decoder::Video) -> Result<Self, ffmpeg::Error> {
+        let scaler = scaling::Context::get(
+            decoder.format(),
+            decoder.width(),
+            decoder.height(),
+            Pixel::GRAY8,
+            SIZE as u32,
+            SIZE as u32,
+            scaling::Flags::AREA,
+        )?;
+        Ok(Self { scaler })
+    }
+
+    fn hash(&mut self, frame: &Video) -> Result<u64, ffmpeg::Error> {
+        let mut gray = Video::empty();
+        self.scaler.run(frame, &mut gray)?;
+        let stride = gray.stride(0);
+        let pixels = gray
+            .data(0)
+            .chunks(stride)
+            .take(SIZE)
+            .flat_map(|line| line[..SIZE].iter().map(|&p| p as f64))
+            .collect::<Vec<_>>();
+        Ok(hash(&pixels))
+    }
+}
+
+/// pHash of a `SIZE`x`SIZE` grey image: a bit per low frequency, set when
+/// it is above the median.
+fn hash(pixels: &[f64]) -> u64 {
+    // cos((2x + 1) u pi / 2N), the DCT-II basis
+    let basis = |u: usize, x: usize| ((2 * x + 1) as f64 * u as f64 * PI / (2 * SIZE) as f64).cos();
+
+    // rows first, then columns, only keeping the low frequencies
+    let mut rows = vec![0.0; SIZE * LOW];
+    for y in 0..SIZE {
+        for u in 0..LOW {
+            rows[y * LOW + u] = (0..SIZE).map(|x| pixels[y * SIZE + x] * basis(u, x)).sum();
+        }
+    }
+    let mut coeffs = Vec::with_capacity(LOW * LOW);
+    for v in 0..LOW {
+        for u in 0..LOW {
+            coeffs.push(
+                (0..SIZE)
+                    .map(|y| rows[y * LOW + u] * basis(v, y))
+                    .sum::<f64>(),
+            );
+        }
+    }
+
+    // the DC term is just the overall brightness, leave it out of the median
+    let mut sorted = coeffs[1..].to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let median = sorted[sorted.len() / 2];
+
+    coeffs.iter().enumerate().fold(
+        0,
+        |hash, (i, &c)| if c > median { hash | (1 << i) } else { hash },
+    )
+}
+
+/// Lists groups of videos that look the same, one file per line and a blank
+/// line between groups.
+#[derive(clap::Args)]
+pub struct CliArgs {
+    /// Videos, or folders of them.
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+    /// Average differing bits per frame hash still counted as the same.
+    #[arg(long, default_value_t = DEFAULT_THRESHOLD)]
+    threshold: f64,
+}
+
+pub fn run_cli(args: CliArgs) -> BasicResult<()> {
+    let threshold = args.threshold;
+    let prints = crate::cli::video_files(args.inputs)?
+        .into_iter()
+        .filter_map(|file| match Fingerprint::of(&file) {
+            Ok(print) => Some((file, print)),
+            Err(e) => {
+                eprintln!("{}: {e}", file.display());
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // every file starts in its own group; matching files merge their groups
+    let mut group = (0..prints.len()).collect::<Vec<_>>();
+    for i in 0..prints.len() {
+        for j in i + 1..prints.len() {
+            if prints[i]
+                .1
+                .distance(&prints[j].1)
+                .is_some_and(|d| d <= threshold)
+            {
+                let (from, to) = (group[j], group[i]);
+                group
+                    .iter_mut()
+                    .filter(|g| **g == from)
+                    .for_each(|g| *g = to);
+            }
+        }
+    }
+
+    let mut found = false;
+    for leader in 0..prints.len() {
+        let members = (0..prints.len())
+            .filter(|&i| group[i] == leader)
+            .collect::<Vec<_>>();
+        if members.len() < 2 {
+            continue;
+        }
+        if found {
+            println!();
+        }
+        found = true;
+        for i in members {
+            println!("{}", prints[i].0.display());
+        }
+    }
+    if !found {
+        println!("no duplicates found");
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use std::sync::Mutex;
+use tauri::{LogicalSize, PhysicalPosition, PhysicalSize, WebviewWindow};
+
+/// Width of the picture-in-picture window in logical pixels; the height
+/// follows the video.
+const PIP_WIDTH: f64 = 480.0;
+/// Gap to the corner of the screen, in logical pixels.
+const MARGIN: f64 = 16.0;
+
+/// How the window looked before it went picture-in-picture.
+struct Saved {
+    size: PhysicalSize<u32>,
+    position: PhysicalPosition<i32>,
+    decorated: bool,
+    fullscreen: bool,
+}
+
+/// Shrinks the window to a small undecorated one that stays on top in the
+/// bottom right corner, and puts it back.
+#[derive(Default)]
+pub struct Pip {
+    saved: Mutex<Option<Saved>>,
+}
+
+impl Pip {
+    pub fn active(&self) -> bool {
+        self.saved.lock().unwrap().is_some()
+    }
+
+    /// Switches picture-in-picture on or off and returns whether it is on
+    /// now. `aspect` is the video's width over its height.
+    pub fn toggle(&self, window: &WebviewWindow, aspect: f64) -> tauri::Result<bool> {
+        let mut saved = self.saved.lock().unwrap();
+
+        if let Some(before) = saved.take() {
+            window.set_always_on_top(false)?;
+            window.set_decorations(before.decorated)?;
+            window.set_size(before.size)?;
+            window.set_position(before.position)?;
+            if before.fullscreen {
+                window.set_fullscreen(true)?;
+            }
+            return Ok(false);
+        }
+
+        let fullscreen = window.is_fullscreen()?;
+        if fullscreen {
+            window.set_fullscreen(false)?;
+        }
+        *saved = Some(Saved {
+            size: window.inner_size()?,
+            position: window.outer_position()?,
+            decorated: window.is_decorated()?,
+            fullscreen,
+        });
+
+        let aspect = if aspect.is_finite() && aspect > 0.0 {
+            aspect
+        } else {
+            16.0 / 9.0
+        };
+        let scale = window.scale_factor()?;
+        let size = LogicalSize::new(PIP_WIDTH, PIP_WIDTH / aspect).to_physical::<i32>(scale);
+
+        window.set_decorations(false)?;
+        window.set_size(PhysicalSize::new(size.width as u32, size.height as u32))?;
+        window.set_always_on_top(true)?;
+        if let Some(monitor) = window.current_monitor()? {
+            // the work area leaves out the taskbar
+            let area = monitor.work_area();
+            let margin = (MARGIN * scale) as i32;
+            window.set_position(PhysicalPosition::new(
+                area.position.x + area.size.width as i32 - size.width - margin,
+                area.position.y + area.size.height as i32 - size.height - margin,
+            ))?;
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,116 @@
+use serde::Deserialize;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often auto mode looks at the power source.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// `power_saver` in the config.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// On while running on battery.
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+
+/// Whether to go easy on the battery: conversions use fewer threads and a
+/// cheaper encoder preset and skip effects, and the player drops its
+/// colour adjustments.
+pub struct PowerSaver {
+    active: AtomicBool,
+    /// Set once the user toggles it by hand, which ends auto mode.
+    manual: AtomicBool,
+}
+
+impl PowerSaver {
+    pub fn new(mode: Mode) -> Self {
+        Self {
+            active: AtomicBool::new(match mode {
+                Mode::Auto => on_battery(),
+                Mode::On => true,
+                Mode::Off => false,
+            }),
+            manual: AtomicBool::new(mode != Mode::Auto),
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Flips power saving by hand and returns whether it is on now.
+    pub fn toggle(&self, app: &AppHandle) -> bool {
+        self.manual.store(true, Ordering::Relaxed);
+        let active = !self.active();
+        self.set(app, active);
+        active
+    }
+
+    fn set(&self, app: &AppHandle, active: bool) {
+        if self.active.swap(active, Ordering::Relaxed) != active {
+            app.emit("power-saver", active).unwrap();
+        }
+    }
+}
+
+/// Follows the power source until the user takes over.
+pub fn spawn_auto(app: AppHandle) {
+    std::thread::spawn(move || {
+        let saver = app.state::<PowerSaver>();
+        while !saver.manual.load(Ordering::Relaxed) {
+            saver.set(&app, on_battery());
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+#[cfg(windows)]
+pub fn on_battery() -> bool {
+    #[repr(C)]
+    #[derive(Default)]
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+
+    let mut status = SystemPowerStatus::default();
+    // 0 is offline, 1 online and 255 unknown
+    unsafe { GetSystemPowerStatus(&mut status) != 0 && status.ac_line_status == 0 }
+}
+
+#[cfg(target_os = "linux")]
+pub fn on_battery() -> bool {
+    let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+    let read = |dir: &std::path::Path, name: &str| {
+        std::fs::read_to_string(dir.join(name)).unwrap_or_default()
+    };
+
+    // desktops have no mains supply listed at all, so look for one that is
+    // there but unplugged
+    supplies.filter_map(|e| e.ok()).any(|e| {
+        let dir = e.path();
+        read(&dir, "type").trim() == "Mains" && read(&dir, "online").trim() == "0"
+    })
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn on_battery() -> bool {
+    false
+}
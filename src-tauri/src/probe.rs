@@ -0,0 +1,61 @@
+use ffmpeg_next::{self as ffmpeg, format::context::Input, Dictionary};
+use serde::Deserialize;
+use std::{path::Path, sync::RwLock};
+
+/// `[probe]` table: how much of a file libav reads to find its streams
+/// before anything else happens. Lower opens large files faster but may
+/// miss streams that start late, such as subtitles deep into an MKV.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ProbeOptions {
+    /// Bytes read while looking for streams; libav's default is 5000000.
+    pub probesize: Option<u64>,
+    /// Microseconds of media analysed for stream parameters; libav's default
+    /// is 5000000.
+    pub analyzeduration: Option<u64>,
+    /// Demuxer flags in ffmpeg's `-fflags` syntax, e.g. `+genpts` or
+    /// `+discardcorrupt`.
+    pub fflags: Option<String>,
+}
+
+impl ProbeOptions {
+    /// `self`, with whatever `overrides` sets taking its place.
+    pub fn merge(&self, overrides: &ProbeOptions) -> ProbeOptions {
+        ProbeOptions {
+            probesize: overrides.probesize.or(self.probesize),
+            analyzeduration: overrides.analyzeduration.or(self.analyzeduration),
+            fflags: overrides.fflags.clone().or_else(|| self.fflags.clone()),
+        }
+    }
+
+    pub fn dictionary(&self) -> Dictionary<'static> {
+        let mut opts = Dictionary::new();
+        if let Some(probesize) = self.probesize {
+            opts.set("probesize", &probesize.to_string());
+        }
+        if let Some(duration) = self.analyzeduration {
+            opts.set("analyzeduration", &duration.to_string());
+        }
+        if let Some(fflags) = &self.fflags {
+            opts.set("fflags", fflags);
+        }
+        opts
+    }
+}
+
+static OPTIONS: RwLock<Option<ProbeOptions>> = RwLock::new(None);
+
+/// Sets what [`open`] uses from now on.
+pub fn set(options: ProbeOptions) {
+    *OPTIONS.write().unwrap() = Some(options);
+}
+
+/// The options from [`set`].
+pub fn options() -> ProbeOptions {
+    OPTIONS.read().unwrap().clone().unwrap_or_default()
+}
+
+/// Opens a source file with the options from [`set`].
+pub fn open<P: AsRef<Path> + ?Sized>(path: &P) -> Result<Input, ffmpeg::Error> {
+    ffmpeg::format::input_with_dictionary(path, options().dictionary())
+}
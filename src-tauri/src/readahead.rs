@@ -0,0 +1,49 @@
+use std::sync::Mutex;
+
+/// Range responses never get smaller than this, in bytes.
+const MIN_CHUNK: u64 = 256 * 1024;
+/// Nor bigger than this, which is several seconds of most videos.
+const MAX_CHUNK: u64 = 8 * 1024 * 1024;
+/// What the first request of a file gets.
+const START_CHUNK: u64 = 1000 * 1024;
+
+/// Sizes the answers to the webview's range requests by how it reads.
+/// Requests that pick up where the last one ended are linear playback and
+/// get twice as much each time, so the player is rarely left waiting. A
+/// request anywhere else is a seek and halves the size, since most of a big
+/// chunk is thrown away when the user is scrubbing.
+pub struct ReadAhead {
+    /// Byte the next linear request starts at, and the current chunk size.
+    state: Mutex<(u64, u64)>,
+}
+
+impl Default for ReadAhead {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new((0, START_CHUNK)),
+        }
+    }
+}
+
+impl ReadAhead {
+    /// How many bytes to send for a range request starting at `start`.
+    pub fn chunk_for(&self, start: u64) -> u64 {
+        let (next, chunk) = &mut *self.state.lock().unwrap();
+        *chunk = if start == *next {
+            (*chunk * 2).min(MAX_CHUNK)
+        } else {
+            (*chunk / 2).max(MIN_CHUNK)
+        };
+        *chunk
+    }
+
+    /// Records that everything up to and including `end` was sent.
+    pub fn served(&self, end: u64) {
+        self.state.lock().unwrap().0 = end + 1;
+    }
+
+    /// Starts over for a new file.
+    pub fn reset(&self) {
+        *self.state.lock().unwrap() = (0, START_CHUNK);
+    }
+}
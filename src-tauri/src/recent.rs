@@ -0,0 +1,45 @@
+use crate::json_store::JsonStore;
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tauri::AppHandle;
+
+const MAX_RECENT: usize = 20;
+
+/// Recently opened files, newest first, kept in `recent.json` in the app
+/// data dir.
+pub struct Recent {
+    store: JsonStore,
+    items: Mutex<Vec<PathBuf>>,
+}
+
+impl Recent {
+    pub fn load(app: &AppHandle) -> Self {
+        let store = JsonStore::new(app, "recent.json");
+        let items = store.load();
+
+        Self {
+            store,
+            items: Mutex::new(items),
+        }
+    }
+
+    /// The files that still exist, newest first.
+    pub fn items(&self) -> Vec<PathBuf> {
+        let mut items = self.items.lock().unwrap();
+        items.retain(|p| p.is_file());
+        items.clone()
+    }
+
+    /// Moves `source` to the front of the list.
+    pub fn push(&self, source: &Path) {
+        let source = std::path::absolute(source).unwrap_or_else(|_| source.to_path_buf());
+
+        let mut items = self.items.lock().unwrap();
+        items.retain(|p| *p != source);
+        items.insert(0, source);
+        items.truncate(MAX_RECENT);
+        self.store.save(&*items);
+    }
+}
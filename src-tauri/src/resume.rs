@@ -0,0 +1,41 @@
+use crate::json_store::{path_key, JsonStore};
+use std::{collections::HashMap, path::Path, sync::Mutex};
+use tauri::AppHandle;
+
+/// Positions closer to the start than this aren't worth offering to resume.
+const MIN_RESUME_TIME: f64 = 10.0;
+
+/// Where playback stopped in each file, kept in `positions.json` in the app
+/// data dir so it survives restarts.
+pub struct Positions {
+    store: JsonStore,
+    times: Mutex<HashMap<String, f64>>,
+}
+
+impl Positions {
+    pub fn load(app: &AppHandle) -> Self {
+        let store = JsonStore::new(app, "positions.json");
+        let times = store.load();
+
+        Self {
+            store,
+            times: Mutex::new(times),
+        }
+    }
+
+    pub fn get(&self, source: &Path) -> Option<f64> {
+        self.times.lock().unwrap().get(&path_key(source)).copied()
+    }
+
+    /// Remembers `time` for `source`, or forgets it when `None`.
+    pub fn set(&self, source: &Path, time: Option<f64>) {
+        let mut times = self.times.lock().unwrap();
+        let changed = match time.filter(|&t| t >= MIN_RESUME_TIME) {
+            Some(t) => times.insert(path_key(source), t) != Some(t),
+            None => times.remove(&path_key(source)).is_some(),
+        };
+        if changed {
+            self.store.save(&*times);
+        }
+    }
+}
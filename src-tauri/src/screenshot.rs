@@ -0,0 +1,47 @@
+use crate::{annotate::Shape, ffhelp::FFHelp};
+use ffmpeg_next::{self as ffmpeg, codec, format::Pixel, frame::Video, Packet};
+use std::{error::Error, path::Path};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Decodes the frame shown at `time`, draws `shapes` on it and encodes it as
+/// PNG.
+pub fn capture<P: AsRef<Path>>(path: &P, time: f64, shapes: &[Shape]) -> BasicResult<Vec<u8>> {
+    let mut help = FFHelp::open(path)?;
+    let index = help
+        .frame_index(time)
+        .min(help.total_frames().saturating_sub(1));
+    let mut rgba = help.get_frame(index)?;
+    let (w, h) = help.get_width_height();
+    crate::annotate::draw(&mut rgba, w, h, shapes);
+
+    encode_png(&rgba, w, h)
+}
+
+/// `rgba` is tightly packed, `w * 4` bytes per row.
+pub fn encode_png(rgba: &[u8], w: u32, h: u32) -> BasicResult<Vec<u8>> {
+    let codec = ffmpeg::encoder::find(codec::Id::PNG).ok_or("PNG encoder not found")?;
+    let mut encoder = codec::context::Context::new_with_codec(codec)
+        .encoder()
+        .video()?;
+    encoder.set_width(w);
+    encoder.set_height(h);
+    encoder.set_format(Pixel::RGBA);
+    encoder.set_time_base((1, 1));
+    let mut encoder = encoder.open_as(codec)?;
+
+    let mut frame = Video::new(Pixel::RGBA, w, h);
+    let row = w as usize * 4;
+    // ffmpeg pads its rows, so copy line by line
+    let stride = frame.stride(0);
+    for (y, line) in rgba.chunks_exact(row).take(h as usize).enumerate() {
+        frame.data_mut(0)[y * stride..y * stride + row].copy_from_slice(line);
+    }
+
+    encoder.send_frame(&frame)?;
+    encoder.send_eof()?;
+    let mut packet = Packet::empty();
+    encoder.receive_packet(&mut packet)?;
+
+    Ok(packet.data().ok_or("PNG encoder returned nothing")?.to_vec())
+}
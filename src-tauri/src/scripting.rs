@@ -0,0 +1,129 @@
+use rhai::{CallFnOptions, Dynamic, Engine, Map, Scope, AST};
+use std::{
+    path::Path,
+    sync::{mpsc, Mutex},
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// A script runs at most this many operations per load or handler call, so
+/// an endless loop stops with an error instead of hanging the player.
+const MAX_OPERATIONS: u64 = 1_000_000;
+/// Handler calls waiting for the worker. A script that can't keep up misses
+/// calls instead of falling further and further behind.
+const QUEUE_LENGTH: usize = 64;
+
+type Call = Box<dyn FnOnce(&Scripts) + Send>;
+
+/// Rhai scripts from the `scripts` config option. A script reacts to the
+/// player by defining any of
+///
+/// - `on_open(path)` when a file is opened,
+/// - `on_time(time)` a few times a second during playback,
+/// - `on_end()` when a file plays to the end,
+///
+/// and drives it with `seek(seconds)`, `screenshot()` and
+/// `osd(text, seconds)`, which the frontend carries out. Handlers can keep
+/// data between calls in `this`, an object map each script has to itself.
+pub struct Scripts {
+    engine: Engine,
+    scripts: Vec<(AST, Mutex<Dynamic>)>,
+    /// Feeds the one thread handlers run on, see [`call`].
+    queue: mpsc::SyncSender<Call>,
+}
+
+impl Scripts {
+    pub fn load<P: AsRef<Path>>(app: &AppHandle, paths: &[P]) -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let h = app.clone();
+        engine.register_fn("seek", move |time: f64| {
+            h.emit("script-seek", time).unwrap();
+        });
+        let h = app.clone();
+        engine.register_fn("screenshot", move || {
+            h.emit("script-screenshot", ()).unwrap();
+        });
+        let h = app.clone();
+        engine.register_fn("osd", move |text: &str, seconds: f64| {
+            h.emit("osd-text", (text, seconds)).unwrap();
+        });
+        engine.on_print(|text| log::info!(target: "script", "{text}"));
+
+        let scripts = paths
+            .iter()
+            .filter_map(|path| {
+                let path = path.as_ref();
+                let ast = engine
+                    .compile_file(path.to_path_buf())
+                    .map_err(|e| log::warn!("skipping script {}: {e}", path.display()))
+                    .ok()?;
+                // top-level statements run once, when the script is loaded
+                if let Err(e) = engine.run_ast(&ast) {
+                    log::warn!("script {} failed: {e}", path.display());
+                }
+                Some((ast, Mutex::new(Map::new().into())))
+            })
+            .collect();
+
+        let (queue, calls) = mpsc::sync_channel::<Call>(QUEUE_LENGTH);
+        let h = app.clone();
+        std::thread::spawn(move || {
+            for call in calls {
+                call(&h.state::<Scripts>());
+            }
+        });
+
+        Self {
+            engine,
+            scripts,
+            queue,
+        }
+    }
+
+    pub fn defines(&self, name: &str) -> bool {
+        self.scripts
+            .iter()
+            .any(|(ast, _)| ast.iter_functions().any(|f| f.name == name))
+    }
+
+    /// Calls `name` in every script that defines it.
+    pub fn call(&self, name: &str, args: impl rhai::FuncArgs + Clone) {
+        for (ast, this) in &self.scripts {
+            if !ast.iter_functions().any(|f| f.name == name) {
+                continue;
+            }
+            let mut this = this.lock().unwrap();
+            let options = CallFnOptions::new()
+                .eval_ast(false)
+                .bind_this_ptr(&mut *this);
+            let result = self.engine.call_fn_with_options::<Dynamic>(
+                options,
+                &mut Scope::new(),
+                ast,
+                name,
+                args.clone(),
+            );
+            if let Err(e) = result {
+                log::warn!("script handler {name} failed: {e}");
+            }
+        }
+    }
+}
+
+/// Calls `name` in the scripts on their worker thread, so a slow script
+/// can't hold up the UI.
+pub fn call(
+    app: &AppHandle,
+    name: &'static str,
+    args: impl rhai::FuncArgs + Clone + Send + 'static,
+) {
+    let scripts = app.state::<Scripts>();
+    if !scripts.defines(name) {
+        return;
+    }
+    let call: Call = Box::new(move |scripts| scripts.call(name, args));
+    if let Err(mpsc::TrySendError::Full(_)) = scripts.queue.try_send(call) {
+        log::warn!("scripts are behind, skipping {name}");
+    }
+}
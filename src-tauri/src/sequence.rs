@@ -0,0 +1,155 @@
+use ffmpeg_next::{
+    self as ffmpeg, codec, format::Pixel, frame::Video, media, software::scaling, Packet,
+};
+use serde::Deserialize;
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// File type of exported frames.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Png,
+    Jpeg,
+}
+
+impl Format {
+    fn codec(self) -> codec::Id {
+        match self {
+            Format::Png => codec::Id::PNG,
+            Format::Jpeg => codec::Id::MJPEG,
+        }
+    }
+
+    fn pixel(self) -> Pixel {
+        match self {
+            Format::Png => Pixel::RGB24,
+            // full range, the way stills are viewed
+            Format::Jpeg => Pixel::YUVJ420P,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Png => "png",
+            Format::Jpeg => "jpg",
+        }
+    }
+}
+
+/// Writes every frame from `start` to `end` seconds into `folder` as
+/// `{stem}_00001.png` and so on, and returns how many were written.
+/// `on_progress` gets the fraction done after every frame.
+pub fn export<P: AsRef<Path> + ?Sized>(
+    path: &P,
+    start: f64,
+    end: f64,
+    format: Format,
+    folder: &Path,
+    stem: &str,
+    mut on_progress: impl FnMut(f64),
+) -> BasicResult<usize> {
+    let mut ictx = ffmpeg::format::input(path)?;
+    let stream = ictx
+        .streams()
+        .best(media::Type::Video)
+        .ok_or("no video stream")?;
+    let index = stream.index();
+    let time_base = f64::from(stream.time_base());
+
+    let context_decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+    let mut decoder = context_decoder.decoder().video()?;
+    let (w, h) = (decoder.width(), decoder.height());
+    let mut scaler = scaling::Context::get(
+        decoder.format(),
+        w,
+        h,
+        format.pixel(),
+        w,
+        h,
+        scaling::Flags::BICUBIC,
+    )?;
+
+    let image_codec = ffmpeg::encoder::find(format.codec()).ok_or("image encoder not found")?;
+    let mut encoder = codec::context::Context::new_with_codec(image_codec)
+        .encoder()
+        .video()?;
+    encoder.set_width(w);
+    encoder.set_height(h);
+    encoder.set_format(format.pixel());
+    encoder.set_time_base((1, 1));
+    // near lossless JPEG, the frames are meant for editing
+    encoder.set_qmin(2);
+    encoder.set_qmax(3);
+    let mut encoder = encoder.open_as(image_codec)?;
+
+    std::fs::create_dir_all(folder)?;
+
+    // keyframe before `start`; the frames up to it are decoded and dropped
+    let target = (start * ffmpeg::ffi::AV_TIME_BASE as f64) as i64;
+    ictx.seek(target, ..target)?;
+
+    // returns whether `end` has been passed
+    let mut written = 0;
+    let mut save = |frame: &Video| -> BasicResult<bool> {
+        let time = frame.timestamp().unwrap_or(0) as f64 * time_base;
+        if time < start {
+            return Ok(false);
+        }
+        if time > end {
+            return Ok(true);
+        }
+
+        let mut converted = Video::empty();
+        scaler.run(frame, &mut converted)?;
+        converted.set_pts(Some(written as i64));
+        encoder.send_frame(&converted)?;
+        let mut packet = Packet::empty();
+        encoder.receive_packet(&mut packet)?;
+
+        written += 1;
+        let name = format!("{stem}_{written:05}.{}", format.extension());
+        let data = packet.data().ok_or("encoder returned nothing")?;
+        std::fs::write(folder.join(name), data)?;
+        if end > start {
+            on_progress(((time - start) / (end - start)).min(1.0));
+        }
+        Ok(false)
+    };
+
+    let mut frame = Video::empty();
+    'decode: {
+        for (stream, packet) in ictx.packets() {
+            if stream.index() != index {
+                continue;
+            }
+            if decoder.send_packet(&packet).is_err() {
+                continue;
+            }
+            while decoder.receive_frame(&mut frame).is_ok() {
+                if save(&frame)? {
+                    break 'decode;
+                }
+            }
+        }
+        decoder.send_eof()?;
+        while decoder.receive_frame(&mut frame).is_ok() {
+            if save(&frame)? {
+                break 'decode;
+            }
+        }
+    }
+
+    Ok(written)
+}
+
+/// A new folder in `parent` for the frames of `stem` from `start`, named by
+/// `template`, see [`crate::naming::render`].
+pub fn folder_for(parent: &Path, template: &str, stem: &str, start: f64) -> Option<PathBuf> {
+    crate::naming::free_path(parent, template, stem, start)
+}
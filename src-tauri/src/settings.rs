@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::Mutex};
+use tauri::{AppHandle, Manager};
+
+/// Player state carried over to the next start. Unlike
+/// [`crate::config::Config`], which the user edits, the player writes this
+/// itself whenever something changes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    /// From 0 to 1.
+    pub volume: f64,
+    pub muted: bool,
+    /// `playlist`, `file` or `stop`, see `LoopMode` in the frontend.
+    pub loop_mode: String,
+    /// Inner size of the window when it was last closed, in logical pixels.
+    pub window: Option<WindowSize>,
+    /// Power saving as last toggled by hand; the config decides when unset.
+    pub power_saver: Option<bool>,
+    /// What was open when the player was last closed, offered for restoring
+    /// at the next start.
+    pub session: Option<Session>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Session {
+    pub items: Vec<PathBuf>,
+    pub current: usize,
+    /// Index of the audio track that was playing, when not the first.
+    pub audio_track: Option<usize>,
+    pub subtitles: Option<crate::subtitles::Selection>,
+    pub second_subtitles: Option<crate::subtitles::Selection>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct WindowSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            muted: false,
+            loop_mode: "playlist".into(),
+            window: None,
+            power_saver: None,
+            session: None,
+        }
+    }
+}
+
+/// [`Settings`] kept in `settings.toml` next to the config.
+pub struct Store {
+    file: Option<PathBuf>,
+    settings: Mutex<Settings>,
+}
+
+impl Store {
+    pub fn load(app: &AppHandle) -> Self {
+        let file = app
+            .path()
+            .app_config_dir()
+            .ok()
+            .map(|dir| dir.join("settings.toml"));
+        let settings = file
+            .as_ref()
+            .and_then(|f| std::fs::read_to_string(f).ok())
+            .map(|text| {
+                toml::from_str(&text).unwrap_or_else(|e| {
+                    log::warn!("ignoring invalid settings: {e}");
+                    Settings::default()
+                })
+            })
+            .unwrap_or_default();
+
+        Self {
+            file,
+            settings: Mutex::new(settings),
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// Changes the settings with `f` and saves them.
+    pub fn update(&self, f: impl FnOnce(&mut Settings)) {
+        let mut settings = self.settings.lock().unwrap();
+        f(&mut settings);
+
+        let Some(file) = &self.file else {
+            return;
+        };
+        let result = std::fs::create_dir_all(file.parent().unwrap())
+            .and_then(|_| crate::write_atomically(file, toml::to_string(&*settings).unwrap()));
+        if let Err(e) = result {
+            log::warn!("could not save {}: {e}", file.display());
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use crate::ffhelp::FFHelp;
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Space between tiles and around the edge, in pixels.
+const GAP: u32 = 4;
+const BACKGROUND: [u8; 4] = [16, 16, 16, 255];
+/// Timestamp glyphs are drawn this many pixels per font dot.
+const FONT_SCALE: u32 = 2;
+
+pub struct SheetOptions {
+    pub cols: u32,
+    pub rows: u32,
+    /// Width of each tile; the height follows the video.
+    pub tile_width: u32,
+}
+
+impl Default for SheetOptions {
+    fn default() -> Self {
+        Self {
+            cols: 4,
+            rows: 4,
+            tile_width: 320,
+        }
+    }
+}
+
+/// Evenly spaced frames of `path` in a grid, each labelled with its time,
+/// as PNG.
+pub fn render<P: AsRef<Path>>(path: &P, options: &SheetOptions) -> BasicResult<Vec<u8>> {
+    let mut help = FFHelp::open_scaled(path, options.tile_width)?;
+    let (tw, th) = help.get_width_height();
+    let count = options.cols * options.rows;
+
+    let width = options.cols * (tw + GAP) + GAP;
+    let height = options.rows * (th + GAP) + GAP;
+    let mut canvas = Canvas::new(width, height);
+
+    let last = help.total_frames().saturating_sub(1);
+    for i in 0..count {
+        // the middle of each of `count` equal slices, so the first tile isn't a black intro
+        let time = help.duration() * (i as f64 + 0.5) / count as f64;
+        let frame = help.get_frame(help.frame_index(time).min(last))?;
+
+        let x = GAP + i % options.cols * (tw + GAP);
+        let y = GAP + i / options.cols * (th + GAP);
+        canvas.blit(&frame, tw, th, x, y);
+        canvas.label(
+            &crate::chapters::youtube_timestamp(time),
+            x + GAP,
+            y + th - GAP,
+        );
+    }
+
+    crate::screenshot::encode_png(&canvas.rgba, width, height)
+}
+
+/// Renders a grid of evenly spaced frames for each video, as
+/// `<name>_sheet.png` next to it or in the output folder.
+#[derive(clap::Args)]
+pub struct CliArgs {
+    /// Videos, or folders of them.
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+    /// Tiles per row.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    #[arg(default_value_t = SheetOptions::default().cols)]
+    cols: u32,
+    /// Rows of tiles.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    #[arg(default_value_t = SheetOptions::default().rows)]
+    rows: u32,
+    /// Width of each tile in pixels.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    #[arg(default_value_t = SheetOptions::default().tile_width)]
+    width: u32,
+    /// Folder to write the sheets to; next to each video when not given.
+    #[arg(short, long)]
+    out: Option<PathBuf>,
+}
+
+pub fn run_cli(args: CliArgs) -> BasicResult<()> {
+    let options = SheetOptions {
+        cols: args.cols,
+        rows: args.rows,
+        tile_width: args.width,
+    };
+    let out_dir = args.out;
+
+    let files = crate::cli::video_files(args.inputs)?;
+
+    let mut failed = 0;
+    for file in &files {
+        let name = format!(
+            "{}_sheet.png",
+            file.file_stem().unwrap_or_default().to_string_lossy()
+        );
+        let out = match &out_dir {
+            Some(dir) => dir.join(name),
+            None => file.with_file_name(name),
+        };
+        match render(file, &options).and_then(|png| Ok(std::fs::write(&out, png)?)) {
+            Ok(()) => println!("{}", out.display()),
+            Err(e) => {
+                eprintln!("{}: {e}", file.display());
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{failed} of {} files failed", files.len()).into());
+    }
+    Ok(())
+}
+
+struct Canvas {
+    rgba: Vec<u8>,
+    width: u32,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            rgba: BACKGROUND.repeat((width * height) as usize),
+            width,
+        }
+    }
+
+    fn blit(&mut self, rgba: &[u8], w: u32, h: u32, x: u32, y: u32) {
+        let row = w as usize * 4;
+        for (dy, line) in rgba.chunks_exact(row).take(h as usize).enumerate() {
+            let start = ((y as usize + dy) * self.width as usize + x as usize) * 4;
+            self.rgba[start..start + row].copy_from_slice(line);
+        }
+    }
+
+    fn fill(&mut self, x: u32, y: u32, w: u32, h: u32, color: [u8; 4]) {
+        for yy in y..y + h {
+            for xx in x..x + w {
+                let i = (yy as usize * self.width as usize + xx as usize) * 4;
+                self.rgba[i..i + 4].copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Writes `text` (digits and colons) with its bottom-left corner at
+    /// `x`/`bottom`, on a dark box so it reads on any picture.
+    fn label(&mut self, text: &str, x: u32, bottom: u32) {
+        let advance = (GLYPH_WIDTH + 1) * FONT_SCALE;
+        let (w, h) = (
+            text.len() as u32 * advance + FONT_SCALE,
+            (GLYPH_HEIGHT + 2) * FONT_SCALE,
+        );
+        let y = bottom.saturating_sub(h);
+        self.fill(x, y, w, h, [0, 0, 0, 255]);
+
+        for (i, c) in text.chars().enumerate() {
+            let Some(glyph) = glyph(c) else {
+                continue;
+            };
+            let gx = x + FONT_SCALE + i as u32 * advance;
+            for (row, bits) in glyph.iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                        let px = gx + col * FONT_SCALE;
+                        let py = y + FONT_SCALE + row as u32 * FONT_SCALE;
+                        self.fill(px, py, FONT_SCALE, FONT_SCALE, [255, 255, 255, 255]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+
+/// A 3x5 dot font, one byte per row with the leftmost dot in the high bit.
+fn glyph(c: char) -> Option<[u8; 5]> {
+    Some(match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        _ => return None,
+    })
+}
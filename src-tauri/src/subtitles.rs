@@ -0,0 +1,532 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ffmpeg_next::{self as ffmpeg, codec, media, subtitle::Rect};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Extensions we know how to parse, in the order sidecars are searched for.
+pub const SUBTITLE_EXTENSIONS: [&str; 3] = ["srt", "ass", "ssa"];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    /// Set instead of `text` for bitmap formats like PGS.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<Image>,
+}
+
+/// One picture of a bitmap subtitle. Position and size are fractions of the
+/// video frame, so the page can scale it along with the video.
+#[derive(Clone, Debug, Serialize)]
+pub struct Image {
+    /// PNG as a `data:` URL.
+    pub src: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    /// `[1:23] text` lines.
+    Text,
+    /// A heading with the file name, then a paragraph per cue.
+    Markdown,
+}
+
+impl TranscriptFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            TranscriptFormat::Text => "transcript.txt",
+            TranscriptFormat::Markdown => "transcript.md",
+        }
+    }
+}
+
+/// Text-based codecs we can turn into cues.
+const TEXT_CODECS: [codec::Id; 7] = [
+    codec::Id::SUBRIP,
+    codec::Id::SRT,
+    codec::Id::ASS,
+    codec::Id::SSA,
+    codec::Id::MOV_TEXT,
+    codec::Id::WEBVTT,
+    codec::Id::TEXT,
+];
+
+/// Bitmap codecs, shown as pictures instead of text.
+const BITMAP_CODECS: [codec::Id; 3] = [
+    codec::Id::HDMV_PGS_SUBTITLE,
+    codec::Id::DVD_SUBTITLE,
+    codec::Id::DVB_SUBTITLE,
+];
+
+/// PGS leaves the end of a picture open until the next display set clears
+/// it; one that is never cleared goes away after this many seconds.
+const MAX_BITMAP_DURATION: f64 = 10.0;
+
+/// Where the currently displayed subtitles come from.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum Selection {
+    File(PathBuf),
+    /// Source file and stream index of a subtitle stream inside it.
+    Embedded(PathBuf, usize),
+}
+
+impl Selection {
+    pub fn load(&self) -> BasicResult<Vec<Cue>> {
+        match self {
+            Selection::File(path) => load(path),
+            Selection::Embedded(path, index) => load_embedded(path, *index),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Track {
+    pub index: usize,
+    pub codec: String,
+    pub language: Option<String>,
+    pub title: Option<String>,
+}
+
+impl Track {
+    pub fn label(&self) -> String {
+        let mut label = format!("#{}", self.index);
+        for part in [&self.language, &self.title].into_iter().flatten() {
+            label.push(' ');
+            label.push_str(part);
+        }
+        format!("{label} ({})", self.codec)
+    }
+}
+
+pub fn is_subtitle_file<P: AsRef<Path>>(path: &P) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SUBTITLE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// Looks for `<video basename>.srt` (or `.ass`/`.ssa`) next to the video.
+pub fn find_sidecar<P: AsRef<Path>>(video: &P) -> Option<PathBuf> {
+    SUBTITLE_EXTENSIONS
+        .iter()
+        .map(|ext| video.as_ref().with_extension(ext))
+        .find(|p| p.is_file())
+}
+
+pub fn load<P: AsRef<Path>>(path: &P) -> BasicResult<Vec<Cue>> {
+    let bytes = std::fs::read(path)?;
+    let text = String::from_utf8_lossy(&bytes);
+    // skip the BOM some editors like to write
+    let text = text.trim_start_matches('\u{feff}');
+
+    let ext = path
+        .as_ref()
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    let mut cues = match ext.as_str() {
+        "srt" => parse_srt(text),
+        "ass" | "ssa" => parse_ass(text),
+        _ => return Err(format!("unsupported subtitle format: {ext}").into()),
+    };
+    cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    Ok(cues)
+}
+
+/// `cues` as running text, each line stamped with when it starts. Lines
+/// repeating the one before are left out; roll-up captions show every line
+/// several times as it scrolls.
+pub fn transcript(cues: &[Cue], format: TranscriptFormat, title: &str) -> String {
+    let mut out = String::new();
+    if let TranscriptFormat::Markdown = format {
+        out.push_str(&format!("# {title}\n\n"));
+    }
+
+    let mut previous = String::new();
+    for cue in cues {
+        let text = cue.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() || text == previous {
+            continue;
+        }
+        let time = crate::chapters::youtube_timestamp(cue.start);
+        match format {
+            TranscriptFormat::Text => out.push_str(&format!("[{time}] {text}\n")),
+            TranscriptFormat::Markdown => out.push_str(&format!("**{time}** {text}\n\n")),
+        }
+        previous = text;
+    }
+    out
+}
+
+/// Lists the text and bitmap subtitle streams inside a media file.
+pub fn embedded_tracks<P: AsRef<Path>>(path: &P) -> BasicResult<Vec<Track>> {
+    let ictx = crate::probe::open(path)?;
+
+    Ok(ictx
+        .streams()
+        .filter(|s| s.parameters().medium() == media::Type::Subtitle)
+        .filter(|s| {
+            let id = s.parameters().id();
+            TEXT_CODECS.contains(&id) || BITMAP_CODECS.contains(&id)
+        })
+        .map(|s| Track {
+            index: s.index(),
+            codec: s.parameters().id().name().to_string(),
+            language: s.metadata().get("language").map(String::from),
+            title: s.metadata().get("title").map(String::from),
+        })
+        .collect())
+}
+
+/// Decodes a whole embedded subtitle stream into cues.
+pub fn load_embedded<P: AsRef<Path>>(path: &P, index: usize) -> BasicResult<Vec<Cue>> {
+    let mut ictx = crate::probe::open(path)?;
+
+    let stream = ictx.stream(index).ok_or("no such subtitle stream")?;
+    let time_base = f64::from(stream.time_base());
+    let frame = frame_size(&ictx, index);
+    let context_decoder = codec::context::Context::from_parameters(stream.parameters())?;
+    let mut decoder = context_decoder.decoder().subtitle()?;
+
+    let mut cues = Vec::new();
+    // bitmap cues still waiting for the display set that clears them
+    let mut open = Vec::new();
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != index {
+            continue;
+        }
+
+        let mut sub = ffmpeg::Subtitle::new();
+        // a single broken packet shouldn't cost us the whole track
+        if !decoder.decode(&packet, &mut sub).unwrap_or(false) {
+            continue;
+        }
+
+        let pts = packet.pts().unwrap_or(0) as f64 * time_base;
+        let start = pts + sub.start() as f64 / 1000.0;
+        let end = if packet.duration() > 0 {
+            Some(pts + packet.duration() as f64 * time_base)
+        } else if sub.end() > sub.start() && sub.end() != u32::MAX {
+            Some(pts + sub.end() as f64 / 1000.0)
+        } else {
+            None
+        };
+
+        for i in open.drain(..) {
+            let cue = &mut cues[i];
+            cue.end = start.min(cue.start + MAX_BITMAP_DURATION);
+        }
+
+        let mut text = Vec::new();
+        for rect in sub.rects() {
+            match rect {
+                Rect::Text(t) => text.push(strip_tags(t.get())),
+                // decoded ASS events are `ReadOrder,Layer,Style,Name,MarginL,MarginR,MarginV,Effect,Text`
+                Rect::Ass(a) => text.extend(a.get().splitn(9, ',').nth(8).map(dialogue_text)),
+                Rect::Bitmap(b) => {
+                    if let Some(image) = bitmap_image(&b, frame) {
+                        if end.is_none() {
+                            open.push(cues.len());
+                        }
+                        cues.push(Cue {
+                            start,
+                            end: end.unwrap_or(start + MAX_BITMAP_DURATION),
+                            text: String::new(),
+                            image: Some(image),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let text = text.join("\n");
+        if !text.is_empty() {
+            cues.push(Cue {
+                start,
+                end: end.unwrap_or(start),
+                text,
+                image: None,
+            });
+        }
+    }
+    cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    Ok(cues)
+}
+
+/// The size bitmap subtitle positions refer to: the subtitle stream's own,
+/// or the video's when the container leaves it out.
+fn frame_size(ictx: &ffmpeg::format::context::Input, index: usize) -> (u32, u32) {
+    let size = |s: ffmpeg::Stream| unsafe {
+        let par = &*s.parameters().as_ptr();
+        (par.width.max(0) as u32, par.height.max(0) as u32)
+    };
+    ictx.stream(index)
+        .map(size)
+        .filter(|&(w, h)| w > 0 && h > 0)
+        .or_else(|| ictx.streams().best(media::Type::Video).map(size))
+        .filter(|&(w, h)| w > 0 && h > 0)
+        // the PGS default
+        .unwrap_or((1920, 1080))
+}
+
+/// Turns a palettized subtitle bitmap into a PNG placed on the frame.
+fn bitmap_image(
+    bitmap: &ffmpeg::subtitle::Bitmap,
+    (frame_w, frame_h): (u32, u32),
+) -> Option<Image> {
+    let (w, h) = (bitmap.width(), bitmap.height());
+    if w == 0 || h == 0 {
+        return None;
+    }
+
+    // SAFETY: a decoded bitmap rect holds `h` rows of `w` palette indices in
+    // `data[0]` and `nb_colors` native-endian ARGB entries in `data[1]`
+    let rgba = unsafe {
+        let rect = &*bitmap.as_ptr();
+        let palette =
+            std::slice::from_raw_parts(rect.data[1] as *const u32, rect.nb_colors as usize);
+        let mut rgba = Vec::with_capacity(w as usize * h as usize * 4);
+        for y in 0..h as usize {
+            let row = rect.data[0].add(y * rect.linesize[0] as usize);
+            for &i in std::slice::from_raw_parts(row, w as usize) {
+                let argb = palette.get(i as usize).copied().unwrap_or(0);
+                rgba.extend_from_slice(&[
+                    (argb >> 16) as u8,
+                    (argb >> 8) as u8,
+                    argb as u8,
+                    (argb >> 24) as u8,
+                ]);
+            }
+        }
+        rgba
+    };
+
+    let png = match crate::screenshot::encode_png(&rgba, w, h) {
+        Ok(png) => png,
+        Err(e) => {
+            log::warn!("could not encode subtitle bitmap: {e}");
+            return None;
+        }
+    };
+    Some(Image {
+        src: format!("data:image/png;base64,{}", BASE64.encode(png)),
+        x: bitmap.x() as f64 / frame_w as f64,
+        y: bitmap.y() as f64 / frame_h as f64,
+        width: w as f64 / frame_w as f64,
+        height: h as f64 / frame_h as f64,
+    })
+}
+
+pub fn parse_srt(text: &str) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut lines = text.lines().map(|l| l.trim_end_matches('\r'));
+
+    while let Some(line) = lines.next() {
+        // the counter line is optional in the wild, so just look for the timing line
+        let Some((start, end)) = line.split_once("-->") else {
+            continue;
+        };
+        let (Some(start), Some(end)) = (parse_timestamp(start), parse_timestamp(end)) else {
+            continue;
+        };
+
+        let mut body = Vec::new();
+        for line in lines.by_ref() {
+            if line.trim().is_empty() {
+                break;
+            }
+            body.push(strip_tags(line));
+        }
+
+        cues.push(Cue {
+            start,
+            end,
+            text: body.join("\n"),
+            image: None,
+        });
+    }
+
+    cues
+}
+
+pub fn parse_ass(text: &str) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut in_events = false;
+    // default v4+ layout, replaced as soon as the section declares its own
+    let mut format = vec![
+        "layer", "start", "end", "style", "name", "marginl", "marginr", "marginv", "effect",
+        "text",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect::<Vec<_>>();
+
+    for line in text.lines().map(|l| l.trim()) {
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+
+        if let Some(fields) = line.strip_prefix("Format:") {
+            format = fields
+                .split(',')
+                .map(|f| f.trim().to_ascii_lowercase())
+                .collect();
+        } else if let Some(fields) = line.strip_prefix("Dialogue:") {
+            // the text column is last and may itself contain commas
+            let fields = fields.trim_start().splitn(format.len(), ',').collect::<Vec<_>>();
+            let field = |name: &str| {
+                format
+                    .iter()
+                    .position(|f| f == name)
+                    .and_then(|i| fields.get(i))
+                    .copied()
+            };
+
+            let (Some(start), Some(end), Some(body)) = (
+                field("start").and_then(parse_timestamp),
+                field("end").and_then(parse_timestamp),
+                field("text"),
+            ) else {
+                continue;
+            };
+
+            cues.push(Cue {
+                start,
+                end,
+                text: dialogue_text(body),
+                image: None,
+            });
+        }
+    }
+
+    cues
+}
+
+/// Parses `HH:MM:SS,mmm` (SRT) and `H:MM:SS.cc` (ASS) into seconds.
+fn parse_timestamp(ts: &str) -> Option<f64> {
+    // SRT timing lines can carry position hints after the end time
+    let ts = ts.split_whitespace().next()?.replace(',', ".");
+    let mut parts = ts.rsplitn(3, ':');
+
+    let secs = parts.next()?.parse::<f64>().ok()?;
+    let mins = parts.next()?.parse::<f64>().ok()?;
+    let hours = parts.next().map_or(Some(0.0), |h| h.parse::<f64>().ok())?;
+
+    Some(hours * 3600.0 + mins * 60.0 + secs)
+}
+
+/// Removes the html-ish `<i>`/`<font ...>` tags SRT files carry.
+fn strip_tags(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Turns the text column of an ASS event into plain text.
+fn dialogue_text(body: &str) -> String {
+    strip_ass_overrides(body)
+        .replace("\\N", "\n")
+        .replace("\\n", "\n")
+        .replace("\\h", " ")
+}
+
+/// Removes `{\...}` override blocks; styling is not rendered.
+fn strip_ass_overrides(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0;
+    for c in text.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_ass, parse_srt, parse_timestamp};
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(parse_timestamp("00:01:02,500"), Some(62.5));
+        assert_eq!(parse_timestamp("1:00:01.25"), Some(3601.25));
+        assert_eq!(parse_timestamp("01:02.5"), Some(62.5));
+        // position hints after the end time
+        assert_eq!(parse_timestamp(" 00:00:05,000 X1:10 X2:20"), Some(5.0));
+        assert_eq!(parse_timestamp("5"), None);
+        assert_eq!(parse_timestamp("a:b:c"), None);
+    }
+
+    #[test]
+    fn parses_srt() {
+        let text = "1\r\n00:00:01,000 --> 00:00:02,500\r\n<i>Hello</i>\r\nworld\r\n\r\n\
+                    00:00:03,000 --> 00:00:04,000\nBye\n";
+        let cues = parse_srt(text);
+        assert_eq!(cues.len(), 2);
+        assert_eq!((cues[0].start, cues[0].end), (1.0, 2.5));
+        assert_eq!(cues[0].text, "Hello\nworld");
+        // no counter line before the second one
+        assert_eq!((cues[1].start, cues[1].end), (3.0, 4.0));
+        assert_eq!(cues[1].text, "Bye");
+    }
+
+    #[test]
+    fn skips_broken_srt_timings() {
+        let cues = parse_srt("1\nsoon --> later\nLost\n\n2\n00:00:01,000 --> 00:00:02,000\nKept\n");
+        assert_eq!(cues.len(), 1);
+        assert_eq!(cues[0].text, "Kept");
+    }
+
+    #[test]
+    fn parses_ass_events() {
+        let text = "[Script Info]\nDialogue: 0,0:00:09.00,0:00:10.00,Default,,0,0,0,,Not an event\n\n\
+                    [Events]\n\
+                    Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+                    Dialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,{\\i1}Hello{\\i0}\\Nworld, again\n";
+        let cues = parse_ass(text);
+        assert_eq!(cues.len(), 1);
+        assert_eq!((cues[0].start, cues[0].end), (1.0, 2.5));
+        assert_eq!(cues[0].text, "Hello\nworld, again");
+    }
+
+    #[test]
+    fn follows_the_declared_ass_format() {
+        let text =
+            "[Events]\nFormat: Start, End, Text\nDialogue: 0:00:03.00,0:00:04.00,Hi\\hthere\n";
+        let cues = parse_ass(text);
+        assert_eq!(cues.len(), 1);
+        assert_eq!((cues[0].start, cues[0].end), (3.0, 4.0));
+        assert_eq!(cues[0].text, "Hi there");
+    }
+}
@@ -0,0 +1,87 @@
+use crate::convert::{self, StreamInfo};
+use ffmpeg_next::{self as ffmpeg, media};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What the player measured. Decoding happens in the webview, so these
+/// numbers come from there.
+#[derive(Deserialize, Serialize)]
+pub struct Playback {
+    /// Seconds of video shown since the file was opened.
+    pub watched: f64,
+    pub presented_frames: u64,
+    pub dropped_frames: u64,
+    /// Frames per second between consecutive presented frames.
+    pub fps: Percentiles,
+    /// Whether the webview reports decoding the video as power efficient,
+    /// which in practice means in hardware. `None` when it wouldn't say.
+    pub hw_accel: Option<bool>,
+    pub user_agent: String,
+}
+
+/// Low percentiles are the interesting ones, they are the stutters.
+#[derive(Deserialize, Serialize)]
+pub struct Percentiles {
+    pub p1: f64,
+    pub p5: f64,
+    pub p50: f64,
+    pub p95: f64,
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    version: &'static str,
+    os: &'static str,
+    arch: &'static str,
+    source: Option<PathBuf>,
+    source_streams: Vec<StreamInfo>,
+    /// Streams of the file the webview plays, which differ from the source's
+    /// when it had to be converted.
+    played_streams: Vec<StreamInfo>,
+    deinterlace: bool,
+    playback: Playback,
+}
+
+pub fn report(
+    source: Option<&Path>,
+    played: &Path,
+    deinterlace: bool,
+    playback: Playback,
+) -> Report {
+    // a file that can't be probed still gets a report, that may be the bug
+    let streams = |path: &Path| {
+        convert::probe_streams(path).unwrap_or_else(|e| {
+            log::warn!("could not probe {}: {e}", path.display());
+            Vec::new()
+        })
+    };
+
+    Report {
+        version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        source: source.map(Path::to_path_buf),
+        source_streams: source.map(streams).unwrap_or_default(),
+        played_streams: streams(played),
+        deinterlace,
+        playback,
+    }
+}
+
+/// The RFC 6381 codec name of the main video stream, e.g. `avc1.64001f`,
+/// which is what the webview wants to hear when asked whether it can decode
+/// something in hardware. Only H.264 is spelled out, which is what
+/// conversions produce.
+pub fn codec_string<P: AsRef<Path> + ?Sized>(path: &P) -> Option<String> {
+    let ictx = ffmpeg::format::input(path).ok()?;
+    let stream = ictx.streams().best(media::Type::Video)?;
+    let par = unsafe { &*stream.parameters().as_ptr() };
+
+    let h264 = par.codec_id == ffmpeg::ffi::AVCodecID::AV_CODEC_ID_H264;
+    if !h264 || par.profile < 0 || par.level < 0 {
+        return None;
+    }
+    // the profile carries constraint flags above the profile_idc byte
+    let profile = par.profile & 0xff;
+    Some(format!("avc1.{profile:02x}00{:02x}", par.level))
+}
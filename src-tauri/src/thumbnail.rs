@@ -0,0 +1,145 @@
+use crate::ffhelp::FFHelp;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    path::{Path, PathBuf},
+    sync::{mpsc, Mutex},
+    time::SystemTime,
+};
+use tauri::{AppHandle, Manager};
+
+type BasicResult<T> = Result<T, Box<dyn Error>>;
+
+/// Width of seek bar previews in CSS pixels; the height follows the video.
+pub const THUMBNAIL_WIDTH: u32 = 160;
+
+/// Previews kept around; hovering back and forth asks for the same
+/// keyframes over and over.
+const CACHE_SIZE: usize = 32;
+
+struct Request {
+    path: PathBuf,
+    time: f64,
+    scale: f64,
+    reply: mpsc::Sender<Result<Vec<u8>, String>>,
+}
+
+/// Makes seek bar previews on a thread of its own, at the lowest OS
+/// priority and behind the conversion of the file being played, so hovering
+/// never slows down playback.
+pub struct Thumbnailer {
+    requests: Mutex<mpsc::Sender<Request>>,
+}
+
+impl Thumbnailer {
+    pub fn spawn(app: AppHandle) -> Self {
+        let (requests, queue) = mpsc::channel::<Request>();
+        std::thread::spawn(move || {
+            lower_thread_priority();
+            let mut worker = Worker::default();
+            let scheduler = app.state::<crate::jobs::Scheduler>();
+
+            while let Ok(mut request) = queue.recv() {
+                // only the newest position matters when several piled up
+                while let Ok(newer) = queue.try_recv() {
+                    request = newer;
+                }
+                let job = scheduler.start(crate::jobs::Priority::Preview);
+                job.checkpoint();
+                let png = worker
+                    .get(&request.path, request.time, request.scale)
+                    .map_err(|e| job.report(e));
+                let _ = request.reply.send(png);
+            }
+        });
+
+        Self {
+            requests: Mutex::new(requests),
+        }
+    }
+
+    /// PNG of the keyframe at or before `time`. `scale` is the display's
+    /// device pixels per CSS pixel, so previews stay sharp on HiDPI screens.
+    /// Blocks until the worker gets to it.
+    pub fn get<P: AsRef<Path>>(&self, path: &P, time: f64, scale: f64) -> Result<Vec<u8>, String> {
+        let (reply, answer) = mpsc::channel();
+        let request = Request {
+            path: path.as_ref().to_path_buf(),
+            time,
+            scale,
+            reply,
+        };
+        self.requests
+            .lock()
+            .unwrap()
+            .send(request)
+            .map_err(|e| e.to_string())?;
+        // a request that was skipped for a newer one gets no answer
+        answer.recv().map_err(|_| "superseded".to_string())?
+    }
+}
+
+/// Keeps a decoder open between previews, since hovering asks for many of
+/// them in a row. It is reopened whenever the file or the size changes.
+#[derive(Default)]
+struct Worker {
+    open: Option<(SystemTime, u32, FFHelp)>,
+    /// Finished PNGs by keyframe index, newest last. Cleared with `open`.
+    cache: VecDeque<(usize, Vec<u8>)>,
+}
+
+impl Worker {
+    fn get(&mut self, path: &Path, time: f64, scale: f64) -> BasicResult<Vec<u8>> {
+        let modified = std::fs::metadata(path)?.modified()?;
+        let width = (THUMBNAIL_WIDTH as f64 * scale.clamp(1.0, 4.0)).round() as u32;
+
+        if !matches!(&self.open, Some((m, w, _)) if *m == modified && *w == width) {
+            self.open = Some((modified, width, FFHelp::open_scaled(&path, width)?));
+            self.cache.clear();
+        }
+        let (_, _, help) = self.open.as_mut().unwrap();
+
+        let index = help
+            .frame_index(time)
+            .min(help.total_frames().saturating_sub(1));
+        if let Some((_, png)) = self.cache.iter().find(|(i, _)| *i == index) {
+            return Ok(png.clone());
+        }
+
+        let rgba = help.get_keyframe(index)?;
+        let (w, h) = help.get_width_height();
+        let png = crate::screenshot::encode_png(&rgba, w, h)?;
+
+        if self.cache.len() == CACHE_SIZE {
+            self.cache.pop_front();
+        }
+        self.cache.push_back((index, png.clone()));
+        Ok(png)
+    }
+}
+
+#[cfg(windows)]
+fn lower_thread_priority() {
+    const THREAD_PRIORITY_LOWEST: i32 = -2;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentThread() -> isize;
+        fn SetThreadPriority(thread: isize, priority: i32) -> i32;
+    }
+
+    unsafe { SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_LOWEST) };
+}
+
+#[cfg(target_os = "linux")]
+fn lower_thread_priority() {
+    extern "C" {
+        fn nice(inc: i32) -> i32;
+    }
+
+    // Linux threads have a nice value of their own, unlike elsewhere
+    unsafe { nice(19) };
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn lower_thread_priority() {}
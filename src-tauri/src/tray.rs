@@ -0,0 +1,104 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{
+    menu::{Menu, MenuItem, PredefinedMenuItem},
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    AppHandle, Emitter, Manager, WebviewWindow, Wry,
+};
+
+/// The tray icon's menu entries that change while running.
+pub struct Tray {
+    awake: MenuItem<Wry>,
+    /// Set by Exit, so closing really closes instead of hiding to the tray.
+    quitting: AtomicBool,
+}
+
+impl Tray {
+    pub fn quitting(&self) -> bool {
+        self.quitting.load(Ordering::Relaxed)
+    }
+
+    /// Shows whether the player keeps the screen from going to sleep.
+    pub fn set_awake(&self, active: bool) {
+        let text = if active {
+            "Keeping the screen awake"
+        } else {
+            "Screen may sleep"
+        };
+        if let Err(e) = self.awake.set_text(text) {
+            log::warn!("could not update the tray menu: {e}");
+        }
+    }
+}
+
+/// Adds a tray icon with playback controls, so the player can be used while
+/// minimized. Entries named after a keymap action are sent to the player as
+/// `tray-action` and do what the key would.
+pub fn create(app: &AppHandle) -> tauri::Result<Tray> {
+    let play_pause = MenuItem::with_id(app, "play-pause", "Play/Pause", true, None::<&str>)?;
+    let previous = MenuItem::with_id(app, "playlist-previous", "Previous", true, None::<&str>)?;
+    let next = MenuItem::with_id(app, "playlist-next", "Next", true, None::<&str>)?;
+    let awake = MenuItem::with_id(app, "awake", "Screen may sleep", false, None::<&str>)?;
+    let show = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
+    let exit = MenuItem::with_id(app, "exit", "Exit", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &play_pause,
+            &previous,
+            &next,
+            &PredefinedMenuItem::separator(app)?,
+            &awake,
+            &PredefinedMenuItem::separator(app)?,
+            &show,
+            &exit,
+        ],
+    )?;
+
+    let mut tray = TrayIconBuilder::with_id("main")
+        .tooltip("SimpleVidView")
+        .menu(&menu)
+        .on_menu_event(|app, event| {
+            let window = app.get_webview_window("main");
+            match (event.id().as_ref(), window) {
+                ("show", Some(window)) => restore(&window),
+                // through the window, so it gets to clean up as on any close
+                ("exit", Some(window)) => {
+                    if let Some(tray) = app.try_state::<Tray>() {
+                        tray.quitting.store(true, Ordering::Relaxed);
+                    }
+                    let _ = window.close();
+                }
+                ("exit", None) => app.exit(0),
+                (action, _) => app.emit("tray-action", action).unwrap(),
+            }
+        })
+        .show_menu_on_left_click(false)
+        .on_tray_icon_event(|icon, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                if let Some(window) = icon.app_handle().get_webview_window("main") {
+                    restore(&window);
+                }
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+
+    Ok(Tray {
+        awake,
+        quitting: AtomicBool::new(false),
+    })
+}
+
+/// Brings the window back from the tray or the taskbar.
+fn restore(window: &WebviewWindow) {
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+}
@@ -0,0 +1,83 @@
+use notify::{event::ModifyKind, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+const VIDEO_EXTENSIONS: [&str; 12] = [
+    "mkv", "webm", "avi", "mov", "m4v", "flv", "wmv", "ts", "mts", "mpg", "mpeg", "gif",
+];
+
+pub fn is_video_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| VIDEO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// Watches `dir`, converting every new video file that shows up in it and
+/// appending it to the playlist. `.mp4` files are played as-is, so they are
+/// left alone.
+pub fn spawn(app: AppHandle, dir: &Path) -> notify::Result<RecommendedWatcher> {
+    // a download usually produces several events, only convert once
+    let pending = Arc::new(Mutex::new(HashSet::<PathBuf>::new()));
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        // browsers download into a temp name and rename it when done
+        if !matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))
+        ) {
+            return;
+        }
+
+        for path in event.paths {
+            if !path.is_file() || !is_video_file(&path) {
+                continue;
+            }
+            if !pending.lock().unwrap().insert(path.clone()) {
+                continue;
+            }
+
+            let app = app.clone();
+            let pending = pending.clone();
+            std::thread::spawn(move || {
+                wait_until_written(&path);
+                match crate::cache::convert(&app, &path) {
+                    Ok(_) => {
+                        let state = app.state::<crate::AppState>();
+                        state.playlist.lock().unwrap().items.push(path.clone());
+                        app.emit("playlist-changed", ()).unwrap();
+                    }
+                    Err(e) => {
+                        log::warn!("background conversion of {} failed: {e}", path.display())
+                    }
+                }
+                pending.lock().unwrap().remove(&path);
+            });
+        }
+    })?;
+
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
+
+/// Blocks until the file size stops changing.
+fn wait_until_written(path: &Path) {
+    let mut last_len = None;
+    loop {
+        let Ok(meta) = std::fs::metadata(path) else {
+            return;
+        };
+        if last_len == Some(meta.len()) {
+            return;
+        }
+        last_len = Some(meta.len());
+        std::thread::sleep(Duration::from_secs(2));
+    }
+}
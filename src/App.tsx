@@ -1,9 +1,29 @@
-import { MouseEvent, useCallback, useEffect, useRef, useState } from "react";
-import { FaPause, FaPlay } from "react-icons/fa6";
+import { MouseEvent, useCallback, useEffect, useMemo, useRef, useState, WheelEvent } from "react";
+import { FaCamera, FaPause, FaPlay } from "react-icons/fa6";
 import "./App.css";
-import { convertFileSrc } from "@tauri-apps/api/core";
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
 import { exists } from '@tauri-apps/plugin-fs';
+import { activeCues, Cue, indexCues, searchCues } from "./subtitles";
+import { chapterAt, Marker, nextChapter, previousChapter } from "./chapters";
+import { FileLock, NO_LOCK, lockedAt } from "./lock";
+import { setLanguage, t } from "./i18n";
+import { formatClock } from "./time";
+import { Action, actionFor, DEFAULT_KEYMAP, Keymap, mergeKeymap } from "./keymap";
+import { adjust, DEFAULT_EQUALIZER, describe, Equalizer, equalizerFilter } from "./equalizer";
+import { extendShape, Point, Shape, shapePath, startShape, THICKNESS, Tool, TOOLS } from "./annotate";
+import { FrameStats, hardwareDecoding, LateFrames } from "./telemetry";
+import { setSpeed, stepSpeed } from "./speed";
+import { SeekDucker } from "./ducking";
+import { ScrubTracker } from "./scrub";
+import { startVisualizer } from "./visualizer";
+import { LoopRing } from "./loopring";
+import { AmbientSampler, ambientBackground, EdgeColors } from "./ambient";
+import { WakeLock } from "./wakelock";
+import { GamepadInput } from "./gamepad";
+import { TouchGestures } from "./touch";
+import { cycleAspect, DEFAULT_VIEW, panBy, rotate, Size, videoTransform, View, zoomAt } from "./view";
 
 function useVideoFrame(
     videoRef: React.RefObject<HTMLVideoElement | null>,
@@ -54,11 +74,65 @@ function useVideoFrame(
     }, [videoRef, onFrame]);
 }
 
-function ProgressBar(props: { progress: number, onChange: (n: number) => void }) {
+function ProgressBar(props: { progress: number, duration: number, marks: Marker[], loop: [number, number] | null, locked: (time: number) => boolean, onChange: (n: number) => void, onRelease: () => void, onWheel: (ev: WheelEvent<HTMLDivElement>) => void }) {
     const [isDragging, setIsDragging] = useState(false);
     const [dragPos, setDragPos] = useState(0);
+    const [hoverPos, setHoverPos] = useState<number | null>(null);
+    const [thumbnail, setThumbnail] = useState<string | null>(null);
+    // time the preview should show; only one request is in flight at a time
+    const wantedTime = useRef<number | null>(null);
+    const fetching = useRef(false);
     const barRef = useRef<HTMLDivElement | null>(null);
 
+    const fetchThumbnail = () => {
+        const time = wantedTime.current;
+        if (fetching.current || time === null) {
+            return;
+        }
+        // the backend refuses previews of locked stretches anyway
+        if (props.locked(time)) {
+            setThumbnail(old => {
+                if (old) {
+                    URL.revokeObjectURL(old);
+                }
+                return null;
+            });
+            return;
+        }
+
+        fetching.current = true;
+        invoke<ArrayBuffer>("get_thumbnail", { time, scale: window.devicePixelRatio }).then(png => {
+            const url = URL.createObjectURL(new Blob([png], { type: "image/png" }));
+            setThumbnail(old => {
+                if (old) {
+                    URL.revokeObjectURL(old);
+                }
+                return url;
+            });
+        }).catch(console.error).finally(() => {
+            fetching.current = false;
+            if (wantedTime.current !== time) {
+                fetchThumbnail();
+            }
+        });
+    };
+
+    const handleHover = (e: MouseEvent<HTMLDivElement>) => {
+        if (!barRef.current || !props.duration) {
+            return;
+        }
+        const rect = barRef.current.getBoundingClientRect();
+        const fraction = Math.min(Math.max((e.clientX - rect.left) / rect.width, 0), 1);
+        setHoverPos(fraction);
+        wantedTime.current = fraction * props.duration;
+        fetchThumbnail();
+    };
+
+    const handleLeave = () => {
+        setHoverPos(null);
+        wantedTime.current = null;
+    };
+
     const updateProgressFromEvent = (e: any) => {
         if (!barRef.current) {
             return;
@@ -84,10 +158,19 @@ function ProgressBar(props: { progress: number, onChange: (n: number) => void })
     };
 
     const handleMouseUp = () => {
+        if (isDragging) {
+            props.onRelease();
+        }
         setIsDragging(false);
         setDragPos(0);
     };
 
+    const position = Math.min(Math.max(isDragging ? dragPos : props.progress, 0), 1) || 0;
+    // motion marks have no title to show
+    const hoverChapter = hoverPos === null
+        ? undefined
+        : chapterAt(props.marks.filter(m => m.title), hoverPos * props.duration);
+
     useEffect(() => {
         window.addEventListener("mousemove", handleMouseMove);
         window.addEventListener("mouseup", handleMouseUp);
@@ -98,17 +181,325 @@ function ProgressBar(props: { progress: number, onChange: (n: number) => void })
     })
 
     return (
-        <div className="pbar" ref={barRef} onMouseDown={handleMouseDown}>
-            <div className="pbardrag" style={{ width: `${isDragging ? dragPos * 100 : props.progress * 100}%`, transition: isDragging ? "none" : "width 0.1s" }} />
+        <div className={isDragging ? "pbar dragging" : "pbar"} ref={barRef} onMouseDown={handleMouseDown} onMouseMove={handleHover} onMouseLeave={handleLeave} onWheel={props.onWheel}>
+            {hoverPos !== null && <div className="pbarhover" style={{ width: `${hoverPos * 100}%` }} />}
+            <div className="pbardrag" style={{ width: `${position * 100}%`, transition: isDragging ? "none" : "width 0.1s" }} />
+            {props.loop && (
+                <div className="pbarloop" style={{ left: `${props.loop[0] * 100}%`, width: `${(props.loop[1] - props.loop[0]) * 100}%` }} />
+            )}
+            {props.duration > 0 && props.marks.map((m, i) => (
+                <div
+                    key={i}
+                    className={m === hoverChapter ? "pbartick current" : "pbartick"}
+                    style={{ left: `${m.start / props.duration * 100}%` }}
+                />
+            ))}
+            <div className="pbarknob" style={{ left: `${position * 100}%`, transition: isDragging ? "none" : "left 0.1s" }} />
+            {hoverPos !== null && (
+                <div className="pbarthumb" style={{ left: `${hoverPos * 100}%` }}>
+                    {thumbnail && <img src={thumbnail} />}
+                    {hoverChapter && <span className="chapter">{hoverChapter.title}</span>}
+                    <span>{formatClock(hoverPos * props.duration)}</span>
+                </div>
+            )}
         </div>
     );
 }
 
+/**
+ * Drawing surface over a paused video. It takes the video's transform so
+ * shapes stay on the picture, and works in video pixels.
+ */
+function AnnotationLayer(props: { shapes: Shape[], tool: Tool, video: Size, transform: string, onAdd: (shape: Shape) => void }) {
+    const [draft, setDraft] = useState<Shape | null>(null);
+    const svgRef = useRef<SVGSVGElement | null>(null);
+
+    const toVideo = (e: MouseEvent<SVGSVGElement>): Point => {
+        const svg = svgRef.current!;
+        const p = new DOMPoint(e.clientX, e.clientY).matrixTransform(svg.getScreenCTM()!.inverse());
+        return [p.x, p.y];
+    };
+
+    const finish = () => {
+        if (draft) {
+            props.onAdd(draft);
+            setDraft(null);
+        }
+    };
+
+    // stop the events here so the box underneath doesn't pan
+    return (
+        <svg
+            ref={svgRef}
+            className="annotations"
+            viewBox={`0 0 ${props.video.width} ${props.video.height}`}
+            preserveAspectRatio="none"
+            style={{ transform: props.transform, strokeWidth: THICKNESS }}
+            onMouseDown={e => { e.stopPropagation(); setDraft(startShape(props.tool, toVideo(e))); }}
+            onMouseMove={e => { e.stopPropagation(); if (draft) setDraft(extendShape(draft, toVideo(e))); }}
+            onMouseUp={finish}
+            onMouseLeave={finish}
+            onDoubleClick={e => e.stopPropagation()}
+        >
+            {[...props.shapes, ...(draft ? [draft] : [])].map((shape, i) => <path key={i} d={shapePath(shape)} />)}
+        </svg>
+    );
+}
+
+/** What the display can show; see `filter::Gamut` in the backend. */
+function displayGamut(): string {
+    if (matchMedia("(color-gamut: rec2020)").matches) {
+        return "rec2020";
+    }
+    return matchMedia("(color-gamut: p3)").matches ? "p3" : "srgb";
+}
+
+type VideoInfo = {
+    fps: number;
+    width: number;
+    height: number;
+    codec: string | null;
+};
+
+/**
+ * Pauses and moves `frames` frames forward or back. Lands in the middle of the
+ * target frame so rounding in the decoder can't pick its neighbour.
+ */
+function stepFrames(video: HTMLVideoElement, fps: number, frames: number) {
+    video.pause();
+    const current = Math.floor(video.currentTime * fps + 1e-3);
+    const target = Math.max(current + frames, 0);
+    video.currentTime = Math.min((target + 0.5) / fps, video.duration);
+}
+
+/** Seeks by `seconds`, unlike `stepFrames` this leaves playback running. */
+function seekBy(video: HTMLVideoElement, seconds: number): number {
+    const target = Math.min(Math.max(video.currentTime + seconds, 0), video.duration);
+    video.currentTime = target;
+    return target;
+}
+
+type RecentFile = {
+    path: string;
+    name: string;
+};
+
+type SeekSteps = {
+    short: number;
+    long: number;
+};
+
+type Verification = {
+    ok: boolean;
+    problems: string[];
+    source_duration: number;
+    output_duration: number;
+};
+
+type PlaylistInfo = {
+    items: string[];
+    current: number;
+};
+
+/** What `get_osd_theme` returns; see `config::OsdTheme` in the backend. */
+type OsdTheme = {
+    color: string | null;
+    background: string | null;
+    font_family: string | null;
+    font_size: number;
+    duration: number;
+};
+
+/** Hands the `[osd]` table to the stylesheet as custom properties. */
+function applyOsdTheme(theme: OsdTheme) {
+    const style = document.documentElement.style;
+    const set = (name: string, value: string | null) => value === null ? style.removeProperty(name) : style.setProperty(name, value);
+    set("--osd-color", theme.color);
+    set("--osd-background", theme.background);
+    set("--osd-font", theme.font_family);
+    set("--osd-size", String(theme.font_size));
+}
+
+type Appearance = {
+    high_contrast: boolean;
+    large_controls: boolean;
+    ambient: boolean;
+    scaling: "linear" | "nearest";
+    integer_scale: boolean;
+};
+
+/** Loop-in and loop-out points in seconds; `b` is only set once `a` is. */
+type AbLoop = { a: number | null, b: number | null };
+
+/** The PIN box: what has been typed and what to do with it on Enter. */
+type PinPrompt = { value: string, then: (pin: string) => void };
+
+/** A small menu asking which format to export, labelled with `format.*` strings. */
+type FormatChoice = { title: string, formats: string[], then: (format: string) => void };
+
+/** What happens at the end of a file: pause, start it over, or open the next playlist item. */
+type LoopMode = "stop" | "file" | "playlist";
+const LOOP_MODES: LoopMode[] = ["playlist", "file", "stop"];
+
+/** More matches than fit on screen are not worth rendering. */
+const MAX_SEARCH_RESULTS = 50;
+
+/** Looping files up to this long, in seconds, are kept in a `LoopRing`. */
+const SHORT_CLIP = 30;
+
+/** Where the next file starts, from a link or `--time`/`--frame`. */
+type StartAt = {
+    time: number;
+    paused: boolean;
+};
+
+/** What `cycle_audio_track` returns; see `AudioTrack` in the backend. */
+type AudioTrack = {
+    n: number;
+    count: number;
+    name: string;
+};
+
+/** What `get_session` returns; see `settings::Session` in the backend. */
+type Session = {
+    items: string[];
+    current: number;
+};
+
+/** Command line options that apply to every file. */
+type LaunchOptions = {
+    speed: number | null;
+    loop_mode: LoopMode | null;
+};
+
+/** What `get_settings` returns; see `settings::Settings` in the backend. */
+type Settings = {
+    volume: number;
+    muted: boolean;
+    loop_mode: string;
+};
+
+/** How long the offer to resume where the file was left off stays open. */
+const RESUME_OFFER_TIME = 6000;
+
+/** How long the mouse has to sit still in fullscreen before the controls hide. */
+const CONTROLS_HIDE_DELAY = 3000;
+
+/**
+ * Re-requests the video so the player sees data appended since it was
+ * loaded, then continues from `time`.
+ */
+function reloadAt(video: HTMLVideoElement, time: number) {
+    const wasPlaying = !video.paused || video.ended;
+    video.src = convertFileSrc('v.mp4', 'stream') + `?t=${Date.now()}`;
+    video.addEventListener("loadedmetadata", () => {
+        video.currentTime = Math.min(time, video.duration);
+        if (wasPlaying) {
+            video.play();
+        }
+    }, { once: true });
+}
+
+/**
+ * Keeps `text` for the OSD after the page reloads. Messages from the same
+ * conversion, like the verification report and skipped packets, queue up
+ * and are shown together.
+ */
+function keepOsd(text: string) {
+    const pending = sessionStorage.getItem("osd");
+    sessionStorage.setItem("osd", pending ? `${pending}\n${text}` : text);
+}
+
+async function toggleFullscreen(): Promise<boolean> {
+    const win = getCurrentWindow();
+    const fullscreen = !(await win.isFullscreen());
+    await win.setFullscreen(fullscreen);
+    return fullscreen;
+}
+
 export default function App() {
     const [playing, setPlaying] = useState(false);
     const [progress, setProgress] = useState(0);
     const [loading, setLoading] = useState(0);
     const [fileExists, setFileExists] = useState(true);
+    const [fullscreen, setFullscreen] = useState(false);
+    /** Small always-on-top window in a screen corner. */
+    const [pip, setPip] = useState(false);
+    const wakeLockRef = useRef<WakeLock | null>(null);
+    /** Lines of the stats overlay; null while it is closed. */
+    const [stats, setStats] = useState<string[] | null>(null);
+    const showStatsRef = useRef(false);
+    const [controlsVisible, setControlsVisible] = useState(true);
+    const [cues, setCues] = useState<Cue[]>([]);
+    const [topCues, setTopCues] = useState<Cue[]>([]);
+    // null while the search box is closed
+    const [search, setSearch] = useState<string | null>(null);
+    const searchIndex = useMemo(() => indexCues([...cues, ...topCues]), [cues, topCues]);
+    const [chapters, setChapters] = useState<Marker[]>([]);
+    const chaptersRef = useRef<Marker[]>([]);
+    const fpsRef = useRef(30);
+    const videoInfoRef = useRef<VideoInfo | null>(null);
+    const frameStatsRef = useRef(new FrameStats());
+    const keymapRef = useRef<Keymap>(DEFAULT_KEYMAP);
+    const seekStepsRef = useRef<SeekSteps>({ short: 5, long: 30 });
+    const resumeRef = useRef<number | null>(null);
+    // a previous session waiting to be restored with the resume key
+    const sessionRef = useRef(false);
+    // shapes are only kept while in drawing mode
+    const [shapes, setShapes] = useState<Shape[] | null>(null);
+    const shapesRef = useRef<Shape[] | null>(null);
+    const [tool, setTool] = useState<Tool>("rect");
+    const [recent, setRecent] = useState<RecentFile[]>([]);
+    // motion events as markers, so they can be walked like chapters
+    const [motion, setMotion] = useState<Marker[]>([]);
+    const motionRef = useRef<Marker[]>([]);
+    const [intro, setIntro] = useState<[number, number] | null>(null);
+    const introRef = useRef<[number, number] | null>(null);
+    const [showRecent, setShowRecent] = useState(false);
+    const toolRef = useRef<Tool>("rect");
+    const savedPositionRef = useRef(0);
+    const [abLoop, setAbLoop] = useState<AbLoop>({ a: null, b: null });
+    const abLoopRef = useRef<AbLoop>({ a: null, b: null });
+    const locksRef = useRef<FileLock>(NO_LOCK);
+    // the PIN was given for this file, which reloads the page
+    const unlockedRef = useRef(false);
+    const [pinPrompt, setPinPrompt] = useState<PinPrompt | null>(null);
+    const [formatChoice, setFormatChoice] = useState<FormatChoice | null>(null);
+    const [playlist, setPlaylist] = useState<PlaylistInfo>({ items: [], current: 0 });
+    const [osd, setOsd] = useState<string | null>(null);
+    const [view, setView] = useState<View>(DEFAULT_VIEW);
+    const [boxSize, setBoxSize] = useState<Size>({ width: 0, height: 0 });
+    const [videoSize, setVideoSize] = useState<Size>({ width: 0, height: 0 });
+    /** The file has sound but no picture, so a visualizer stands in. */
+    const [audioOnly, setAudioOnly] = useState(false);
+    /** Object URL of the embedded cover of a music file. */
+    const [cover, setCover] = useState<string | null>(null);
+    const canvasRef = useRef<HTMLCanvasElement | null>(null);
+    /** Frames of the current loop, once it has played through once. */
+    const ringRef = useRef<LoopRing | null>(null);
+    const [ringReady, setRingReady] = useState(false);
+    const ringCanvasRef = useRef<HTMLCanvasElement | null>(null);
+    /** Colours for the bars around the picture; black while null. */
+    const [ambient, setAmbient] = useState<EdgeColors | null>(null);
+    const ambientRef = useRef<AmbientSampler | null>(null);
+    const boxRef = useRef<HTMLDivElement | null>(null);
+    // only here to re-render once the configured language is known
+    const [, setLanguageTag] = useState(navigator.language);
+    const [equalizer, setEqualizer] = useState<Equalizer>(DEFAULT_EQUALIZER);
+    // colour adjustments cost GPU time on every frame, so they pause meanwhile
+    const [powerSaver, setPowerSaver] = useState(false);
+    const equalizerRef = useRef<Equalizer>(DEFAULT_EQUALIZER);
+    const duckerRef = useRef(new SeekDucker());
+    const scrubRef = useRef(new ScrubTracker());
+    /** Duration of a source that is still being written, as last probed. */
+    const [growing, setGrowing] = useState<number | null>(null);
+    /** Playback is stalled waiting for data, e.g. from a network stream. */
+    const [buffering, setBuffering] = useState(false);
+    const [loopMode, setLoopMode] = useState<LoopMode>("playlist");
+    const loopModeRef = useRef<LoopMode>("playlist");
+    const hideTimer = useRef<number | null>(null);
+    const osdTimer = useRef<number | null>(null);
+    const osdDurationRef = useRef(2000);
     const vidRef = useRef<HTMLVideoElement | null>(null);
     const playCallback = useCallback(() => {
         setPlaying(prev => {
@@ -126,8 +517,203 @@ export default function App() {
         });
     }, [playing, vidRef]);
 
-    useVideoFrame(vidRef, (curTime) => {
+    const showOsd = useCallback((text: string, ms: number = osdDurationRef.current) => {
+        setOsd(text);
+        if (osdTimer.current !== null) {
+            clearTimeout(osdTimer.current);
+        }
+        osdTimer.current = setTimeout(() => setOsd(null), ms);
+    }, []);
+
+    const updateShapes = useCallback((next: Shape[] | null) => {
+        shapesRef.current = next;
+        setShapes(next);
+    }, []);
+
+    const takeScreenshot = useCallback(() => {
+        if (vidRef.current) {
+            invoke<string>("save_screenshot", { time: vidRef.current.currentTime, shapes: shapesRef.current })
+                .then(path => showOsd(t("saved", { path })))
+                .catch(e => showOsd(t(e)));
+        }
+    }, [showOsd]);
+
+    const copyFrame = useCallback(() => {
+        if (vidRef.current) {
+            const png = invoke<ArrayBuffer>("capture_frame", { time: vidRef.current.currentTime, shapes: shapesRef.current })
+                .then(bytes => new Blob([bytes], { type: "image/png" }));
+            // handing over the promise keeps the clipboard call inside the key press
+            navigator.clipboard.write([new ClipboardItem({ "image/png": png })])
+                .then(() => showOsd(t("frame.copied")))
+                .catch(e => showOsd(typeof e === "string" ? t(e) : String(e)));
+        }
+    }, [showOsd]);
+
+    const fullscreenCallback = useCallback(() => {
+        toggleFullscreen().then(setFullscreen);
+    }, []);
+
+    const showControls = useCallback(() => {
+        setControlsVisible(true);
+        if (hideTimer.current !== null) {
+            clearTimeout(hideTimer.current);
+            hideTimer.current = null;
+        }
+        if (fullscreen) {
+            hideTimer.current = setTimeout(() => setControlsVisible(false), CONTROLS_HIDE_DELAY);
+        }
+    }, [fullscreen]);
+
+    useEffect(() => {
+        showControls();
+        window.addEventListener("mousemove", showControls);
+        return () => {
+            window.removeEventListener("mousemove", showControls);
+            if (hideTimer.current !== null) {
+                clearTimeout(hideTimer.current);
+            }
+        };
+    }, [showControls]);
+
+    const adjustEqualizer = useCallback((key: keyof Equalizer, delta: number) => {
+        const next = adjust(equalizerRef.current, key, delta);
+        equalizerRef.current = next;
+        setEqualizer(next);
+        showOsd(t(`eq.${key}`, { value: describe(next, key) }));
+    }, [showOsd]);
+
+    const updateAbLoop = useCallback((loop: AbLoop) => {
+        abLoopRef.current = loop;
+        setAbLoop(loop);
+    }, []);
+
+    useEffect(() => {
+        if (!audioOnly || !vidRef.current || !canvasRef.current) {
+            return;
+        }
+        return startVisualizer(vidRef.current, canvasRef.current);
+    }, [audioOnly]);
+
+    useEffect(() => {
+        wakeLockRef.current = new WakeLock(active => {
+            invoke("set_awake", { active }).catch(console.error);
+        });
+        const late = new LateFrames();
+        // refreshed twice a second while open
+        const timer = setInterval(() => {
+            const video = vidRef.current;
+            if (video && !video.paused && late.check(video)) {
+                const quality = video.getVideoPlaybackQuality();
+                showOsd(t("stats.late", { dropped: quality.droppedVideoFrames }), 5000);
+            }
+            if (!showStatsRef.current || !video) {
+                return;
+            }
+            const quality = video.getVideoPlaybackQuality();
+            setStats([
+                t("stats.video", { width: video.videoWidth, height: video.videoHeight, fps: fpsRef.current.toFixed(2) }),
+                t("stats.frames", { dropped: quality.droppedVideoFrames, total: quality.totalVideoFrames }),
+                t("speed", { rate: video.playbackRate }),
+                t(wakeLockRef.current?.active ? "stats.awake" : "stats.sleep"),
+            ]);
+        }, 500);
+        return () => clearInterval(timer);
+    }, []);
+
+    useEffect(() => {
+        if (!audioOnly) {
+            return;
+        }
+        let url: string | null = null;
+        invoke<ArrayBuffer>("get_cover_art").then(image => {
+            url = URL.createObjectURL(new Blob([image]));
+            setCover(url);
+        }).catch(() => { /* no cover, the visualizer is enough */ });
+        return () => {
+            if (url) {
+                URL.revokeObjectURL(url);
+            }
+            setCover(null);
+        };
+    }, [audioOnly]);
+
+    // an A-B loop, or a short file that loops as a whole, is captured for a
+    // seamless wrap
+    useEffect(() => {
+        ringRef.current?.dispose();
+        ringRef.current = null;
+        setRingReady(false);
+
+        const video = vidRef.current;
+        if (!video || growing !== null) {
+            return;
+        }
+        const region: [number, number] | null = abLoop.a !== null && abLoop.b !== null
+            ? [abLoop.a, abLoop.b]
+            : video.loop && video.duration <= SHORT_CLIP ? [0, video.duration] : null;
+        if (region) {
+            const maxWidth = video.clientWidth * window.devicePixelRatio;
+            ringRef.current = LoopRing.create(video, region[0], region[1], fpsRef.current, maxWidth);
+        }
+    }, [abLoop, loopMode, playlist.items.length, videoSize, growing]);
+
+    useEffect(() => {
+        const video = vidRef.current;
+        const canvas = ringCanvasRef.current;
+        if (!ringReady || !video || !canvas) {
+            return;
+        }
+        let frame = 0;
+        const draw = () => {
+            frame = requestAnimationFrame(draw);
+            const bitmap = ringRef.current?.frameAt(video.currentTime);
+            const ctx = canvas.getContext("2d");
+            if (!bitmap || !ctx) {
+                return;
+            }
+            if (canvas.width !== bitmap.width || canvas.height !== bitmap.height) {
+                canvas.width = bitmap.width;
+                canvas.height = bitmap.height;
+            }
+            ctx.drawImage(bitmap, 0, 0);
+        };
+        draw();
+        return () => cancelAnimationFrame(frame);
+    }, [ringReady]);
+
+    useVideoFrame(vidRef, (curTime, metadata) => {
         setProgress(curTime);
+        if (metadata) {
+            frameStatsRef.current.record(metadata);
+        }
+
+        const colors = vidRef.current && ambientRef.current?.sample(vidRef.current);
+        if (colors) {
+            setAmbient(colors);
+        }
+
+        const ring = ringRef.current;
+        if (ring && !ring.ready && vidRef.current) {
+            ring.capture(vidRef.current, curTime);
+            if (ring.ready) {
+                setRingReady(true);
+            }
+        }
+
+        const video = vidRef.current;
+        const locked = unlockedRef.current ? undefined : lockedAt(locksRef.current, curTime);
+        // hold at the start of the lock, also when seeking into it while paused
+        if (video && locked && (!video.paused || curTime > locked.start + 0.05)) {
+            video.pause();
+            setPlaying(false);
+            video.currentTime = locked.start;
+            askUnlock();
+        }
+
+        const { a, b } = abLoopRef.current;
+        if (vidRef.current && a !== null && b !== null && curTime >= b) {
+            vidRef.current.currentTime = a;
+        }
     });
 
     useEffect(() => {
@@ -136,20 +722,414 @@ export default function App() {
             vidRef.current.load();
             vidRef.current.play();
             setPlaying(true);
+            invoke<StartAt | null>("take_start_time").then(async start => {
+                if (start !== null && vidRef.current) {
+                    // --time and --frame hold still on the exact picture
+                    if (start.paused) {
+                        vidRef.current.pause();
+                        setPlaying(false);
+                    }
+                    vidRef.current.currentTime = start.time;
+                    return;
+                }
+                const resume = await invoke<number | null>("get_resume_position");
+                if (resume !== null) {
+                    resumeRef.current = resume;
+                    showOsd(t("resume.offer", { time: formatClock(resume) }), RESUME_OFFER_TIME);
+                    setTimeout(() => resumeRef.current = null, RESUME_OFFER_TIME);
+                }
+            }).catch(console.error);
         }
     }, [vidRef]);
 
+    useEffect(() => {
+        // only offered when started without anything to play
+        invoke<Session | null>("get_session").then(session => {
+            const current = session?.items[session.current];
+            if (!session || current === undefined) {
+                return;
+            }
+            sessionRef.current = true;
+            const name = current.split(/[\\/]/).pop() ?? current;
+            showOsd(t("session.offer", { name, count: session.items.length }), RESUME_OFFER_TIME);
+            setTimeout(() => sessionRef.current = false, RESUME_OFFER_TIME);
+        }).catch(console.error);
+    }, []);
+
+    /** Opens the PIN box, unless it already is. */
+    function askPin(then: (pin: string) => void) {
+        setPinPrompt(open => open ?? { value: "", then });
+    }
+
+    function askUnlock() {
+        showOsd(t("lock.locked"));
+        askPin(pin => invoke<boolean>("check_pin", { pin }).then(ok => {
+            if (!ok) {
+                showOsd(t("lock.wrong-pin"));
+                return;
+            }
+            unlockedRef.current = true;
+            showOsd(t("lock.unlocked"));
+            const video = vidRef.current;
+            // the stream of a file locked as a whole was refused until now
+            if (video && locksRef.current.whole) {
+                reloadAt(video, video.currentTime);
+            }
+            video?.play();
+            setPlaying(true);
+        }).catch(console.error));
+    }
+
     function keyDown(ev: KeyboardEvent) {
+        // typing into the search box is not a shortcut
+        if (ev.target instanceof HTMLInputElement) {
+            return;
+        }
+        console.log(ev.code);
+        runAction(actionFor(keymapRef.current, ev));
+    }
+
+    /** Does what the key bound to `action` does; the tray menu uses it too. */
+    function runAction(action: Action | undefined) {
         if (vidRef.current) {
-            console.log(ev.code);
-            switch (ev.code) {
-                case "ArrowRight":
+            switch (action) {
+                case "volume-up":
+                case "volume-down": {
+                    const video = vidRef.current;
+                    video.volume = Math.min(Math.max(video.volume + (action === "volume-up" ? 0.05 : -0.05), 0), 1);
+                    showOsd(t("volume", { percent: Math.round(video.volume * 100) }));
                     break;
-                case "ArrowLeft":
+                }
+                case "step-forward":
+                    stepFrames(vidRef.current, fpsRef.current, 1);
+                    setPlaying(false);
                     break;
-                case "Space":
+                case "step-back":
+                    stepFrames(vidRef.current, fpsRef.current, -1);
+                    setPlaying(false);
+                    break;
+                case "seek-forward":
+                case "seek-back":
+                case "jump-forward":
+                case "jump-back": {
+                    const step = action.startsWith("seek") ? seekStepsRef.current.short : seekStepsRef.current.long;
+                    duckerRef.current.nudge(vidRef.current);
+                    const target = seekBy(vidRef.current, action.endsWith("forward") ? step : -step);
+                    showOsd(formatClock(target, vidRef.current.duration >= 3600));
+                    break;
+                }
+                case "resume":
+                    if (sessionRef.current) {
+                        sessionRef.current = false;
+                        invoke("restore_session").catch(e => showOsd(t(e)));
+                    } else if (resumeRef.current !== null) {
+                        vidRef.current.currentTime = resumeRef.current;
+                        resumeRef.current = null;
+                        showOsd(formatClock(vidRef.current.currentTime));
+                    }
+                    break;
+                case "play-pause":
                     playCallback();
                     break;
+                case "fullscreen":
+                    fullscreenCallback();
+                    break;
+                case "stats":
+                    showStatsRef.current = !showStatsRef.current;
+                    if (!showStatsRef.current) {
+                        setStats(null);
+                    }
+                    break;
+                case "pip": {
+                    const video = vidRef.current;
+                    invoke<boolean>("toggle_pip", { aspect: video.videoWidth / video.videoHeight }).then(on => {
+                        setPip(on);
+                        document.documentElement.classList.toggle("pip", on);
+                        if (on) {
+                            setFullscreen(false);
+                        }
+                    }).catch(console.error);
+                    break;
+                }
+                case "audio-track":
+                    // the file is converted again with the new track and reloaded where it was
+                    invoke<AudioTrack | null>("cycle_audio_track", {
+                        time: vidRef.current.currentTime,
+                        paused: vidRef.current.paused,
+                    }).then(track => showOsd(track ? t("audio.track", track).trim() : t("audio.none")))
+                        .catch(e => showOsd(t(e)));
+                    break;
+                case "next-chapter": {
+                    const next = nextChapter(chaptersRef.current, vidRef.current.currentTime);
+                    if (next !== undefined) {
+                        vidRef.current.currentTime = next;
+                    }
+                    break;
+                }
+                case "previous-chapter": {
+                    const prev = previousChapter(chaptersRef.current, vidRef.current.currentTime);
+                    vidRef.current.currentTime = prev ?? 0;
+                    break;
+                }
+                case "playlist-next":
+                    invoke<boolean>("playlist_step", { offset: 1 }).then(ok => ok || showOsd(t("playlist.end")));
+                    break;
+                case "playlist-previous":
+                    invoke<boolean>("playlist_step", { offset: -1 }).then(ok => ok || showOsd(t("playlist.start")));
+                    break;
+                case "new-window":
+                    invoke("open_window").catch(e => showOsd(t(e)));
+                    break;
+                case "bookmark":
+                    invoke("add_bookmark", { time: vidRef.current.currentTime });
+                    break;
+                case "export-markers":
+                    setFormatChoice({
+                        title: t("export.markers"),
+                        formats: ["ffmetadata", "youtube", "cue"],
+                        then: format => invoke<string>("export_markers", { format }).then(path => showOsd(t("saved", { path }))).catch(e => showOsd(t(e))),
+                    });
+                    break;
+                case "export-transcript":
+                    setFormatChoice({
+                        title: t("export.transcript"),
+                        formats: ["text", "markdown"],
+                        then: format => invoke<string>("export_transcript", { format }).then(path => showOsd(t("saved", { path }))).catch(e => showOsd(t(e))),
+                    });
+                    break;
+                case "ab-loop": {
+                    const { a, b } = abLoopRef.current;
+                    const now = vidRef.current.currentTime;
+                    if (a === null) {
+                        updateAbLoop({ a: now, b: null });
+                        showOsd(t("loop.a"));
+                    } else if (b === null && now > a) {
+                        updateAbLoop({ a, b: now });
+                        showOsd(t("loop.b"));
+                    } else {
+                        updateAbLoop({ a: null, b: null });
+                        showOsd(t("loop.cleared"));
+                    }
+                    break;
+                }
+                case "loop-mode": {
+                    const next = LOOP_MODES[(LOOP_MODES.indexOf(loopModeRef.current) + 1) % LOOP_MODES.length];
+                    loopModeRef.current = next;
+                    setLoopMode(next);
+                    invoke("update_settings", { loopMode: next }).catch(console.error);
+                    showOsd(t(`loop.${next}`));
+                    break;
+                }
+                case "screenshot":
+                    takeScreenshot();
+                    break;
+                case "copy-frame":
+                    copyFrame();
+                    break;
+                case "export-frames": {
+                    // the A-B loop doubles as the in and out points
+                    const { a, b } = abLoopRef.current;
+                    if (a === null || b === null) {
+                        showOsd(t("sequence.no-range"));
+                        break;
+                    }
+                    showOsd(t("sequence.exporting", { percent: 0 }));
+                    invoke<string>("export_sequence", { start: a, end: b })
+                        .then(path => showOsd(t("saved", { path })))
+                        .catch(e => showOsd(t(e)));
+                    break;
+                }
+                case "export-clip": {
+                    const { a, b } = abLoopRef.current;
+                    if (a === null || b === null) {
+                        showOsd(t("sequence.no-range"));
+                        break;
+                    }
+                    showOsd(t("clip.exporting", { percent: 0 }));
+                    invoke<string>("export_clip", { start: a, end: b })
+                        .then(path => showOsd(t("saved", { path })))
+                        .catch(e => showOsd(t(e)));
+                    break;
+                }
+                case "lock-file":
+                    askPin(pin => invoke<FileLock>("toggle_file_lock", { pin }).then(lock => {
+                        locksRef.current = lock;
+                        // whoever just locked it can keep watching
+                        unlockedRef.current = true;
+                        showOsd(t(lock.whole ? "lock.file-locked" : "lock.cleared"));
+                    }).catch(e => showOsd(t(e))));
+                    break;
+                case "lock-segment": {
+                    const { a, b } = abLoopRef.current;
+                    if (a === null || b === null) {
+                        showOsd(t("sequence.no-range"));
+                        break;
+                    }
+                    askPin(pin => invoke<FileLock>("lock_segment", { pin, start: a, end: b }).then(lock => {
+                        locksRef.current = lock;
+                        unlockedRef.current = true;
+                        showOsd(t("lock.segment-locked", { start: formatClock(a), end: formatClock(b) }));
+                    }).catch(e => showOsd(t(e))));
+                    break;
+                }
+                case "telemetry": {
+                    const video = vidRef.current;
+                    const info = videoInfoRef.current;
+                    hardwareDecoding(info?.codec ?? null, info?.width ?? 0, info?.height ?? 0, fpsRef.current)
+                        .then(hwAccel => invoke<string>("export_telemetry", { playback: frameStatsRef.current.summary(video, hwAccel) }))
+                        .then(path => showOsd(t("saved", { path })))
+                        .catch(e => showOsd(t(e)));
+                    break;
+                }
+                case "recent":
+                    invoke<RecentFile[]>("get_recent").then(setRecent).catch(console.error);
+                    setShowRecent(shown => !shown);
+                    break;
+                case "detect-motion":
+                    showOsd(t("motion.scanning", { percent: 0 }));
+                    invoke<number[]>("detect_motion").then(times => {
+                        const markers = times.map(start => ({ start, end: null, title: "" }));
+                        motionRef.current = markers;
+                        setMotion(markers);
+                        showOsd(t("motion.found", { count: times.length }));
+                    }).catch(e => showOsd(t(e)));
+                    break;
+                case "next-motion": {
+                    const next = nextChapter(motionRef.current, vidRef.current.currentTime);
+                    if (next !== undefined) {
+                        vidRef.current.currentTime = next;
+                    }
+                    break;
+                }
+                case "previous-motion": {
+                    const prev = previousChapter(motionRef.current, vidRef.current.currentTime);
+                    if (prev !== undefined) {
+                        vidRef.current.currentTime = prev;
+                    }
+                    break;
+                }
+                case "skip-intro":
+                    if (introRef.current) {
+                        vidRef.current.currentTime = introRef.current[1];
+                    }
+                    break;
+                case "annotate":
+                    if (shapesRef.current === null) {
+                        vidRef.current.pause();
+                        setPlaying(false);
+                        updateShapes([]);
+                        showOsd(t("annotate.on"));
+                    } else {
+                        updateShapes(null);
+                        showOsd(t("annotate.off"));
+                    }
+                    break;
+                case "annotation-tool":
+                    if (shapesRef.current !== null) {
+                        const next = TOOLS[(TOOLS.indexOf(toolRef.current) + 1) % TOOLS.length];
+                        toolRef.current = next;
+                        setTool(next);
+                        showOsd(t(`annotate.tool.${next}`));
+                    }
+                    break;
+                case "annotation-undo":
+                    if (shapesRef.current !== null) {
+                        updateShapes(shapesRef.current.slice(0, -1));
+                    }
+                    break;
+                case "rotate":
+                    setView(rotate);
+                    break;
+                case "ambient": {
+                    const on = ambientRef.current === null;
+                    ambientRef.current = on ? new AmbientSampler() : null;
+                    setAmbient(null);
+                    showOsd(t(on ? "ambient.on" : "ambient.off"));
+                    break;
+                }
+                case "aspect":
+                    setView(v => {
+                        const next = cycleAspect(v);
+                        showOsd(t("aspect", { mode: next.aspect }));
+                        return next;
+                    });
+                    break;
+                case "integer-scale":
+                    setView(v => {
+                        showOsd(t(v.integerScale ? "integer-scale.off" : "integer-scale.on"));
+                        return { ...v, integerScale: !v.integerScale };
+                    });
+                    break;
+                case "flip-horizontal":
+                    setView(v => ({ ...v, flipX: !v.flipX }));
+                    break;
+                case "flip-vertical":
+                    setView(v => ({ ...v, flipY: !v.flipY }));
+                    break;
+                case "contrast-down":
+                    adjustEqualizer("contrast", -5);
+                    break;
+                case "contrast-up":
+                    adjustEqualizer("contrast", 5);
+                    break;
+                case "brightness-down":
+                    adjustEqualizer("brightness", -5);
+                    break;
+                case "brightness-up":
+                    adjustEqualizer("brightness", 5);
+                    break;
+                case "saturation-down":
+                    adjustEqualizer("saturation", -5);
+                    break;
+                case "saturation-up":
+                    adjustEqualizer("saturation", 5);
+                    break;
+                case "speed-up":
+                case "speed-down": {
+                    const rate = stepSpeed(vidRef.current.playbackRate, action === "speed-up" ? 1 : -1);
+                    setSpeed(vidRef.current, rate);
+                    showOsd(t("speed", { rate }));
+                    break;
+                }
+                case "speed-reset":
+                    setSpeed(vidRef.current, 1);
+                    showOsd(t("speed", { rate: 1 }));
+                    break;
+                case "equalizer-reset":
+                    equalizerRef.current = DEFAULT_EQUALIZER;
+                    setEqualizer(DEFAULT_EQUALIZER);
+                    showOsd(t("eq.reset"));
+                    break;
+                case "copy-link":
+                    invoke<string>("share_link", { time: vidRef.current.currentTime })
+                        .then(link => navigator.clipboard.writeText(link))
+                        .then(() => showOsd(t("link.copied")))
+                        .catch(e => showOsd(t(e)));
+                    break;
+                case "deinterlace":
+                    invoke<boolean>("toggle_deinterlace").then(on => showOsd(t(on ? "deinterlace.on" : "deinterlace.off")));
+                    break;
+                case "interpolate":
+                    invoke<boolean>("toggle_interpolation").then(on => showOsd(t(on ? "interpolate.on" : "interpolate.off")));
+                    break;
+                case "power-saver":
+                    invoke<boolean>("toggle_power_saver").then(on => {
+                        setPowerSaver(on);
+                        showOsd(t(on ? "power-saver.on" : "power-saver.off"));
+                    });
+                    break;
+                case "subtitles":
+                    invoke<string>("cycle_subtitles").then(label => showOsd(t(label))).catch(e => showOsd(t(e)));
+                    break;
+                case "search-subtitles":
+                    setSearch(open => open === null ? "" : null);
+                    break;
+                case "second-subtitles":
+                    invoke<string>("cycle_subtitles", { secondary: true }).then(label => showOsd(t(label))).catch(e => showOsd(t(e)));
+                    break;
+                case "exit-fullscreen":
+                    getCurrentWindow().setFullscreen(false).then(() => setFullscreen(false));
+                    break;
             }
         }
     }
@@ -169,6 +1149,79 @@ export default function App() {
     useEffect(() => {
         (async () => setFileExists(await exists("./v.mp4")))();
 
+        invoke<string | null>("get_language").then(tag => {
+            if (tag) {
+                setLanguage(tag);
+                setLanguageTag(tag);
+            }
+        }).catch(console.error);
+
+        invoke<Record<string, string | string[]>>("get_keymap").then(keys => {
+            keymapRef.current = mergeKeymap(keys);
+        }).catch(console.error);
+
+        invoke<RecentFile[]>("get_recent").then(setRecent).catch(console.error);
+
+        invoke<SeekSteps>("get_seek_steps").then(steps => {
+            seekStepsRef.current = steps;
+        }).catch(console.error);
+
+        // decides whether wide-gamut video is mapped down when converting
+        invoke("set_display_gamut", { gamut: displayGamut() }).catch(console.error);
+
+        invoke<OsdTheme>("get_osd_theme").then(theme => {
+            applyOsdTheme(theme);
+            osdDurationRef.current = theme.duration;
+        }).catch(console.error);
+
+        invoke<Appearance>("get_appearance").then(a => {
+            document.documentElement.classList.toggle("high-contrast", a.high_contrast);
+            document.documentElement.classList.toggle("large-controls", a.large_controls);
+            document.documentElement.classList.toggle("pixelated", a.scaling === "nearest");
+            setView(v => ({ ...v, integerScale: a.integer_scale }));
+            ambientRef.current = a.ambient ? new AmbientSampler() : null;
+        }).catch(console.error);
+
+        const loadSubtitles = () => {
+            invoke<Cue[]>("get_subtitles").then(setCues).catch(console.error);
+            invoke<Cue[]>("get_subtitles", { secondary: true }).then(setTopCues).catch(console.error);
+        };
+        loadSubtitles();
+
+        invoke<VideoInfo>("get_video_info").then(info => {
+            videoInfoRef.current = info;
+            if (info.fps > 0) {
+                fpsRef.current = info.fps;
+            }
+        }).catch(console.error);
+
+        const loadPlaylist = () => invoke<PlaylistInfo>("get_playlist").then(setPlaylist).catch(console.error);
+        invoke<PlaylistInfo>("get_playlist").then(p => {
+            setPlaylist(p);
+            if (p.items.length > 1) {
+                showOsd(`${p.current + 1}/${p.items.length} ${p.items[p.current]}`);
+            }
+        }).catch(console.error);
+
+        invoke<Marker[]>("get_chapters").then(c => {
+            chaptersRef.current = c;
+            setChapters(c);
+        }).catch(console.error);
+
+        invoke<FileLock>("get_locks").then(lock => {
+            locksRef.current = lock;
+            if (lock.whole && vidRef.current) {
+                vidRef.current.pause();
+                setPlaying(false);
+                askUnlock();
+            }
+        }).catch(console.error);
+
+        invoke<[number, number] | null>("detect_intro").then(found => {
+            introRef.current = found;
+            setIntro(found);
+        }).catch(console.error);
+
         const unlisten1 = listen('refresh-mega', () => {
             //if (vidRef.current) {
             //    vidRef.current.src = convertFileSrc('v.mp4', 'stream');
@@ -180,38 +1233,418 @@ export default function App() {
             setLoading(e.payload);
         });
 
+        const unlisten3 = listen('subs-changed', loadSubtitles);
+        // the page reloads right after a conversion, so keep the report until then
+        const unlisten4 = listen<Verification>('c-verify', (e) => {
+            const report = e.payload.ok
+                ? t("convert.verified")
+                : t("convert.damaged", { problems: e.payload.problems.join("; ") });
+            keepOsd(report);
+        });
+
+        const unlisten5 = listen<string>('c-error', (e) => {
+            setLoading(0);
+            showOsd(t("convert.failed", { error: e.payload }), 8000);
+        });
+
+        const unlisten6 = listen('playlist-changed', loadPlaylist);
+
+        const unlisten7 = listen<number>('c-damaged', (e) => {
+            keepOsd(t("convert.skipped", { count: e.payload }));
+        });
+
+        const unlisten8 = listen<number>('source-grew', (e) => setGrowing(e.payload));
+        const unlisten9 = listen('source-done', () => {
+            setGrowing(null);
+            if (vidRef.current) {
+                reloadAt(vidRef.current, vidRef.current.currentTime);
+            }
+        });
+
+        invoke<boolean>("get_power_saver").then(setPowerSaver).catch(console.error);
+        invoke<Settings>("get_settings").then(s => {
+            if (vidRef.current) {
+                vidRef.current.volume = s.volume;
+                vidRef.current.muted = s.muted;
+            }
+            if (LOOP_MODES.includes(s.loop_mode as LoopMode)) {
+                loopModeRef.current = s.loop_mode as LoopMode;
+                setLoopMode(s.loop_mode as LoopMode);
+            }
+            // then the command line, which wins
+            return invoke<LaunchOptions>("get_launch_options");
+        }).then(launch => {
+            if (launch.speed !== null && vidRef.current) {
+                setSpeed(vidRef.current, launch.speed);
+            }
+            if (launch.loop_mode !== null) {
+                loopModeRef.current = launch.loop_mode;
+                setLoopMode(launch.loop_mode);
+            }
+        }).catch(console.error);
+        // --fullscreen, or still fullscreen from before the page reloaded
+        getCurrentWindow().isFullscreen().then(setFullscreen).catch(console.error);
+        const unlisten16 = listen<number>('sequence-progress', (e) => {
+            showOsd(t("sequence.exporting", { percent: Math.floor(e.payload * 100) }));
+        });
+        const unlisten17 = listen<number>('clip-progress', (e) => {
+            showOsd(t("clip.exporting", { percent: Math.floor(e.payload * 100) }));
+        });
+        const unlisten15 = listen<Action>('tray-action', (e) => runAction(e.payload));
+        const gamepad = new GamepadInput(runAction, connected => {
+            showOsd(t(connected ? "gamepad.connected" : "gamepad.disconnected"));
+        });
+        const touch = boxRef.current && new TouchGestures(boxRef.current, runAction);
+        const unlisten14 = listen<boolean>('power-saver', (e) => {
+            setPowerSaver(e.payload);
+            showOsd(t(e.payload ? "power-saver.on" : "power-saver.off"));
+        });
+        const unlisten13 = listen<number>('motion-progress', (e) => {
+            showOsd(t("motion.scanning", { percent: Math.floor(e.payload * 100) }));
+        });
+
+        // requests from the scripts the backend runs
+        const unlisten10 = listen<number>('script-seek', (e) => {
+            if (vidRef.current) {
+                vidRef.current.currentTime = e.payload;
+            }
+        });
+        const unlisten11 = listen('script-screenshot', takeScreenshot);
+        const unlisten12 = listen<[string, number]>('osd-text', (e) => showOsd(e.payload[0], e.payload[1] * 1000));
+
+        const pending = sessionStorage.getItem("osd");
+        if (pending) {
+            sessionStorage.removeItem("osd");
+            showOsd(pending, 6000);
+        }
+
+        const paste = (ev: ClipboardEvent) => {
+            const text = ev.clipboardData?.getData("text");
+            if (text) {
+                invoke("open_media", { target: text }).catch(e => showOsd(t(e)));
+            }
+        };
+
         window.addEventListener("keydown", keyDown);
         window.addEventListener("keyup", keyUp);
+        window.addEventListener("paste", paste);
 
         return () => {
             unlisten1.then(u => u());
             unlisten2.then(u => u());
+            unlisten3.then(u => u());
+            unlisten4.then(u => u());
+            unlisten5.then(u => u());
+            unlisten6.then(u => u());
+            unlisten7.then(u => u());
+            unlisten8.then(u => u());
+            unlisten9.then(u => u());
+            unlisten10.then(u => u());
+            unlisten11.then(u => u());
+            unlisten12.then(u => u());
+            unlisten13.then(u => u());
+            unlisten14.then(u => u());
+            unlisten15.then(u => u());
+            unlisten16.then(u => u());
+            unlisten17.then(u => u());
+            gamepad.dispose();
+            touch?.dispose();
             window.removeEventListener("keydown", keyDown);
             window.removeEventListener("keyup", keyUp);
+            window.removeEventListener("paste", paste);
         };
     }, []);
 
+    useEffect(() => {
+        const box = boxRef.current;
+        if (!box) return;
+
+        const observer = new ResizeObserver(() => setBoxSize({ width: box.clientWidth, height: box.clientHeight }));
+        observer.observe(box);
+        return () => observer.disconnect();
+    }, []);
+
+    // over the video the wheel changes the volume, or zooms with Ctrl held
+    const handleWheel = (ev: WheelEvent<HTMLDivElement>) => {
+        if (ev.ctrlKey) {
+            const rect = ev.currentTarget.getBoundingClientRect();
+            const x = (ev.clientX - rect.left) / rect.width - 0.5;
+            const y = (ev.clientY - rect.top) / rect.height - 0.5;
+            setView(v => zoomAt(v, ev.deltaY < 0 ? 1.1 : 1 / 1.1, x, y));
+        } else if (vidRef.current) {
+            const video = vidRef.current;
+            video.volume = Math.min(Math.max(video.volume + (ev.deltaY < 0 ? 0.05 : -0.05), 0), 1);
+            showOsd(t("volume", { percent: Math.round(video.volume * 100) }));
+        }
+    };
+
+    // over the seek bar it nudges the position
+    const handleTimelineWheel = (ev: WheelEvent<HTMLDivElement>) => {
+        if (vidRef.current && !loading) {
+            const step = seekStepsRef.current.short;
+            duckerRef.current.nudge(vidRef.current);
+            const target = seekBy(vidRef.current, ev.deltaY < 0 ? step : -step);
+            showOsd(formatClock(target, vidRef.current.duration >= 3600));
+        }
+    };
+
+    const handlePan = (ev: MouseEvent<HTMLDivElement>) => {
+        if ((ev.buttons & 1) && view.zoom > 1) {
+            const rect = ev.currentTarget.getBoundingClientRect();
+            setView(v => panBy(v, ev.movementX / rect.width, ev.movementY / rect.height));
+        }
+    };
+
     const handleSeek = async (val: number) => {
         if (vidRef.current && !loading) {
-            const upProg = vidRef.current.duration * val;
-            vidRef.current.currentTime = upProg;
+            const upProg = (growing ?? vidRef.current.duration) * val;
+            duckerRef.current.duck(vidRef.current);
+            // past what the player knows about, so pick up the new data first
+            if (growing !== null && upProg > vidRef.current.duration) {
+                reloadAt(vidRef.current, upProg);
+            } else {
+                scrubRef.current.seek(vidRef.current, upProg);
+            }
             setProgress(upProg);
         }
     };
 
+    // every few seconds is plenty, and keeps the positions file from being rewritten constantly
+    const savePosition = (video: HTMLVideoElement) => {
+        if (Math.abs(video.currentTime - savedPositionRef.current) < 5) {
+            return;
+        }
+        savedPositionRef.current = video.currentTime;
+        const done = video.duration - video.currentTime < 10;
+        invoke("save_position", { time: done ? null : video.currentTime });
+    };
+
+    const handleEnded = () => {
+        if (growing !== null && vidRef.current) {
+            // caught up with the download; cleared once it plays again
+            setBuffering(true);
+            reloadAt(vidRef.current, vidRef.current.currentTime);
+        } else {
+            const finished = invoke("playback_finished");
+            if (loopMode === "stop") {
+                setPlaying(false);
+                return;
+            }
+            // wrap around to the first item after the last one
+            finished
+                .then(() => invoke<boolean>("playlist_step", { offset: 1 }))
+                .then(ok => ok || invoke("playlist_step", { offset: 1 - playlist.items.length }));
+        }
+    };
+
+    const duration = vidRef.current ? growing ?? vidRef.current.duration : 0;
+
+    // "name — playing — 0:42/10:00", so taskbar entries tell files apart
+    const fileName = fileExists ? playlist.items[playlist.current] : undefined;
+    const second = Math.floor(progress);
+    useEffect(() => {
+        if (!fileName) {
+            return;
+        }
+        const state = t(playing ? "title.playing" : "title.paused");
+        const clock = `${formatClock(second, duration >= 3600)}/${formatClock(duration)}`;
+        getCurrentWindow().setTitle(`${fileName} — ${state} — ${clock}`).catch(console.error);
+    }, [fileName, playing, second, duration]);
+
+    const recentList = recent.length > 0 && (
+        <div className="recent">
+            <span>{t("recent")}</span>
+            {recent.map(file => (
+                <div key={file.path} title={file.path} onClick={() => invoke("open_recent", { path: file.path })}>
+                    {file.name}
+                </div>
+            ))}
+        </div>
+    );
+
+    const jumpToCue = (cue: Cue) => {
+        if (vidRef.current) {
+            vidRef.current.currentTime = cue.start;
+        }
+        setSearch(null);
+    };
+
+    const matches = search === null ? [] : searchCues(searchIndex, search).slice(0, MAX_SEARCH_RESULTS);
+    const searchBox = search !== null && (
+        <div className="recent search">
+            <input
+                autoFocus
+                value={search}
+                placeholder={t("search.placeholder")}
+                onChange={ev => setSearch(ev.target.value)}
+                onKeyDown={ev => {
+                    if (ev.key === "Escape") {
+                        setSearch(null);
+                    } else if (ev.key === "Enter" && matches.length > 0) {
+                        jumpToCue(matches[0]);
+                    }
+                }}
+            />
+            {search.trim() && matches.length === 0 && <span>{t("search.none")}</span>}
+            {matches.map((cue, i) => (
+                <div key={i} onClick={() => jumpToCue(cue)}>
+                    <span>{formatClock(cue.start)}</span> {cue.text}
+                </div>
+            ))}
+        </div>
+    );
+
+    const pinBox = pinPrompt !== null && (
+        <div className="recent search">
+            <input
+                autoFocus
+                type="password"
+                inputMode="numeric"
+                value={pinPrompt.value}
+                placeholder={t("lock.pin")}
+                onChange={ev => setPinPrompt({ ...pinPrompt, value: ev.target.value })}
+                onKeyDown={ev => {
+                    if (ev.key === "Escape") {
+                        setPinPrompt(null);
+                    } else if (ev.key === "Enter") {
+                        pinPrompt.then(pinPrompt.value);
+                        setPinPrompt(null);
+                    }
+                }}
+            />
+        </div>
+    );
+
+    const formatMenu = formatChoice !== null && (
+        <div
+            className="recent"
+            tabIndex={-1}
+            ref={el => el?.focus()}
+            onKeyDown={ev => ev.key === "Escape" && setFormatChoice(null)}
+            onBlur={() => setFormatChoice(null)}
+        >
+            <span>{formatChoice.title}</span>
+            {formatChoice.formats.map(format => (
+                <div key={format} onMouseDown={() => {
+                    formatChoice.then(format);
+                    setFormatChoice(null);
+                }}>
+                    {t(`format.${format}`)}
+                </div>
+            ))}
+        </div>
+    );
+
     return (
         <>
-            <div className="vid">
+            <div
+                ref={boxRef}
+                className={fullscreen && !controlsVisible ? "vid nocursor" : view.zoom > 1 ? "vid zoomed" : "vid"}
+                style={ambient ? { background: ambientBackground(ambient) } : undefined}
+                onDoubleClick={fullscreenCallback}
+                onWheel={handleWheel}
+                onMouseMove={handlePan}
+                onMouseDown={ev => {
+                    // there is no title bar to grab in picture-in-picture
+                    if (pip && ev.button === 0 && view.zoom <= 1) {
+                        getCurrentWindow().startDragging();
+                    }
+                }}
+            >
                 {fileExists ? (
-                    <video loop ref={vidRef} itemType='video/mp4' />
+                    <video
+                        loop={growing === null && (loopMode === "file" || (loopMode === "playlist" && playlist.items.length <= 1))}
+                        ref={vidRef}
+                        itemType='video/mp4'
+                        style={{ transform: videoTransform(view, boxSize, videoSize), filter: powerSaver ? "none" : equalizerFilter(equalizer) }}
+                        crossOrigin="anonymous"
+                        onLoadedMetadata={ev => {
+                            setVideoSize({
+                                width: ev.currentTarget.videoWidth,
+                                height: ev.currentTarget.videoHeight,
+                            });
+                            setAudioOnly(ev.currentTarget.videoWidth === 0);
+                        }}
+                        onVolumeChange={ev => {
+                            // ducking while seeking isn't the user's volume
+                            if (duckerRef.current.active) {
+                                return;
+                            }
+                            invoke("update_settings", {
+                                volume: ev.currentTarget.volume,
+                                muted: ev.currentTarget.muted,
+                            }).catch(console.error);
+                        }}
+                        onTimeUpdate={ev => {
+                            invoke("script_time", { time: ev.currentTarget.currentTime });
+                            savePosition(ev.currentTarget);
+                        }}
+                        onEnded={handleEnded}
+                        onPlay={() => wakeLockRef.current?.set(true)}
+                        onPause={() => wakeLockRef.current?.set(false)}
+                        onWaiting={() => setBuffering(true)}
+                        onPlaying={() => setBuffering(false)}
+                        onCanPlay={() => setBuffering(false)}
+                    />
                 ) : loading ? (
                     <p>{(loading * 100).toPrecision(4)}%</p>
                 ) : (
-                    <p>Drop video file here</p>
+                    <div className="empty">
+                        <p>{t("drop-here")}</p>
+                        {recentList}
+                    </div>
+                )}
+                {fileExists && audioOnly && cover && <img className="cover" src={cover} />}
+                {fileExists && audioOnly && <canvas className="visualizer" ref={canvasRef} />}
+                {fileExists && ringReady && (
+                    <canvas
+                        className="loopring"
+                        ref={ringCanvasRef}
+                        style={{ transform: videoTransform(view, boxSize, videoSize), filter: powerSaver ? "none" : equalizerFilter(equalizer) }}
+                    />
+                )}
+                {fileExists && showRecent && recentList}
+                {fileExists && searchBox}
+                {fileExists && pinBox}
+                {fileExists && formatMenu}
+                {fileExists && shapes !== null && videoSize.width > 0 && (
+                    <AnnotationLayer
+                        shapes={shapes}
+                        tool={tool}
+                        video={videoSize}
+                        transform={videoTransform(view, boxSize, videoSize)}
+                        onAdd={shape => updateShapes([...shapes, shape])}
+                    />
+                )}
+                {osd && <div className="osd">{osd}</div>}
+                {stats && <div className="stats">{stats.join("\n")}</div>}
+                {fileExists && buffering && <div className="buffering">{t("buffering")}</div>}
+                {intro && progress >= intro[0] && progress < intro[1] - 1 && (
+                    <button className="skipintro" onClick={() => {
+                        if (vidRef.current) {
+                            vidRef.current.currentTime = intro[1];
+                        }
+                    }}>
+                        {t("intro.skip")}
+                    </button>
+                )}
+                {fileExists && (
+                    <div className="subs">
+                        {activeCues(cues, progress).map((cue, i) => cue.image
+                            ? <img key={i} src={cue.image.src} style={{ width: `${cue.image.width * 100}%` }} />
+                            : <span key={i}>{cue.text}</span>)}
+                    </div>
+                )}
+                {fileExists && (
+                    <div className="subs top">
+                        {activeCues(topCues, progress).map((cue, i) => cue.image
+                            ? <img key={i} src={cue.image.src} style={{ width: `${cue.image.width * 100}%` }} />
+                            : <span key={i}>{cue.text}</span>)}
+                    </div>
                 )}
             </div>
 
-            <div className="options">
+            <div className={fullscreen && !controlsVisible ? "options autohide" : "options"}>
                 <div className="playpause" onClick={() => {
                     playCallback();
                 }}>
@@ -221,7 +1654,33 @@ export default function App() {
                         <FaPause className="playpause" size="100%" />
                     )}
                 </div>
-                <ProgressBar progress={vidRef.current ? progress / vidRef.current.duration : loading} onChange={handleSeek} />
+                <ProgressBar
+                    progress={vidRef.current ? progress / duration : loading}
+                    duration={fileExists ? duration : 0}
+                    marks={[...chapters, ...motion].sort((a, b) => a.start - b.start)}
+                    loop={duration && abLoop.a !== null
+                        ? [abLoop.a / duration, (abLoop.b ?? abLoop.a) / duration]
+                        : null}
+                    locked={time => !unlockedRef.current && lockedAt(locksRef.current, time) !== undefined}
+                    onChange={handleSeek}
+                    onRelease={() => {
+                        if (vidRef.current) {
+                            scrubRef.current.settle(vidRef.current);
+                            duckerRef.current.release(vidRef.current);
+                        }
+                    }}
+                    onWheel={handleTimelineWheel}
+                />
+                {fileExists && duration > 0 && (
+                    <div className="time">
+                        {formatClock(progress, duration >= 3600)} / {formatClock(duration)}
+                    </div>
+                )}
+                {fileExists && (
+                    <div className="capture" title={t("frame.copy")} onClick={copyFrame}>
+                        <FaCamera size="100%" />
+                    </div>
+                )}
             </div>
         </>
     );
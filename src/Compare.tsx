@@ -0,0 +1,71 @@
+import { useEffect, useState } from "react";
+import "./App.css";
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import { setLanguage, t } from "./i18n";
+
+/**
+ * An extra window for comparing files side by side; see `compare` in the
+ * backend. It plays `v-<n>.mp4` with its own clock and the webview's own
+ * controls, and takes other files by drag and drop.
+ */
+function Compare(props: { n: number }) {
+    const [name, setName] = useState<string | null>(null);
+    const [loading, setLoading] = useState(0);
+    const [error, setError] = useState<string | null>(null);
+    // changes with every file, so the video element loads the new one
+    const [version, setVersion] = useState(0);
+
+    useEffect(() => {
+        const win = getCurrentWebviewWindow();
+        const ready = (file: string) => {
+            setName(file);
+            setLoading(0);
+            setError(null);
+            setVersion(Date.now());
+        };
+
+        invoke<string | null>("get_language").then(tag => tag && setLanguage(tag)).catch(console.error);
+        // the file may have been ready before this page was
+        invoke<string | null>("window_ready").then(file => file && ready(file)).catch(console.error);
+
+        const unlisten1 = win.listen<number>("window-progress", e => {
+            setName(null);
+            setError(null);
+            setLoading(e.payload);
+        });
+        const unlisten2 = win.listen<string>("window-ready", e => ready(e.payload));
+        const unlisten3 = win.listen<string>("window-error", e => {
+            setLoading(0);
+            setError(t(e.payload));
+        });
+
+        return () => {
+            unlisten1.then(f => f());
+            unlisten2.then(f => f());
+            unlisten3.then(f => f());
+        };
+    }, []);
+
+    return (
+        <div className="vid">
+            {name !== null ? (
+                <video
+                    key={version}
+                    src={convertFileSrc(`v-${props.n}.mp4`, "stream") + `?t=${version}`}
+                    controls
+                    autoPlay
+                />
+            ) : loading ? (
+                <p>{(loading * 100).toPrecision(4)}%</p>
+            ) : (
+                <div className="empty">
+                    <p>{t("window.drop")}</p>
+                </div>
+            )}
+            {error && <div className="osd">{t("convert.failed", { error })}</div>}
+        </div>
+    );
+}
+
+export default Compare;
@@ -0,0 +1,92 @@
+/** The frame is shrunk to this before its edges are averaged. */
+const SAMPLE_WIDTH = 32;
+const SAMPLE_HEIGHT = 18;
+/** Edges are sampled at most this often, in ms. */
+const INTERVAL = 200;
+/** Weight of a new sample against the running colour; lower is calmer. */
+const SMOOTHING = 0.3;
+
+type Rgb = [number, number, number];
+
+/** Average colour along each edge of the picture. */
+export type EdgeColors = { top: Rgb; bottom: Rgb; left: Rgb; right: Rgb };
+
+/**
+ * Follows the colours at the edges of the playing video, for filling the
+ * bars around it the way ambient modes do. Frames are scaled down on a
+ * small canvas first, so a sample costs next to nothing.
+ */
+export class AmbientSampler {
+    private canvas = document.createElement("canvas");
+    private last = 0;
+    private colors: EdgeColors | null = null;
+
+    constructor() {
+        this.canvas.width = SAMPLE_WIDTH;
+        this.canvas.height = SAMPLE_HEIGHT;
+    }
+
+    /** New colours for the current frame, or null when it isn't time yet. */
+    sample(video: HTMLVideoElement): EdgeColors | null {
+        const now = performance.now();
+        const ctx = this.canvas.getContext("2d", { willReadFrequently: true });
+        if (now - this.last < INTERVAL || !ctx || !video.videoWidth) {
+            return null;
+        }
+        this.last = now;
+
+        ctx.drawImage(video, 0, 0, SAMPLE_WIDTH, SAMPLE_HEIGHT);
+        let pixels: Uint8ClampedArray;
+        try {
+            pixels = ctx.getImageData(0, 0, SAMPLE_WIDTH, SAMPLE_HEIGHT).data;
+        } catch {
+            // a tainted canvas; nothing to read
+            return null;
+        }
+
+        const average = (xs: number[], ys: number[]): Rgb => {
+            const sum: Rgb = [0, 0, 0];
+            for (const y of ys) {
+                for (const x of xs) {
+                    const i = (y * SAMPLE_WIDTH + x) * 4;
+                    sum[0] += pixels[i];
+                    sum[1] += pixels[i + 1];
+                    sum[2] += pixels[i + 2];
+                }
+            }
+            const n = xs.length * ys.length;
+            return [sum[0] / n, sum[1] / n, sum[2] / n];
+        };
+        const range = (from: number, to: number) => Array.from({ length: to - from }, (_, i) => from + i);
+        const columns = range(0, SAMPLE_WIDTH);
+        const rows = range(0, SAMPLE_HEIGHT);
+        const fresh: EdgeColors = {
+            top: average(columns, [0, 1]),
+            bottom: average(columns, [SAMPLE_HEIGHT - 2, SAMPLE_HEIGHT - 1]),
+            left: average([0, 1], rows),
+            right: average([SAMPLE_WIDTH - 2, SAMPLE_WIDTH - 1], rows),
+        };
+
+        const previous = this.colors;
+        const mix = (a: Rgb, b: Rgb): Rgb => [0, 1, 2].map(i => a[i] + (b[i] - a[i]) * SMOOTHING) as Rgb;
+        this.colors = previous
+            ? {
+                top: mix(previous.top, fresh.top),
+                bottom: mix(previous.bottom, fresh.bottom),
+                left: mix(previous.left, fresh.left),
+                right: mix(previous.right, fresh.right),
+            }
+            : fresh;
+        return this.colors;
+    }
+}
+
+const css = ([r, g, b]: Rgb) => `rgb(${Math.round(r)} ${Math.round(g)} ${Math.round(b)})`;
+
+/** CSS background fading each edge colour out towards the middle. */
+export function ambientBackground(colors: EdgeColors): string {
+    return [
+        `linear-gradient(to bottom, ${css(colors.top)}, transparent 50%, ${css(colors.bottom)})`,
+        `linear-gradient(to right, ${css(colors.left)}, ${css(colors.right)})`,
+    ].join(", ");
+}
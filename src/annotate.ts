@@ -0,0 +1,48 @@
+/** A point in video pixels. */
+export type Point = [number, number];
+
+/** Mirrors `annotate::Shape` in the backend, which draws these on screenshots. */
+export type Shape =
+    | { kind: "rect"; from: Point; to: Point }
+    | { kind: "arrow"; from: Point; to: Point }
+    | { kind: "freehand"; points: Point[] };
+
+export type Tool = Shape["kind"];
+
+export const TOOLS: Tool[] = ["rect", "arrow", "freehand"];
+
+/** Line width in video pixels, the same as the backend draws with. */
+export const THICKNESS = 4;
+/** Arrow head side length, relative to `THICKNESS`. */
+const HEAD = 5;
+
+export function startShape(tool: Tool, at: Point): Shape {
+    return tool === "freehand" ? { kind: tool, points: [at] } : { kind: tool, from: at, to: at };
+}
+
+/** The shape being drawn after the pointer moved to `to`. */
+export function extendShape(shape: Shape, to: Point): Shape {
+    return shape.kind === "freehand"
+        ? { ...shape, points: [...shape.points, to] }
+        : { ...shape, to };
+}
+
+/** SVG path data for `shape`. */
+export function shapePath(shape: Shape): string {
+    const line = (points: Point[]) => points.map(([x, y], i) => `${i ? "L" : "M"}${x} ${y}`).join(" ");
+    switch (shape.kind) {
+        case "rect": {
+            const [[x0, y0], [x1, y1]] = [shape.from, shape.to];
+            return line([[x0, y0], [x1, y0], [x1, y1], [x0, y1]]) + " Z";
+        }
+        case "arrow": {
+            const [fx, fy] = shape.from;
+            const [tx, ty] = shape.to;
+            const angle = Math.atan2(fy - ty, fx - tx);
+            const side = (a: number): Point => [tx + Math.cos(a) * THICKNESS * HEAD, ty + Math.sin(a) * THICKNESS * HEAD];
+            return `${line([shape.from, shape.to])} ${line([side(angle - 0.5), shape.to, side(angle + 0.5)])}`;
+        }
+        case "freehand":
+            return line(shape.points);
+    }
+}
@@ -0,0 +1,28 @@
+export type Marker = {
+    start: number;
+    end: number | null;
+    title: string;
+};
+
+/**
+ * The chapter `time` falls in: the last one starting at or before it, unless
+ * that one has already ended. `chapters` must be sorted by start.
+ */
+export function chapterAt(chapters: Marker[], time: number): Marker | undefined {
+    const current = chapters.filter(c => c.start <= time).pop();
+    return current && (current.end === null || time < current.end) ? current : undefined;
+}
+
+/** Start of the first chapter after `time`, if any. */
+export function nextChapter(chapters: Marker[], time: number): number | undefined {
+    return chapters.find(c => c.start > time + 0.5)?.start;
+}
+
+/**
+ * Start of the current chapter, or of the previous one when we are already
+ * within a second of the current chapter's start.
+ */
+export function previousChapter(chapters: Marker[], time: number): number | undefined {
+    const before = chapters.filter(c => c.start < time - 1);
+    return before.length > 0 ? before[before.length - 1].start : undefined;
+}
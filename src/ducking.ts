@@ -0,0 +1,93 @@
+/** Seeks closer together than this, in ms, count as one scrub. */
+const SETTLE_MS = 150;
+/** How long the volume takes to come back once the scrub is over, in ms. */
+const FADE_MS = 250;
+
+/**
+ * Silences the audio while the user scrubs. Rapid seeks otherwise play a
+ * stutter of audio fragments from each position. When the scrub ends, the
+ * audio fades back in from wherever it landed.
+ */
+export class SeekDucker {
+    /** The user's volume, while ours is in effect. */
+    private saved: number | null = null;
+    private timer: number | null = null;
+    private frame: number | null = null;
+    /** Bumped on every duck so a stale fade-in never starts. */
+    private generation = 0;
+
+    /** Whether the video's volume is currently set by us, not the user. */
+    get active(): boolean {
+        return this.saved !== null;
+    }
+
+    /** Call on every seek of a scrub. */
+    duck(video: HTMLVideoElement) {
+        // nothing audible to garble
+        if (this.saved === null && (video.paused || video.muted || video.volume === 0)) {
+            return;
+        }
+        this.generation++;
+        this.cancel();
+        if (this.saved === null) {
+            this.saved = video.volume;
+        }
+        video.volume = 0;
+    }
+
+    /** Call once the scrub is over, e.g. when the seek bar is let go. */
+    release(video: HTMLVideoElement) {
+        if (this.saved === null) {
+            return;
+        }
+        const generation = this.generation;
+        if (this.timer !== null) {
+            clearTimeout(this.timer);
+        }
+        this.timer = window.setTimeout(() => {
+            this.timer = null;
+            // wait for the last seek to land so the fade starts in the right place
+            if (video.seeking) {
+                video.addEventListener("seeked", () => this.fadeIn(video, generation), { once: true });
+            } else {
+                this.fadeIn(video, generation);
+            }
+        }, SETTLE_MS);
+    }
+
+    /** Shorthand for a single seek, like a key press, that ends the scrub by itself. */
+    nudge(video: HTMLVideoElement) {
+        this.duck(video);
+        this.release(video);
+    }
+
+    private fadeIn(video: HTMLVideoElement, generation: number) {
+        const target = this.saved;
+        if (target === null || generation !== this.generation) {
+            return;
+        }
+        const start = performance.now();
+        const step = (now: number) => {
+            const t = Math.min((now - start) / FADE_MS, 1);
+            video.volume = target * t;
+            if (t < 1) {
+                this.frame = requestAnimationFrame(step);
+            } else {
+                this.frame = null;
+                this.saved = null;
+            }
+        };
+        this.frame = requestAnimationFrame(step);
+    }
+
+    private cancel() {
+        if (this.timer !== null) {
+            clearTimeout(this.timer);
+            this.timer = null;
+        }
+        if (this.frame !== null) {
+            cancelAnimationFrame(this.frame);
+            this.frame = null;
+        }
+    }
+}
@@ -0,0 +1,24 @@
+/** Adjustments in percent, 0 meaning untouched; each ranges from -100 to 100. */
+export type Equalizer = {
+    brightness: number;
+    contrast: number;
+    saturation: number;
+};
+
+export const DEFAULT_EQUALIZER: Equalizer = { brightness: 0, contrast: 0, saturation: 0 };
+
+export function adjust(eq: Equalizer, key: keyof Equalizer, delta: number): Equalizer {
+    return { ...eq, [key]: Math.min(Math.max(eq[key] + delta, -100), 100) };
+}
+
+/** Applied by the compositor, so nothing has to be re-encoded. */
+export function equalizerFilter(eq: Equalizer): string {
+    return `brightness(${1 + eq.brightness / 100}) `
+        + `contrast(${1 + eq.contrast / 100}) `
+        + `saturate(${1 + eq.saturation / 100})`;
+}
+
+/** The value of `key` with its sign, e.g. `+15`. */
+export function describe(eq: Equalizer, key: keyof Equalizer): string {
+    return `${eq[key] > 0 ? "+" : ""}${eq[key]}`;
+}
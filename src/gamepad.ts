@@ -0,0 +1,101 @@
+import { Action } from "./keymap";
+
+/** Buttons of the standard gamepad layout, by index, and what they do. */
+const BUTTONS: Partial<Record<number, Action>> = {
+    0: "play-pause", // A, cross
+    1: "exit-fullscreen", // B, circle
+    3: "fullscreen", // Y, triangle
+    4: "seek-back", // left bumper
+    5: "seek-forward", // right bumper
+    6: "jump-back", // left trigger
+    7: "jump-forward", // right trigger
+    9: "play-pause", // start
+    12: "volume-up",
+    13: "volume-down",
+    14: "step-back",
+    15: "step-forward",
+};
+/** Actions that keep going while their button is held. */
+const REPEATING = new Set<Action>(["seek-back", "seek-forward", "volume-up", "volume-down", "step-back", "step-forward"]);
+/** A held button repeats after this long, then this often, in ms. */
+const REPEAT_DELAY = 400;
+const REPEAT_INTERVAL = 100;
+
+/**
+ * Turns game controller buttons into player actions, for watching from the
+ * couch. Controllers can come and go at any time; they are only polled
+ * while at least one is connected.
+ */
+export class GamepadInput {
+    private frame: number | null = null;
+    /** When each held button, as `pad:button`, fires next. */
+    private held = new Map<string, number>();
+
+    constructor(
+        private onAction: (action: Action) => void,
+        private onConnection: (connected: boolean) => void,
+    ) {
+        window.addEventListener("gamepadconnected", this.connected);
+        window.addEventListener("gamepaddisconnected", this.disconnected);
+        // plugged in before the page loaded, which reloads with every file
+        if (navigator.getGamepads().some(pad => pad)) {
+            this.start();
+        }
+    }
+
+    dispose() {
+        window.removeEventListener("gamepadconnected", this.connected);
+        window.removeEventListener("gamepaddisconnected", this.disconnected);
+        if (this.frame !== null) {
+            cancelAnimationFrame(this.frame);
+            this.frame = null;
+        }
+    }
+
+    private connected = () => {
+        this.onConnection(true);
+        this.start();
+    };
+
+    private disconnected = (ev: GamepadEvent) => {
+        this.onConnection(false);
+        for (const key of this.held.keys()) {
+            if (key.startsWith(`${ev.gamepad.index}:`)) {
+                this.held.delete(key);
+            }
+        }
+    };
+
+    private start() {
+        if (this.frame === null) {
+            this.frame = requestAnimationFrame(this.poll);
+        }
+    }
+
+    private poll = (now: number) => {
+        const pads = navigator.getGamepads();
+        for (const pad of pads) {
+            // other layouts number their buttons however they like
+            if (!pad || pad.mapping !== "standard") {
+                continue;
+            }
+            pad.buttons.forEach((button, index) => {
+                const action = BUTTONS[index];
+                const key = `${pad.index}:${index}`;
+                if (!action || !button.pressed) {
+                    this.held.delete(key);
+                    return;
+                }
+                const next = this.held.get(key);
+                if (next === undefined) {
+                    this.onAction(action);
+                    this.held.set(key, now + REPEAT_DELAY);
+                } else if (REPEATING.has(action) && now >= next) {
+                    this.onAction(action);
+                    this.held.set(key, now + REPEAT_INTERVAL);
+                }
+            });
+        }
+        this.frame = pads.some(pad => pad) ? requestAnimationFrame(this.poll) : null;
+    };
+}
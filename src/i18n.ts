@@ -0,0 +1,210 @@
+type Strings = Record<string, string>;
+
+const en: Strings = {
+    "drop-here": "Drop video file here",
+    "title.playing": "playing",
+    "title.paused": "paused",
+    "audio.none": "No other audio tracks",
+    "audio.track": "Audio {n}/{count} {name}",
+    "playlist.end": "End of playlist",
+    "playlist.start": "Start of playlist",
+    "recent": "Recent files",
+    "window.drop": "Drop a file here to compare",
+    "window.busy": "Still converting the previous file",
+    "window.no-urls": "Only local files can be compared",
+    "window.locked": "This file is locked",
+    "intro.skip": "Skip intro",
+    "motion.scanning": "Looking for motion… {percent}%",
+    "motion.found": "{count} motion events, Shift+PgDn/PgUp to jump",
+    "saved": "Saved {path}",
+    "export.markers": "Export chapters as",
+    "format.ffmetadata": "FFmpeg metadata",
+    "format.youtube": "YouTube description",
+    "format.cue": "Cue sheet",
+    "export.transcript": "Export transcript as",
+    "format.text": "Plain text",
+    "format.markdown": "Markdown",
+    "frame.copy": "Copy frame",
+    "frame.copied": "Frame copied",
+    "resume.offer": "Press Enter to resume at {time}",
+    "session.offer": "Press Enter to restore the last session ({name}, {count} files)",
+    "session.none": "No previous session to restore",
+    "loop.a": "Loop A set",
+    "loop.b": "Loop B set",
+    "loop.cleared": "Loop cleared",
+    "loop.stop": "Stop at end",
+    "loop.file": "Loop file",
+    "loop.playlist": "Loop playlist",
+    "sequence.no-range": "Set loop A and B with L to mark the frames first",
+    "sequence.exporting": "Exporting frames… {percent}%",
+    "sequence.empty": "No frames between A and B",
+    "clip.exporting": "Rendering clip… {percent}%",
+    "clip.empty": "No frames between A and B",
+    "aspect": "Aspect: {mode}",
+    "integer-scale.on": "Integer scaling on",
+    "integer-scale.off": "Integer scaling off",
+    "volume": "Volume: {percent}%",
+    "lock.pin": "PIN",
+    "lock.locked": "Locked, enter the PIN to keep watching",
+    "lock.unlocked": "Unlocked",
+    "lock.wrong-pin": "Wrong PIN",
+    "lock.short-pin": "The PIN needs at least 4 characters",
+    "lock.no-file": "Nothing open to lock",
+    "lock.file-locked": "File locked",
+    "lock.segment-locked": "Locked {start}–{end}",
+    "lock.cleared": "Locks removed",
+    "gamepad.connected": "Controller connected",
+    "gamepad.disconnected": "Controller disconnected",
+    "stats.video": "{width}×{height} at {fps} fps",
+    "stats.frames": "{dropped} of {total} frames dropped",
+    "stats.late": "Decoding can't keep up, {dropped} frames dropped so far",
+    "stats.awake": "Keeping the screen awake",
+    "stats.sleep": "Screen may sleep",
+    "eq.brightness": "Brightness: {value}",
+    "eq.contrast": "Contrast: {value}",
+    "eq.saturation": "Saturation: {value}",
+    "eq.reset": "Equalizer reset",
+    "link.copied": "Link copied",
+    "annotate.on": "Drawing mode: drag to draw, T changes the tool, S saves",
+    "annotate.off": "Drawing mode off",
+    "annotate.tool.rect": "Rectangle",
+    "annotate.tool.arrow": "Arrow",
+    "annotate.tool.freehand": "Freehand",
+    "ambient.on": "Ambient colours on",
+    "ambient.off": "Ambient colours off",
+    "deinterlace.on": "Deinterlace on",
+    "deinterlace.off": "Deinterlace off",
+    "interpolate.on": "Smooth motion on",
+    "interpolate.off": "Smooth motion off",
+    "power-saver.on": "Power saving on",
+    "power-saver.off": "Power saving off",
+    "speed": "Speed {rate}×",
+    "convert.verified": "Conversion verified",
+    "convert.damaged": "Conversion may be damaged: {problems}",
+    "convert.skipped": "Skipped {count} damaged packets",
+    "convert.failed": "Could not convert: {error}",
+    "subs.none": "No subtitles",
+    "subs.off": "Subtitles off",
+    "search.placeholder": "Search subtitles",
+    "search.none": "No matches",
+    "buffering": "Buffering…",
+    "error.nothing-open": "Nothing is open",
+    "error.nothing-playable": "Nothing playable to open",
+};
+
+const de: Strings = {
+    "drop-here": "Videodatei hier ablegen",
+    "title.playing": "läuft",
+    "title.paused": "pausiert",
+    "audio.none": "Keine weiteren Tonspuren",
+    "audio.track": "Ton {n}/{count} {name}",
+    "playlist.end": "Ende der Wiedergabeliste",
+    "playlist.start": "Anfang der Wiedergabeliste",
+    "recent": "Zuletzt geöffnet",
+    "window.drop": "Zum Vergleichen eine Datei hierher ziehen",
+    "window.busy": "Die vorige Datei wird noch konvertiert",
+    "window.no-urls": "Nur lokale Dateien lassen sich vergleichen",
+    "window.locked": "Diese Datei ist gesperrt",
+    "intro.skip": "Intro überspringen",
+    "motion.scanning": "Suche nach Bewegung… {percent}%",
+    "motion.found": "{count} Bewegungen, Umschalt+Bild↓/Bild↑ zum Springen",
+    "saved": "Gespeichert: {path}",
+    "export.markers": "Kapitel exportieren als",
+    "format.ffmetadata": "FFmpeg-Metadaten",
+    "format.youtube": "YouTube-Beschreibung",
+    "format.cue": "Cue-Sheet",
+    "export.transcript": "Transkript exportieren als",
+    "format.text": "Reiner Text",
+    "format.markdown": "Markdown",
+    "frame.copy": "Bild kopieren",
+    "frame.copied": "Bild kopiert",
+    "resume.offer": "Eingabe drücken, um bei {time} fortzusetzen",
+    "session.offer": "Eingabe drücken, um die letzte Sitzung wiederherzustellen ({name}, {count} Dateien)",
+    "session.none": "Keine vorherige Sitzung vorhanden",
+    "loop.a": "Schleife A gesetzt",
+    "loop.b": "Schleife B gesetzt",
+    "loop.cleared": "Schleife entfernt",
+    "loop.stop": "Am Ende anhalten",
+    "loop.file": "Datei wiederholen",
+    "loop.playlist": "Wiedergabeliste wiederholen",
+    "sequence.no-range": "Zuerst mit L Schleife A und B setzen, um die Bilder zu markieren",
+    "sequence.exporting": "Bilder werden exportiert… {percent}%",
+    "sequence.empty": "Keine Bilder zwischen A und B",
+    "clip.exporting": "Clip wird erstellt… {percent}%",
+    "clip.empty": "Keine Bilder zwischen A und B",
+    "aspect": "Seitenverhältnis: {mode}",
+    "integer-scale.on": "Ganzzahlige Skalierung an",
+    "integer-scale.off": "Ganzzahlige Skalierung aus",
+    "volume": "Lautstärke: {percent}%",
+    "lock.pin": "PIN",
+    "lock.locked": "Gesperrt, zum Weiterschauen PIN eingeben",
+    "lock.unlocked": "Entsperrt",
+    "lock.wrong-pin": "Falsche PIN",
+    "lock.short-pin": "Die PIN braucht mindestens 4 Zeichen",
+    "lock.no-file": "Nichts geöffnet zum Sperren",
+    "lock.file-locked": "Datei gesperrt",
+    "lock.segment-locked": "{start}–{end} gesperrt",
+    "lock.cleared": "Sperren aufgehoben",
+    "gamepad.connected": "Controller verbunden",
+    "gamepad.disconnected": "Controller getrennt",
+    "stats.video": "{width}×{height} mit {fps} fps",
+    "stats.frames": "{dropped} von {total} Bildern verworfen",
+    "stats.late": "Dekodieren kommt nicht hinterher, bisher {dropped} Bilder verworfen",
+    "stats.awake": "Bildschirm bleibt an",
+    "stats.sleep": "Bildschirm darf ausgehen",
+    "eq.brightness": "Helligkeit: {value}",
+    "eq.contrast": "Kontrast: {value}",
+    "eq.saturation": "Sättigung: {value}",
+    "eq.reset": "Bildeinstellungen zurückgesetzt",
+    "link.copied": "Link kopiert",
+    "annotate.on": "Zeichenmodus: ziehen zum Zeichnen, T wechselt das Werkzeug, S speichert",
+    "annotate.off": "Zeichenmodus aus",
+    "annotate.tool.rect": "Rechteck",
+    "annotate.tool.arrow": "Pfeil",
+    "annotate.tool.freehand": "Freihand",
+    "ambient.on": "Umgebungsfarben an",
+    "ambient.off": "Umgebungsfarben aus",
+    "deinterlace.on": "Deinterlacing an",
+    "deinterlace.off": "Deinterlacing aus",
+    "interpolate.on": "Bewegungsglättung an",
+    "interpolate.off": "Bewegungsglättung aus",
+    "power-saver.on": "Energiesparen an",
+    "power-saver.off": "Energiesparen aus",
+    "speed": "Geschwindigkeit {rate}×",
+    "convert.verified": "Konvertierung geprüft",
+    "convert.damaged": "Konvertierung möglicherweise beschädigt: {problems}",
+    "convert.skipped": "{count} beschädigte Pakete übersprungen",
+    "convert.failed": "Konvertierung fehlgeschlagen: {error}",
+    "subs.none": "Keine Untertitel",
+    "subs.off": "Untertitel aus",
+    "search.placeholder": "Untertitel durchsuchen",
+    "search.none": "Keine Treffer",
+    "buffering": "Puffern…",
+    "error.nothing-open": "Es ist nichts geöffnet",
+    "error.nothing-playable": "Nichts Abspielbares zum Öffnen",
+};
+
+const LANGUAGES: Record<string, Strings> = { en, de };
+
+let current: Strings = en;
+
+/**
+ * Picks the strings for a BCP 47 tag like `de-AT`, falling back to the bare
+ * language and then to English.
+ */
+export function setLanguage(tag: string) {
+    const lower = tag.toLowerCase();
+    current = LANGUAGES[lower] ?? LANGUAGES[lower.split("-")[0]] ?? en;
+}
+
+/**
+ * Looks up `key` and fills in its `{name}` placeholders. Unknown keys are
+ * returned as they are, so messages that come from ffmpeg or the OS pass
+ * through untouched.
+ */
+export function t(key: string, vars: Record<string, string | number> = {}): string {
+    const text = current[key] ?? en[key] ?? key;
+    return text.replace(/\{(\w+)\}/g, (whole, name) => name in vars ? String(vars[name]) : whole);
+}
+
+setLanguage(navigator.language);
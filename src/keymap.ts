@@ -0,0 +1,169 @@
+/** Everything that can be bound to a key. */
+export type Action =
+    | "play-pause"
+    | "resume"
+    | "step-forward"
+    | "step-back"
+    | "seek-forward"
+    | "seek-back"
+    | "jump-forward"
+    | "jump-back"
+    | "fullscreen"
+    | "exit-fullscreen"
+    | "pip"
+    | "audio-track"
+    | "subtitles"
+    | "second-subtitles"
+    | "search-subtitles"
+    | "next-chapter"
+    | "previous-chapter"
+    | "detect-motion"
+    | "next-motion"
+    | "previous-motion"
+    | "skip-intro"
+    | "playlist-next"
+    | "playlist-previous"
+    | "recent"
+    | "new-window"
+    | "bookmark"
+    | "export-markers"
+    | "export-transcript"
+    | "ab-loop"
+    | "loop-mode"
+    | "screenshot"
+    | "copy-frame"
+    | "export-frames"
+    | "export-clip"
+    | "lock-file"
+    | "lock-segment"
+    | "telemetry"
+    | "stats"
+    | "annotate"
+    | "annotation-tool"
+    | "annotation-undo"
+    | "copy-link"
+    | "rotate"
+    | "flip-horizontal"
+    | "flip-vertical"
+    | "aspect"
+    | "integer-scale"
+    | "ambient"
+    | "deinterlace"
+    | "interpolate"
+    | "power-saver"
+    | "contrast-down"
+    | "contrast-up"
+    | "brightness-down"
+    | "brightness-up"
+    | "saturation-down"
+    | "saturation-up"
+    | "equalizer-reset"
+    | "speed-up"
+    | "speed-down"
+    | "speed-reset"
+    | "volume-up"
+    | "volume-down";
+
+/**
+ * Keys are `KeyboardEvent.code` names, optionally prefixed with `Ctrl+`,
+ * `Alt+`, `Shift+` and `Meta+` in that order, e.g. `Ctrl+KeyC`.
+ */
+export type Keymap = Record<Action, string[]>;
+
+export const DEFAULT_KEYMAP: Keymap = {
+    "play-pause": ["Space"],
+    "resume": ["Enter"],
+    "step-forward": ["ArrowRight"],
+    "step-back": ["ArrowLeft"],
+    "seek-forward": ["Shift+ArrowRight"],
+    "seek-back": ["Shift+ArrowLeft"],
+    "jump-forward": ["Ctrl+ArrowRight"],
+    "jump-back": ["Ctrl+ArrowLeft"],
+    "fullscreen": ["KeyF"],
+    "exit-fullscreen": ["Escape"],
+    "pip": ["Shift+KeyP"],
+    "audio-track": ["KeyA"],
+    "subtitles": ["KeyJ"],
+    "second-subtitles": ["Shift+KeyJ"],
+    "search-subtitles": ["Ctrl+KeyF"],
+    "next-chapter": ["PageDown"],
+    "previous-chapter": ["PageUp"],
+    "detect-motion": ["KeyG"],
+    "next-motion": ["Shift+PageDown"],
+    "previous-motion": ["Shift+PageUp"],
+    "skip-intro": ["KeyI"],
+    "playlist-next": ["Period"],
+    "playlist-previous": ["Comma"],
+    "recent": ["KeyO"],
+    "new-window": ["Ctrl+KeyN"],
+    "bookmark": ["KeyM"],
+    "export-markers": ["KeyE"],
+    "export-transcript": ["Shift+KeyE"],
+    "ab-loop": ["KeyL"],
+    "loop-mode": ["Shift+KeyL"],
+    "screenshot": ["KeyS"],
+    "copy-frame": ["Ctrl+Shift+KeyC"],
+    "export-frames": ["Shift+KeyS"],
+    "export-clip": ["Ctrl+KeyS"],
+    "lock-file": ["Ctrl+KeyL"],
+    "lock-segment": ["Alt+KeyL"],
+    "telemetry": ["Ctrl+KeyI"],
+    "stats": ["KeyU"],
+    "annotate": ["KeyN"],
+    "annotation-tool": ["KeyT"],
+    "annotation-undo": ["Ctrl+KeyZ"],
+    "copy-link": ["Ctrl+KeyC"],
+    "rotate": ["KeyR"],
+    "flip-horizontal": ["KeyH"],
+    "flip-vertical": ["KeyV"],
+    "aspect": ["KeyX"],
+    "integer-scale": ["Shift+KeyX"],
+    "ambient": ["KeyB"],
+    "deinterlace": ["KeyD"],
+    "interpolate": ["Shift+KeyD"],
+    "power-saver": ["KeyP"],
+    "contrast-down": ["Digit1"],
+    "contrast-up": ["Digit2"],
+    "brightness-down": ["Digit3"],
+    "brightness-up": ["Digit4"],
+    "saturation-down": ["Digit5"],
+    "saturation-up": ["Digit6"],
+    "equalizer-reset": ["Digit0"],
+    "speed-up": ["BracketRight"],
+    "speed-down": ["BracketLeft"],
+    "speed-reset": ["Backspace"],
+    "volume-up": ["ArrowUp"],
+    "volume-down": ["ArrowDown"],
+};
+
+/**
+ * Applies the `[keys]` table from the config on top of the defaults. A
+ * binding replaces all default keys of its action.
+ */
+export function mergeKeymap(overrides: Record<string, string | string[]>): Keymap {
+    const keymap = { ...DEFAULT_KEYMAP };
+    for (const [action, keys] of Object.entries(overrides)) {
+        if (!(action in keymap)) {
+            console.warn(`unknown action in keymap: ${action}`);
+            continue;
+        }
+        keymap[action as Action] = typeof keys === "string" ? [keys] : keys;
+    }
+    return keymap;
+}
+
+/** `ev` written the way bindings are, e.g. `Ctrl+Shift+KeyS`. */
+export function keyName(ev: KeyboardEvent): string {
+    const modifiers = [
+        ev.ctrlKey && "Ctrl",
+        ev.altKey && "Alt",
+        ev.shiftKey && "Shift",
+        ev.metaKey && "Meta",
+    ].filter(Boolean);
+    return [...modifiers, ev.code].join("+");
+}
+
+export function actionFor(keymap: Keymap, ev: KeyboardEvent): Action | undefined {
+    const name = keyName(ev);
+    return (Object.keys(keymap) as Action[]).find(action => keymap[action].includes(name));
+}
@@ -0,0 +1,20 @@
+export type Segment = {
+    start: number;
+    end: number;
+};
+
+/** What `get_locks` returns; see `lock::FileLock` in the backend. */
+export type FileLock = {
+    whole: boolean;
+    segments: Segment[];
+};
+
+export const NO_LOCK: FileLock = { whole: false, segments: [] };
+
+/** The locked stretch `time` falls into, the whole file counting as one. */
+export function lockedAt(lock: FileLock, time: number): Segment | undefined {
+    if (lock.whole) {
+        return { start: 0, end: Infinity };
+    }
+    return lock.segments.find(s => time >= s.start && time < s.end);
+}
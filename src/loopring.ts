@@ -0,0 +1,103 @@
+/** Memory a ring may take, counting frames as uncompressed RGBA. */
+const MAX_BYTES = 1 << 30;
+/** How close to the loop ends the captured frames have to reach, in seconds. */
+const START_SLACK = 0.1;
+
+/** `bitmap` is null while it is still being copied. */
+type Frame = { time: number; bitmap: ImageBitmap | null };
+
+/**
+ * Keeps every frame of a loop region once it has played through, so the
+ * loop can be shown from memory. The video still seeks back at the wrap
+ * point, but the ring already has the frame for the time it reports, so
+ * nothing stalls or flashes there.
+ */
+export class LoopRing {
+    private frames: Frame[] = [];
+    private complete = false;
+
+    private constructor(
+        readonly start: number,
+        readonly end: number,
+        private width: number,
+        private height: number,
+        /** Longest gap between frames that isn't a skip, in seconds. */
+        private maxGap: number,
+    ) { }
+
+    /**
+     * A ring for `start` to `end` of `video`, captured at no more than
+     * `maxWidth` pixels across. Null if it would take too much memory.
+     */
+    static create(video: HTMLVideoElement, start: number, end: number, fps: number, maxWidth: number): LoopRing | null {
+        if (!video.videoWidth || !fps || end <= start) {
+            return null;
+        }
+        const width = Math.min(video.videoWidth, Math.round(maxWidth));
+        const height = Math.round(video.videoHeight * width / video.videoWidth);
+        const bytes = (end - start) * fps * width * height * 4;
+        return bytes <= MAX_BYTES ? new LoopRing(start, end, width, height, 2.5 / fps) : null;
+    }
+
+    /** Whether the whole region is in memory. */
+    get ready(): boolean {
+        return this.complete;
+    }
+
+    /** Call with every presented frame until the ring is ready. */
+    capture(video: HTMLVideoElement, time: number) {
+        if (this.complete || time < this.start || time >= this.end) {
+            return;
+        }
+
+        const last = this.frames[this.frames.length - 1];
+        if (last && time - last.time > this.maxGap) {
+            // seeked ahead, so the frames in between are missing
+            this.dispose();
+        } else if (last && time <= last.time) {
+            // wrapped around; complete if the pass went from start to end
+            if (this.frames[0].time - this.start <= START_SLACK && this.end - last.time <= START_SLACK) {
+                this.complete = true;
+            } else {
+                this.dispose();
+            }
+            return;
+        }
+
+        const frame: Frame = { time, bitmap: null };
+        this.frames.push(frame);
+        createImageBitmap(video, { resizeWidth: this.width, resizeHeight: this.height })
+            .then(bitmap => {
+                // thrown away meanwhile
+                if (this.frames.includes(frame)) {
+                    frame.bitmap = bitmap;
+                } else {
+                    bitmap.close();
+                }
+            })
+            .catch(() => this.dispose());
+    }
+
+    /** The frame on screen at `time`. */
+    frameAt(time: number): ImageBitmap | null {
+        let low = 0;
+        let high = this.frames.length - 1;
+        while (low < high) {
+            const mid = Math.ceil((low + high) / 2);
+            if (this.frames[mid].time <= time) {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        return this.frames[low]?.bitmap ?? null;
+    }
+
+    dispose() {
+        for (const frame of this.frames) {
+            frame.bitmap?.close();
+        }
+        this.frames = [];
+        this.complete = false;
+    }
+}
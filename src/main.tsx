@@ -1,9 +1,13 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import Compare from "./Compare";
+
+// extra windows for comparing files are opened with `?window=<n>`
+const compareWindow = new URLSearchParams(location.search).get("window");
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
     <React.StrictMode>
-        <App />
+        {compareWindow === null ? <App /> : <Compare n={Number(compareWindow)} />}
     </React.StrictMode>,
 );
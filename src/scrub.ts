@@ -0,0 +1,38 @@
+/** Seeks within this long, in ms, are looked at together. */
+const WINDOW_MS = 1000;
+/** That many seeks within the window means the user is scrubbing. */
+const SCRUB_SEEKS = 3;
+
+/** Tells scrubbing through a video apart from the odd jump. */
+export class ScrubTracker {
+    private seeks: number[] = [];
+    /** Where the last fast seek was headed, until it is made exact. */
+    private pending: number | null = null;
+
+    /**
+     * Seeks `video` to `time`. While scrubbing, this only goes to the nearest
+     * keyframe where the webview supports that, so nothing past it has to be
+     * decoded before the picture updates.
+     */
+    seek(video: HTMLVideoElement, time: number) {
+        const now = performance.now();
+        this.seeks = [...this.seeks.filter(t => now - t < WINDOW_MS), now];
+
+        if (this.seeks.length >= SCRUB_SEEKS && "fastSeek" in video) {
+            video.fastSeek(time);
+            this.pending = time;
+        } else {
+            video.currentTime = time;
+            this.pending = null;
+        }
+    }
+
+    /** Lands exactly where the last fast seek was going, once scrubbing stops. */
+    settle(video: HTMLVideoElement) {
+        if (this.pending !== null) {
+            video.currentTime = this.pending;
+            this.pending = null;
+        }
+        this.seeks = [];
+    }
+}
@@ -0,0 +1,21 @@
+/** Playback rates the speed keys step through. */
+export const SPEEDS = [0.25, 0.5, 0.75, 1, 1.25, 1.5, 1.75, 2, 3, 4];
+
+/** The rate in `SPEEDS` after `rate`, or before it if `direction` is -1. */
+export function stepSpeed(rate: number, direction: 1 | -1): number {
+    const next = direction > 0
+        ? SPEEDS.find(s => s > rate + 1e-6)
+        : [...SPEEDS].reverse().find(s => s < rate - 1e-6);
+    return next ?? rate;
+}
+
+/**
+ * Changes the rate with pitch correction on, so voices keep their pitch
+ * instead of chipmunking. The webview time-stretches the audio itself.
+ */
+export function setSpeed(video: HTMLVideoElement, rate: number) {
+    video.preservesPitch = true;
+    // WebKitGTK and older WKWebView only know the prefixed name
+    (video as any).webkitPreservesPitch = true;
+    video.playbackRate = rate;
+}
@@ -0,0 +1,44 @@
+export type Cue = {
+    start: number;
+    end: number;
+    text: string;
+    /** Bitmap subtitles (PGS, VobSub) come as pictures with empty `text`. */
+    image?: CueImage;
+};
+
+/** Position and size are fractions of the video frame. */
+export type CueImage = {
+    src: string;
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+};
+
+/** Returns every cue showing at `time`, oldest first. */
+export function activeCues(cues: Cue[], time: number): Cue[] {
+    return cues.filter(c => c.start <= time && time < c.end);
+}
+
+/** Cues with their text folded for searching, see `indexCues`. */
+export type SearchIndex = { cue: Cue; text: string }[];
+
+/** Ignores case and accents, so "uber" finds "Über". */
+function fold(text: string): string {
+    return text.normalize("NFD").replace(/\p{Diacritic}/gu, "").toLowerCase();
+}
+
+export function indexCues(cues: Cue[]): SearchIndex {
+    return [...cues]
+        .sort((a, b) => a.start - b.start)
+        .map(cue => ({ cue, text: fold(cue.text) }));
+}
+
+/** Cues containing `query`, in playback order. */
+export function searchCues(index: SearchIndex, query: string): Cue[] {
+    const needle = fold(query.trim());
+    if (!needle) {
+        return [];
+    }
+    return index.filter(entry => entry.text.includes(needle)).map(entry => entry.cue);
+}
@@ -0,0 +1,131 @@
+/** What `export_telemetry` takes; see `telemetry::Playback` in the backend. */
+export type Playback = {
+    watched: number;
+    presented_frames: number;
+    dropped_frames: number;
+    fps: { p1: number; p5: number; p50: number; p95: number };
+    hw_accel: boolean | null;
+    user_agent: string;
+};
+
+/** Intervals kept; about ten minutes at 30 fps. */
+const MAX_SAMPLES = 18000;
+/** Longer gaps between frames are pauses or seeks, not stutters. */
+const MAX_GAP = 1000;
+
+/** Collects the time between presented frames over a playback session. */
+export class FrameStats {
+    private intervals: number[] = [];
+    private next = 0;
+    private last: number | null = null;
+    private watched = 0;
+
+    record(metadata: VideoFrameCallbackMetadata) {
+        const now = metadata.presentationTime;
+        if (this.last !== null && now > this.last && now - this.last < MAX_GAP) {
+            const interval = now - this.last;
+            // overwrite the oldest once full, so long sessions stay bounded
+            if (this.intervals.length < MAX_SAMPLES) {
+                this.intervals.push(interval);
+            } else {
+                this.intervals[this.next] = interval;
+                this.next = (this.next + 1) % MAX_SAMPLES;
+            }
+            this.watched += interval / 1000;
+        }
+        this.last = now;
+    }
+
+    /** Frames per second at percentile `p` of the recorded intervals. */
+    private fps(sorted: number[], p: number): number {
+        if (!sorted.length) {
+            return 0;
+        }
+        // long intervals are slow frames, so low fps percentiles come from the top
+        const index = Math.min(Math.floor((1 - p / 100) * sorted.length), sorted.length - 1);
+        return 1000 / sorted[index];
+    }
+
+    summary(video: HTMLVideoElement, hwAccel: boolean | null): Playback {
+        const sorted = [...this.intervals].sort((a, b) => a - b);
+        const quality = video.getVideoPlaybackQuality();
+        return {
+            watched: this.watched,
+            presented_frames: quality.totalVideoFrames,
+            dropped_frames: quality.droppedVideoFrames,
+            fps: {
+                p1: this.fps(sorted, 1),
+                p5: this.fps(sorted, 5),
+                p50: this.fps(sorted, 50),
+                p95: this.fps(sorted, 95),
+            },
+            hw_accel: hwAccel,
+            user_agent: navigator.userAgent,
+        };
+    }
+}
+
+/** Share of dropped frames that counts as not keeping up. */
+const LATE_RATIO = 0.2;
+/** Frames a stretch has to span before it is judged. */
+const LATE_WINDOW = 150;
+
+/**
+ * Notices when decoding can't keep up. The webview already drops late
+ * frames to stay in time with the audio, so this only tells the user when it
+ * drops so many that playback turns choppy, once per file.
+ */
+export class LateFrames {
+    private dropped = 0;
+    private total = 0;
+    private reported = false;
+
+    /** Whether playback just fell behind; called regularly while playing. */
+    check(video: HTMLVideoElement): boolean {
+        const quality = video.getVideoPlaybackQuality();
+        const total = quality.totalVideoFrames - this.total;
+        if (total < LATE_WINDOW) {
+            return false;
+        }
+        const dropped = quality.droppedVideoFrames - this.dropped;
+        this.total = quality.totalVideoFrames;
+        this.dropped = quality.droppedVideoFrames;
+        if (this.reported || dropped < total * LATE_RATIO) {
+            return false;
+        }
+        this.reported = true;
+        return true;
+    }
+}
+
+/**
+ * Whether the webview says it decodes `codec` power efficiently, which in
+ * practice means in hardware. Null when there's no codec name to ask about
+ * or the webview can't answer.
+ */
+export async function hardwareDecoding(
+    codec: string | null,
+    width: number,
+    height: number,
+    fps: number,
+): Promise<boolean | null> {
+    if (!codec || !navigator.mediaCapabilities) {
+        return null;
+    }
+    try {
+        const info = await navigator.mediaCapabilities.decodingInfo({
+            type: "file",
+            video: {
+                contentType: `video/mp4; codecs="${codec}"`,
+                width,
+                height,
+                // the answer hardly depends on it, but it is required
+                bitrate: 5_000_000,
+                framerate: fps,
+            },
+        });
+        return info.powerEfficient;
+    } catch {
+        return null;
+    }
+}
@@ -0,0 +1,13 @@
+/**
+ * `m:ss`, or `h:mm:ss` when `hours` is set, so both sides of a
+ * "position / duration" readout can share one layout.
+ */
+export function formatClock(seconds: number, hours: boolean = seconds >= 3600): string {
+    const total = Number.isFinite(seconds) ? Math.max(Math.floor(seconds), 0) : 0;
+    const s = String(total % 60).padStart(2, "0");
+    const m = Math.floor(total / 60) % 60;
+    if (!hours) {
+        return `${Math.floor(total / 60)}:${s}`;
+    }
+    return `${Math.floor(total / 3600)}:${String(m).padStart(2, "0")}:${s}`;
+}
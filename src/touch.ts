@@ -0,0 +1,112 @@
+import { Action } from "./keymap";
+
+/** A finger has to move this far, in px, before it counts as a swipe. */
+const SLOP = 20;
+/** A tap is shorter than this, in ms. */
+const TAP_TIME = 300;
+/** Each this many px of a horizontal swipe seek once. */
+const SEEK_STEP = 60;
+/** Each this many px of a vertical swipe change volume or brightness once. */
+const LEVEL_STEP = 30;
+
+type Swipe = {
+    pointer: number;
+    x: number;
+    y: number;
+    time: number;
+    /** Decided once the finger leaves the slop. */
+    axis: "horizontal" | "vertical" | null;
+    /** Steps already fired, so the count follows the finger back as well. */
+    steps: number;
+};
+
+/**
+ * Turns touch gestures on `element` into player actions: a tap plays or
+ * pauses, a horizontal swipe seeks, and a vertical swipe changes brightness
+ * on the left half and volume on the right. Only the first finger counts.
+ */
+export class TouchGestures {
+    private swipe: Swipe | null = null;
+
+    constructor(private element: HTMLElement, private onAction: (action: Action) => void) {
+        // the webview would scroll or zoom instead
+        element.style.touchAction = "none";
+        element.addEventListener("pointerdown", this.down);
+        element.addEventListener("pointermove", this.move);
+        element.addEventListener("pointerup", this.up);
+        element.addEventListener("pointercancel", this.cancel);
+    }
+
+    dispose() {
+        this.element.style.touchAction = "";
+        this.element.removeEventListener("pointerdown", this.down);
+        this.element.removeEventListener("pointermove", this.move);
+        this.element.removeEventListener("pointerup", this.up);
+        this.element.removeEventListener("pointercancel", this.cancel);
+    }
+
+    private down = (ev: PointerEvent) => {
+        if (ev.pointerType !== "touch" || this.swipe) {
+            return;
+        }
+        // buttons, lists and the drawing layer on top handle their own touches
+        const target = ev.target;
+        if (target !== this.element && !(target instanceof HTMLVideoElement) && !(target instanceof HTMLCanvasElement)) {
+            return;
+        }
+        this.swipe = { pointer: ev.pointerId, x: ev.clientX, y: ev.clientY, time: ev.timeStamp, axis: null, steps: 0 };
+    };
+
+    private move = (ev: PointerEvent) => {
+        const swipe = this.swipe;
+        if (!swipe || ev.pointerId !== swipe.pointer) {
+            return;
+        }
+        const dx = ev.clientX - swipe.x;
+        const dy = ev.clientY - swipe.y;
+        if (swipe.axis === null) {
+            if (Math.hypot(dx, dy) < SLOP) {
+                return;
+            }
+            swipe.axis = Math.abs(dx) > Math.abs(dy) ? "horizontal" : "vertical";
+        }
+
+        let steps: number;
+        let up: Action;
+        let down: Action;
+        if (swipe.axis === "horizontal") {
+            steps = Math.trunc(dx / SEEK_STEP);
+            [up, down] = ["seek-forward", "seek-back"];
+        } else {
+            // screen y grows downwards, levels go up with the finger
+            steps = Math.trunc(-dy / LEVEL_STEP);
+            const rect = this.element.getBoundingClientRect();
+            [up, down] = swipe.x < rect.left + rect.width / 2
+                ? ["brightness-up", "brightness-down"]
+                : ["volume-up", "volume-down"];
+        }
+        for (; swipe.steps < steps; swipe.steps++) {
+            this.onAction(up);
+        }
+        for (; swipe.steps > steps; swipe.steps--) {
+            this.onAction(down);
+        }
+    };
+
+    private up = (ev: PointerEvent) => {
+        const swipe = this.swipe;
+        if (!swipe || ev.pointerId !== swipe.pointer) {
+            return;
+        }
+        this.swipe = null;
+        if (swipe.axis === null && ev.timeStamp - swipe.time < TAP_TIME) {
+            this.onAction("play-pause");
+        }
+    };
+
+    private cancel = (ev: PointerEvent) => {
+        if (ev.pointerId === this.swipe?.pointer) {
+            this.swipe = null;
+        }
+    };
+}
@@ -0,0 +1,130 @@
+/**
+ * How the video sits inside its box. Pan is the offset of the video's centre
+ * from the box's centre, in fractions of the box size. Flips are applied on
+ * screen, after rotating.
+ */
+export type View = {
+    zoom: number;
+    panX: number;
+    panY: number;
+    /** Clockwise, in degrees; always a multiple of 90. */
+    rotation: number;
+    flipX: boolean;
+    flipY: boolean;
+    aspect: AspectMode;
+    /** Only whole multiples of the video size, for crisp pixels. */
+    integerScale: boolean;
+};
+
+/**
+ * `fit` letterboxes, `fill` crops to cover the box, `stretch` ignores the
+ * video's shape, and the ratios force that display aspect for sources whose
+ * own is missing or wrong.
+ */
+export type AspectMode = "fit" | "fill" | "stretch" | "4:3" | "16:9";
+
+export const ASPECT_MODES: AspectMode[] = ["fit", "fill", "stretch", "4:3", "16:9"];
+
+export type Size = { width: number; height: number };
+
+export const DEFAULT_VIEW: View = {
+    zoom: 1,
+    panX: 0,
+    panY: 0,
+    rotation: 0,
+    flipX: false,
+    flipY: false,
+    aspect: "fit",
+    integerScale: false,
+};
+
+const MAX_ZOOM = 8;
+
+/** Keeps the zoomed video covering the whole box. */
+function clampPan(view: View): View {
+    const limit = (view.zoom - 1) / 2;
+    return {
+        ...view,
+        panX: Math.min(Math.max(view.panX, -limit), limit),
+        panY: Math.min(Math.max(view.panY, -limit), limit),
+    };
+}
+
+/**
+ * Zooms by `factor` while keeping the point under the cursor in place. `x`
+ * and `y` are the cursor position relative to the box centre, in fractions of
+ * the box size.
+ */
+export function zoomAt(view: View, factor: number, x: number, y: number): View {
+    const zoom = Math.min(Math.max(view.zoom * factor, 1), MAX_ZOOM);
+    const scale = zoom / view.zoom;
+    return clampPan({
+        ...view,
+        zoom,
+        panX: x - (x - view.panX) * scale,
+        panY: y - (y - view.panY) * scale,
+    });
+}
+
+/** Moves the video by `dx`/`dy` fractions of the box size. */
+export function panBy(view: View, dx: number, dy: number): View {
+    return clampPan({ ...view, panX: view.panX + dx, panY: view.panY + dy });
+}
+
+export function rotate(view: View): View {
+    return { ...view, rotation: (view.rotation + 90) % 360 };
+}
+
+export function cycleAspect(view: View): View {
+    const next = (ASPECT_MODES.indexOf(view.aspect) + 1) % ASPECT_MODES.length;
+    return { ...view, aspect: ASPECT_MODES[next] };
+}
+
+/**
+ * Size the picture is drawn at before rotating. The video element always
+ * spans the whole box and stretches its content (`object-fit: fill`), so this
+ * is what the transform has to scale it to.
+ */
+function displaySize(view: View, box: Size, video: Size): Size {
+    // a quarter turn swaps which side of the picture meets which side of the box
+    const turned = view.rotation % 180 !== 0;
+    const width = turned ? box.height : box.width;
+    const height = turned ? box.width : box.height;
+
+    let ratio: number;
+    switch (view.aspect) {
+        case "stretch":
+            return { width, height };
+        case "4:3":
+            ratio = 4 / 3;
+            break;
+        case "16:9":
+            ratio = 16 / 9;
+            break;
+        default:
+            ratio = video.width && video.height ? video.width / video.height : width / height;
+    }
+
+    // in device pixels, since that is where the pixel grid is
+    if (view.integerScale && view.aspect === "fit" && video.width && video.height) {
+        const dpr = window.devicePixelRatio || 1;
+        const n = Math.max(Math.floor(Math.min(width * dpr / video.width, height * dpr / video.height)), 1);
+        return { width: video.width * n / dpr, height: video.height * n / dpr };
+    }
+
+    const scale = view.aspect === "fill"
+        ? Math.max(width / ratio, height)
+        : Math.min(width / ratio, height);
+    return { width: scale * ratio, height: scale };
+}
+
+export function videoTransform(view: View, box: Size, video: Size): string {
+    if (!box.width || !box.height) {
+        return "none";
+    }
+    const size = displaySize(view, box, video);
+    return `translate(${view.panX * 100}%, ${view.panY * 100}%) `
+        + `scale(${view.flipX ? -1 : 1}, ${view.flipY ? -1 : 1}) `
+        + `rotate(${view.rotation}deg) scale(${view.zoom}) `
+        + `scale(${size.width / box.width}, ${size.height / box.height})`;
+}
@@ -0,0 +1,99 @@
+/** Bars in the spectrum. */
+const BARS = 64;
+/** Lowest and highest frequency shown, in Hz, spread logarithmically. */
+const MIN_FREQUENCY = 40;
+const MAX_FREQUENCY = 16000;
+const COLOR = "#4caf50";
+
+/**
+ * An element can only ever be connected to Web Audio once, after which its
+ * sound goes through the graph, so the analyser is kept for the page's life.
+ */
+const analysers = new WeakMap<HTMLMediaElement, { context: AudioContext; analyser: AnalyserNode }>();
+
+function analyserFor(video: HTMLMediaElement) {
+    let entry = analysers.get(video);
+    if (!entry) {
+        const context = new AudioContext();
+        const analyser = context.createAnalyser();
+        analyser.fftSize = 2048;
+        analyser.smoothingTimeConstant = 0.8;
+        context.createMediaElementSource(video).connect(analyser);
+        analyser.connect(context.destination);
+        entry = { context, analyser };
+        analysers.set(video, entry);
+    }
+    return entry;
+}
+
+/**
+ * Draws a spectrum with the waveform on top into `canvas`, for files
+ * without a picture. Returns a function that stops drawing.
+ */
+export function startVisualizer(video: HTMLVideoElement, canvas: HTMLCanvasElement): () => void {
+    const { context, analyser } = analyserFor(video);
+    // created before any user gesture, so it may start out suspended
+    const resume = () => context.resume();
+    video.addEventListener("play", resume);
+    resume();
+
+    const spectrum = new Uint8Array(analyser.frequencyBinCount);
+    const waveform = new Uint8Array(analyser.fftSize);
+    const binWidth = context.sampleRate / analyser.fftSize;
+    let frame = 0;
+
+    const draw = () => {
+        frame = requestAnimationFrame(draw);
+        const ctx = canvas.getContext("2d");
+        if (!ctx) {
+            return;
+        }
+        // follow the element's size, in device pixels so it stays sharp
+        const width = Math.round(canvas.clientWidth * window.devicePixelRatio);
+        const height = Math.round(canvas.clientHeight * window.devicePixelRatio);
+        if (canvas.width !== width || canvas.height !== height) {
+            canvas.width = width;
+            canvas.height = height;
+        }
+        ctx.clearRect(0, 0, width, height);
+
+        analyser.getByteFrequencyData(spectrum);
+        ctx.fillStyle = COLOR;
+        ctx.globalAlpha = 0.6;
+        const barWidth = width / BARS;
+        for (let i = 0; i < BARS; i++) {
+            const from = MIN_FREQUENCY * Math.pow(MAX_FREQUENCY / MIN_FREQUENCY, i / BARS);
+            const to = MIN_FREQUENCY * Math.pow(MAX_FREQUENCY / MIN_FREQUENCY, (i + 1) / BARS);
+            const start = Math.floor(from / binWidth);
+            const end = Math.max(Math.ceil(to / binWidth), start + 1);
+            let peak = 0;
+            for (let bin = start; bin < end && bin < spectrum.length; bin++) {
+                peak = Math.max(peak, spectrum[bin]);
+            }
+            const barHeight = (peak / 255) * height * 0.8;
+            ctx.fillRect(i * barWidth + 1, height - barHeight, barWidth - 2, barHeight);
+        }
+
+        analyser.getByteTimeDomainData(waveform);
+        ctx.globalAlpha = 1;
+        ctx.strokeStyle = "white";
+        ctx.lineWidth = window.devicePixelRatio;
+        ctx.beginPath();
+        for (let i = 0; i < waveform.length; i++) {
+            const x = (i / (waveform.length - 1)) * width;
+            const y = (waveform[i] / 255) * height * 0.5 + height * 0.05;
+            if (i === 0) {
+                ctx.moveTo(x, y);
+            } else {
+                ctx.lineTo(x, y);
+            }
+        }
+        ctx.stroke();
+    };
+    draw();
+
+    return () => {
+        cancelAnimationFrame(frame);
+        video.removeEventListener("play", resume);
+    };
+}
@@ -0,0 +1,52 @@
+/**
+ * Keeps the screen from sleeping while a video plays, through the Screen
+ * Wake Lock API. `onChange` hears whenever the lock is taken or lost.
+ */
+export class WakeLock {
+    private sentinel: WakeLockSentinel | null = null;
+    private wanted = false;
+
+    constructor(private onChange: (active: boolean) => void) {
+        // the webview drops the lock whenever the window is hidden
+        document.addEventListener("visibilitychange", () => {
+            if (this.wanted && document.visibilityState === "visible") {
+                this.acquire();
+            }
+        });
+    }
+
+    get active(): boolean {
+        return this.sentinel !== null;
+    }
+
+    /** Asks for the lock while `wanted`, lets it go otherwise. */
+    set(wanted: boolean) {
+        this.wanted = wanted;
+        if (wanted) {
+            this.acquire();
+        } else {
+            this.sentinel?.release();
+        }
+    }
+
+    private async acquire() {
+        if (this.sentinel || !("wakeLock" in navigator)) {
+            return;
+        }
+        try {
+            const sentinel = await navigator.wakeLock.request("screen");
+            sentinel.addEventListener("release", () => {
+                this.sentinel = null;
+                this.onChange(false);
+            });
+            this.sentinel = sentinel;
+            this.onChange(true);
+            // stopped while the request was pending
+            if (!this.wanted) {
+                sentinel.release();
+            }
+        } catch {
+            this.onChange(false);
+        }
+    }
+}